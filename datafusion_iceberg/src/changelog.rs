@@ -0,0 +1,566 @@
+/*!
+ * Tableprovider and table function to query the changelog of an iceberg table with datafusion.
+*/
+
+use std::{any::Any, fmt, sync::Arc};
+
+use async_trait::async_trait;
+use datafusion::{
+    arrow::datatypes::{DataType, Field, Schema as ArrowSchema, SchemaRef},
+    common::{plan_err, DataFusionError},
+    datasource::{function::TableFunctionImpl, TableProvider},
+    execution::{context::SessionState, TaskContext},
+    logical_expr::TableType,
+    physical_expr::EquivalenceProperties,
+    physical_plan::{
+        stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionMode,
+        ExecutionPlan, Partitioning, PlanProperties, SendableRecordBatchStream,
+    },
+    prelude::Expr,
+    scalar::ScalarValue,
+};
+use futures::{stream, StreamExt};
+use iceberg_rust::{
+    arrow::read::read_changelog,
+    catalog::{identifier::Identifier, tabular::Tabular, Catalog},
+    table::{
+        changelog::{ChangelogTask, CHANGE_ORDINAL, CHANGE_TYPE, COMMIT_SNAPSHOT_ID},
+        incremental::StartSnapshot,
+        Table,
+    },
+};
+use object_store::ObjectStore;
+use tokio::runtime::Handle;
+
+use crate::{error::Error, file_scan::project};
+
+/// Changelog of an iceberg table between two snapshots for datafusion. The start snapshot is excluded from the changelog.
+#[derive(Debug)]
+pub struct DataFusionChangelog {
+    table: Table,
+    snapshot_range: (Option<i64>, Option<i64>),
+    schema: SchemaRef,
+}
+
+impl DataFusionChangelog {
+    /// Create the changelog of the table. The schema contains the columns of the current table schema
+    /// followed by the change type, the change ordinal and the commit snapshot id.
+    pub fn new(table: Table, start: Option<i64>, end: Option<i64>) -> Result<Self, Error> {
+        let table_schema: ArrowSchema = table.current_schema(None)?.fields().try_into()?;
+        let mut fields = table_schema.fields().to_vec();
+        fields.extend([
+            Arc::new(Field::new(CHANGE_TYPE, DataType::Utf8, false)),
+            Arc::new(Field::new(CHANGE_ORDINAL, DataType::Int64, false)),
+            Arc::new(Field::new(COMMIT_SNAPSHOT_ID, DataType::Int64, false)),
+        ]);
+        Ok(DataFusionChangelog {
+            table,
+            snapshot_range: (start, end),
+            schema: Arc::new(ArrowSchema::new(fields)),
+        })
+    }
+}
+
+#[async_trait]
+impl TableProvider for DataFusionChangelog {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+    async fn scan(
+        &self,
+        _session: &SessionState,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>, DataFusionError> {
+        let tasks = self
+            .table
+            .changelog_tasks(
+                self.snapshot_range.0.map(StartSnapshot::Exclusive),
+                self.snapshot_range.1,
+            )
+            .await
+            .map_err(Error::from)?;
+        let schema = match projection {
+            Some(projection) => Arc::new(self.schema.project(projection)?),
+            None => self.schema.clone(),
+        };
+        Ok(Arc::new(ChangelogScanExec::new(
+            tasks,
+            self.table.object_store(),
+            schema,
+        )))
+    }
+}
+
+/// Reads the changed rows of the changelog tasks with the iceberg reader
+#[derive(Debug)]
+struct ChangelogScanExec {
+    tasks: Vec<ChangelogTask>,
+    object_store: Arc<dyn ObjectStore>,
+    schema: SchemaRef,
+    properties: PlanProperties,
+}
+
+impl ChangelogScanExec {
+    fn new(
+        tasks: Vec<ChangelogTask>,
+        object_store: Arc<dyn ObjectStore>,
+        schema: SchemaRef,
+    ) -> Self {
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema.clone()),
+            Partitioning::UnknownPartitioning(1),
+            ExecutionMode::Bounded,
+        );
+        ChangelogScanExec {
+            tasks,
+            object_store,
+            schema,
+            properties,
+        }
+    }
+}
+
+impl DisplayAs for ChangelogScanExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(f, "ChangelogScanExec: tasks={}", self.tasks.len())
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for ChangelogScanExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>, DataFusionError> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream, DataFusionError> {
+        if partition != 0 {
+            return Err(DataFusionError::Internal(format!(
+                "Invalid partition {} for ChangelogScanExec",
+                partition
+            )));
+        }
+        let tasks = self.tasks.clone();
+        let object_store = self.object_store.clone();
+        let schema = self.schema.clone();
+
+        let stream = stream::once(async move { read_changelog(tasks, object_store).await })
+            .flatten()
+            .map(move |batch| project(batch?, &schema));
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            stream,
+        )))
+    }
+}
+
+/// Table function `table_changes('namespace.table', start, end)` that returns the changelog of a table.
+/// The start snapshot is excluded from the changelog, both snapshot ids are optional and can be NULL.
+/// Loading the table from the catalog blocks the current thread, which requires a multi-threaded tokio runtime.
+pub struct TableChanges {
+    catalog: Arc<dyn Catalog>,
+}
+
+impl TableChanges {
+    /// Create the table function for the tables of the catalog
+    pub fn new(catalog: Arc<dyn Catalog>) -> Self {
+        TableChanges { catalog }
+    }
+}
+
+impl TableFunctionImpl for TableChanges {
+    fn call(&self, args: &[Expr]) -> Result<Arc<dyn TableProvider>, DataFusionError> {
+        let (identifier, snapshot_ids) = match args {
+            [Expr::Literal(ScalarValue::Utf8(Some(identifier))), snapshot_ids @ ..]
+                if snapshot_ids.len() <= 2 =>
+            {
+                (identifier, snapshot_ids)
+            }
+            _ => {
+                return plan_err!(
+                "table_changes expects a table identifier and optional start and end snapshot ids"
+            )
+            }
+        };
+        let snapshot_ids = snapshot_ids
+            .iter()
+            .map(|expr| match expr {
+                Expr::Literal(ScalarValue::Null) => Ok(None),
+                Expr::Literal(ScalarValue::Int64(snapshot_id)) => Ok(*snapshot_id),
+                _ => plan_err!("table_changes expects the snapshot ids to be integer literals"),
+            })
+            .collect::<Result<Vec<_>, DataFusionError>>()?;
+        let start = snapshot_ids.first().copied().flatten();
+        let end = snapshot_ids.get(1).copied().flatten();
+
+        let identifier = Identifier::parse(identifier).map_err(Error::from)?;
+        let catalog = self.catalog.clone();
+        let tabular = tokio::task::block_in_place(|| {
+            Handle::current().block_on(catalog.load_tabular(&identifier))
+        })
+        .map_err(Error::from)?;
+        let Tabular::Table(table) = tabular else {
+            return plan_err!("table_changes is only supported for tables");
+        };
+        Ok(Arc::new(DataFusionChangelog::new(table, start, end)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::{
+        arrow::{
+            array::{ArrayRef, Int64Array, StringArray},
+            record_batch::RecordBatch,
+        },
+        prelude::SessionContext,
+    };
+    use futures::stream;
+    use iceberg_rust::{
+        arrow::write::{write_parquet_partitioned, write_position_deletes},
+        catalog::Catalog,
+        spec::{
+            partition::PartitionSpec,
+            schema::Schema,
+            types::{PrimitiveType, StructField, StructType, Type},
+        },
+        table::{transaction::overwrite::OverwriteMode, Table},
+    };
+    use iceberg_sql_catalog::SqlCatalog;
+    use object_store::{memory::InMemory, ObjectStore};
+
+    use super::TableChanges;
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn test_table_changes() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        let write = |table: &Table, ids: Vec<i64>| {
+            let metadata = table.metadata().clone();
+            let object_store = table.object_store();
+            async move {
+                let batch = RecordBatch::try_from_iter(vec![(
+                    "id",
+                    Arc::new(Int64Array::from(ids)) as ArrayRef,
+                )])
+                .unwrap();
+                write_parquet_partitioned(
+                    &metadata,
+                    stream::iter(vec![Ok(batch)]),
+                    object_store,
+                    None,
+                )
+                .await
+                .expect("Failed to write data files")
+            }
+        };
+
+        let files = write(&table, vec![1, 2, 3]).await;
+        table
+            .new_transaction(None)
+            .append(files)
+            .commit()
+            .await
+            .expect("Failed to commit append");
+        let manifests = table.manifests(None, None).await.unwrap();
+        let data_file = table.datafiles(&manifests, None).await.unwrap()[0]
+            .data_file()
+            .clone();
+
+        let files = write(&table, vec![4]).await;
+        table
+            .new_transaction(None)
+            .append(files)
+            .commit()
+            .await
+            .expect("Failed to commit append");
+
+        // Delete the first row of the first data file
+        let delete_files = write_position_deletes(
+            table.metadata(),
+            vec![(data_file, vec![0])],
+            table.object_store(),
+        )
+        .await
+        .expect("Failed to write position deletes");
+        table
+            .new_transaction(None)
+            .row_delta(Vec::new(), delete_files)
+            .commit()
+            .await
+            .expect("Failed to commit row delta");
+
+        let files = write(&table, vec![10]).await;
+        table
+            .new_transaction(None)
+            .overwrite(files, OverwriteMode::Dynamic)
+            .commit()
+            .await
+            .expect("Failed to commit overwrite");
+
+        let ctx = SessionContext::new();
+        ctx.register_udtf("table_changes", Arc::new(TableChanges::new(catalog)));
+
+        let batches = ctx
+            .sql("select id, _change_type, _change_ordinal from table_changes('test.orders', NULL, NULL) order by _change_ordinal, _change_type, id;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let mut changes = Vec::new();
+        for batch in batches {
+            let ids = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            let change_types = batch
+                .column(1)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            let change_ordinals = batch
+                .column(2)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            for i in 0..batch.num_rows() {
+                changes.push((
+                    ids.value(i),
+                    change_types.value(i).to_owned(),
+                    change_ordinals.value(i),
+                ));
+            }
+        }
+
+        assert_eq!(
+            changes,
+            vec![
+                (1, "INSERT".to_owned(), 0),
+                (2, "INSERT".to_owned(), 0),
+                (3, "INSERT".to_owned(), 0),
+                (4, "INSERT".to_owned(), 1),
+                (1, "DELETE".to_owned(), 2),
+                (2, "DELETE".to_owned(), 3),
+                (3, "DELETE".to_owned(), 3),
+                (4, "DELETE".to_owned(), 3),
+                (10, "INSERT".to_owned(), 3),
+            ]
+        );
+    }
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn test_table_changes_schema_evolution() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let field = |id: i32, name: &str, primitive: PrimitiveType| StructField {
+            id,
+            name: name.to_string(),
+            required: false,
+            field_type: Type::Primitive(primitive),
+            doc: None,
+        };
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(field(1, "id", PrimitiveType::Long))
+                    .with_struct_field(field(2, "name", PrimitiveType::String))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "name",
+                Arc::new(StringArray::from(vec!["apple", "banana"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let old_files = write_parquet_partitioned(
+            table.metadata(),
+            stream::iter(vec![Ok(batch)]),
+            table.object_store(),
+            None,
+        )
+        .await
+        .expect("Failed to write data files");
+
+        // The column "name" is renamed to "customer", the column "id" is dropped and the column "amount" is added
+        let evolved_schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(field(3, "amount", PrimitiveType::Long))
+                    .with_struct_field(field(2, "customer", PrimitiveType::String))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut evolved = Table::builder()
+            .with_name("evolved_orders")
+            .with_location("/test/evolved_orders")
+            .with_schema(evolved_schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        evolved
+            .new_transaction(None)
+            .append(old_files)
+            .commit()
+            .await
+            .expect("Failed to commit append");
+
+        let batch = RecordBatch::try_from_iter(vec![
+            ("amount", Arc::new(Int64Array::from(vec![5])) as ArrayRef),
+            (
+                "customer",
+                Arc::new(StringArray::from(vec!["cherry"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let files = write_parquet_partitioned(
+            evolved.metadata(),
+            stream::iter(vec![Ok(batch)]),
+            evolved.object_store(),
+            None,
+        )
+        .await
+        .expect("Failed to write data files");
+        evolved
+            .new_transaction(None)
+            .append(files)
+            .commit()
+            .await
+            .expect("Failed to commit append");
+
+        let ctx = SessionContext::new();
+        ctx.register_udtf("table_changes", Arc::new(TableChanges::new(catalog)));
+
+        let batches = ctx
+            .sql(
+                "select * from table_changes('test.evolved_orders', NULL, NULL) order by customer;",
+            )
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let mut changes = Vec::new();
+        for batch in batches {
+            assert_eq!(batch.schema().field(0).name(), "amount");
+            assert_eq!(batch.schema().field(1).name(), "customer");
+            let amounts = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            let customers = batch
+                .column(1)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            for (amount, customer) in amounts.iter().zip(customers.iter()) {
+                changes.push((amount, customer.unwrap().to_owned()));
+            }
+        }
+
+        assert_eq!(
+            changes,
+            vec![
+                (None, "apple".to_owned()),
+                (None, "banana".to_owned()),
+                (Some(5), "cherry".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,212 @@
+/*!
+ * Execution plan to apply delete files to the parquet scan of data files
+*/
+
+use std::{any::Any, collections::HashMap, fmt, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::AsArray,
+        compute::{filter_record_batch, not},
+        datatypes::SchemaRef,
+        record_batch::RecordBatch,
+    },
+    common::DataFusionError,
+    execution::TaskContext,
+    physical_expr::EquivalenceProperties,
+    physical_plan::{
+        stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionMode,
+        ExecutionPlan, PlanProperties, SendableRecordBatchStream,
+    },
+};
+use futures::{future, stream, StreamExt, TryStreamExt};
+use iceberg_rust::{
+    arrow::{
+        delete::{EqualityDeleteFile, EqualityDeletes, PositionDeletes},
+        read::deleted_rows,
+    },
+    spec::manifest::ManifestEntry,
+};
+use object_store::ObjectStore;
+use tokio::sync::OnceCell;
+
+use crate::error::Error;
+
+/// Name of the partition column that the parquet scan of data files with delete files appends to every record batch.
+/// It contains the path of the data file that the record batch was read from.
+pub(crate) static DATA_FILE_PATH_COLUMN: &str = "__iceberg_data_file_path";
+
+/// Applies the delete files to the record batches of a parquet scan of data files with delete files.
+/// The last column of the parquet scan contains the path of the data file, which is used to look up its deletes.
+/// The output consists of the first columns of the parquet scan, additional columns are only used to evaluate equality deletes.
+/// Files with position deletes have to be scanned without pruning and without splitting them, otherwise the positions of the rows are unknown.
+#[derive(Debug)]
+pub(crate) struct DeleteScanExec {
+    /// The parquet scan isn't exposed as a child, so that the optimizer doesn't repartition the data files
+    input: Arc<dyn ExecutionPlan>,
+    data_files: Arc<HashMap<String, ManifestEntry>>,
+    delete_files: Arc<Vec<ManifestEntry>>,
+    object_store: Arc<dyn ObjectStore>,
+    deletes: Arc<OnceCell<Arc<(PositionDeletes, EqualityDeletes)>>>,
+    schema: SchemaRef,
+    properties: PlanProperties,
+}
+
+impl DeleteScanExec {
+    pub(crate) fn try_new(
+        input: Arc<dyn ExecutionPlan>,
+        data_files: Vec<ManifestEntry>,
+        delete_files: Vec<ManifestEntry>,
+        object_store: Arc<dyn ObjectStore>,
+        num_columns: usize,
+    ) -> Result<Self, DataFusionError> {
+        let schema = Arc::new(
+            input
+                .schema()
+                .project(&(0..num_columns).collect::<Vec<_>>())?,
+        );
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema.clone()),
+            input.output_partitioning().clone(),
+            ExecutionMode::Bounded,
+        );
+        Ok(DeleteScanExec {
+            input,
+            data_files: Arc::new(
+                data_files
+                    .into_iter()
+                    .map(|entry| (entry.data_file().file_path().clone(), entry))
+                    .collect(),
+            ),
+            delete_files: Arc::new(delete_files),
+            object_store,
+            deletes: Arc::new(OnceCell::new()),
+            schema,
+            properties,
+        })
+    }
+}
+
+impl DisplayAs for DeleteScanExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(
+                    f,
+                    "DeleteScanExec: data_files={}, delete_files={}, input=",
+                    self.data_files.len(),
+                    self.delete_files.len()
+                )?;
+                self.input.fmt_as(t, f)
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for DeleteScanExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>, DataFusionError> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream, DataFusionError> {
+        let input = self.input.execute(partition, context)?;
+        let data_files = self.data_files.clone();
+        let delete_files = self.delete_files.clone();
+        let object_store = self.object_store.clone();
+        let deletes = self.deletes.clone();
+        let projection = (0..self.schema.fields().len()).collect::<Vec<_>>();
+
+        // The delete files are read once and shared by all partitions
+        let stream = stream::once(async move {
+            let deletes = deletes
+                .get_or_try_init(|| async move {
+                    future::try_join(
+                        PositionDeletes::try_new(
+                            delete_files.iter().cloned(),
+                            object_store.clone(),
+                        ),
+                        EqualityDeletes::try_new(delete_files.iter().cloned(), object_store),
+                    )
+                    .await
+                    .map(Arc::new)
+                })
+                .await
+                .map_err(Error::from)?
+                .clone();
+
+            let mut current: Option<FileDeletes> = None;
+            Ok::<_, DataFusionError>(input.map(
+                move |batch| -> Result<RecordBatch, DataFusionError> {
+                    let batch = batch?;
+                    if batch.num_rows() == 0 {
+                        return Ok(batch.project(&projection)?);
+                    }
+                    let file_path = batch
+                        .column(batch.num_columns() - 1)
+                        .as_string_opt::<i32>()
+                        .ok_or(Error::InvalidFormat(DATA_FILE_PATH_COLUMN.to_owned()))?
+                        .value(0);
+                    // The record batches of a file are returned in order and don't contain rows of other files
+                    if current
+                        .as_ref()
+                        .map_or(true, |current| current.file_path != file_path)
+                    {
+                        let data_file = data_files.get(file_path).ok_or(Error::NotFound(
+                            "Data file".to_owned(),
+                            file_path.to_owned(),
+                        ))?;
+                        current = Some(FileDeletes {
+                            file_path: file_path.to_owned(),
+                            offset: 0,
+                            positions: deletes.0.positions(data_file),
+                            equality_deletes: deletes.1.files(data_file),
+                        });
+                    }
+                    let file = current.as_mut().unwrap();
+                    let deleted =
+                        deleted_rows(&batch, file.offset, &file.positions, &file.equality_deletes)?;
+                    file.offset += batch.num_rows() as i64;
+                    Ok(filter_record_batch(&batch, &not(&deleted)?)?.project(&projection)?)
+                },
+            ))
+        })
+        .try_flatten();
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            stream,
+        )))
+    }
+}
+
+/// Deletes of the data file that is currently read
+struct FileDeletes {
+    file_path: String,
+    /// Position of the first row of the next record batch in the data file
+    offset: i64,
+    positions: Vec<i64>,
+    equality_deletes: Vec<Arc<EqualityDeleteFile>>,
+}
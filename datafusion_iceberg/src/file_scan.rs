@@ -0,0 +1,146 @@
+/*!
+ * Execution plan to read data files with the iceberg reader
+*/
+
+use std::{any::Any, fmt, sync::Arc};
+
+use datafusion::{
+    arrow::{array::new_null_array, datatypes::SchemaRef, record_batch::RecordBatch},
+    common::DataFusionError,
+    execution::TaskContext,
+    physical_expr::EquivalenceProperties,
+    physical_plan::{
+        stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionMode,
+        ExecutionPlan, Partitioning, PlanProperties, SendableRecordBatchStream,
+    },
+};
+use futures::{stream, StreamExt};
+use iceberg_rust::{
+    arrow::{projection::project_batch, read::read},
+    spec::{manifest::ManifestEntry, name_mapping::NameMapping, types::StructType},
+};
+use object_store::ObjectStore;
+
+use crate::error::Error;
+
+/// Scans data files of file formats that the parquet scan of datafusion can't read with the iceberg reader, which also applies their delete files.
+/// Every file group consists of data files and the delete files that might apply to them.
+#[derive(Debug)]
+pub(crate) struct FileScanExec {
+    file_groups: Vec<Vec<ManifestEntry>>,
+    object_store: Arc<dyn ObjectStore>,
+    schema: SchemaRef,
+    table_schema: Arc<StructType>,
+    name_mapping: Option<Arc<NameMapping>>,
+    properties: PlanProperties,
+}
+
+impl FileScanExec {
+    pub(crate) fn new(
+        file_groups: Vec<Vec<ManifestEntry>>,
+        object_store: Arc<dyn ObjectStore>,
+        schema: SchemaRef,
+        table_schema: StructType,
+        name_mapping: Option<NameMapping>,
+    ) -> Self {
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema.clone()),
+            Partitioning::UnknownPartitioning(file_groups.len()),
+            ExecutionMode::Bounded,
+        );
+        FileScanExec {
+            file_groups,
+            object_store,
+            schema,
+            table_schema: Arc::new(table_schema),
+            name_mapping: name_mapping.map(Arc::new),
+            properties,
+        }
+    }
+}
+
+impl DisplayAs for FileScanExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(f, "FileScanExec: file_groups={}", self.file_groups.len())
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for FileScanExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>, DataFusionError> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream, DataFusionError> {
+        let files = self
+            .file_groups
+            .get(partition)
+            .cloned()
+            .ok_or(DataFusionError::Internal(format!(
+                "Invalid partition {} for FileScanExec",
+                partition
+            )))?;
+        let object_store = self.object_store.clone();
+        let schema = self.schema.clone();
+        let table_schema = self.table_schema.clone();
+        let name_mapping = self.name_mapping.clone();
+
+        // The columns of the data files are matched to the table schema by field id before the output columns are selected
+        let stream = stream::once(async move { read(files.into_iter(), object_store).await })
+            .flatten()
+            .map(move |batch| {
+                let batch = project_batch(&batch?, &table_schema, name_mapping.as_deref())
+                    .map_err(Error::from)?;
+                project(batch, &schema)
+            });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            stream,
+        )))
+    }
+}
+
+/// Select the columns of the output schema from a record batch. Columns that are not part of the data file are null.
+pub(crate) fn project(
+    batch: RecordBatch,
+    schema: &SchemaRef,
+) -> Result<RecordBatch, DataFusionError> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            batch
+                .column_by_name(field.name())
+                .cloned()
+                .unwrap_or_else(|| new_null_array(field.data_type(), batch.num_rows()))
+        })
+        .collect();
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
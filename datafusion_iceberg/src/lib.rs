@@ -1,7 +1,11 @@
 pub mod catalog;
+pub mod changelog;
+mod delete;
 pub mod error;
+mod file_scan;
 pub mod materialized_view;
 mod pruning_statistics;
+mod schema_adapter;
 pub mod sql;
 mod statistics;
 pub mod table;
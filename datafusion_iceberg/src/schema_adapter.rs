@@ -0,0 +1,102 @@
+/*!
+ * Schema adapter that matches the columns of parquet files to the table schema by field id
+*/
+
+use std::sync::Arc;
+
+use datafusion::{
+    arrow::{
+        datatypes::{Schema as ArrowSchema, SchemaRef},
+        record_batch::RecordBatch,
+    },
+    common::DataFusionError,
+    datasource::schema_adapter::{SchemaAdapter, SchemaAdapterFactory, SchemaMapper},
+};
+use iceberg_rust::{
+    arrow::projection::{project_batch, source_index},
+    spec::{name_mapping::NameMapping, types::StructType},
+};
+
+use crate::error::Error;
+
+/// Creates schema adapters for the projected table schema of a parquet scan.
+/// Renamed, added, dropped and promoted columns are resolved with the field ids of the table schema.
+#[derive(Debug)]
+pub(crate) struct IcebergSchemaAdapterFactory {
+    schema: StructType,
+    name_mapping: Option<NameMapping>,
+}
+
+impl IcebergSchemaAdapterFactory {
+    pub(crate) fn new(schema: StructType, name_mapping: Option<NameMapping>) -> Self {
+        IcebergSchemaAdapterFactory {
+            schema,
+            name_mapping,
+        }
+    }
+}
+
+impl SchemaAdapterFactory for IcebergSchemaAdapterFactory {
+    fn create(&self, projected_table_schema: SchemaRef) -> Box<dyn SchemaAdapter> {
+        let schema = StructType::new(
+            projected_table_schema
+                .fields()
+                .iter()
+                .filter_map(|field| self.schema.get_name(field.name()).cloned())
+                .collect(),
+        );
+        Box::new(IcebergSchemaAdapter {
+            schema: Arc::new(schema),
+            name_mapping: self.name_mapping.clone().map(Arc::new),
+        })
+    }
+}
+
+struct IcebergSchemaAdapter {
+    schema: Arc<StructType>,
+    name_mapping: Option<Arc<NameMapping>>,
+}
+
+impl SchemaAdapter for IcebergSchemaAdapter {
+    fn map_column_index(&self, index: usize, file_schema: &ArrowSchema) -> Option<usize> {
+        source_index(
+            self.schema.iter().nth(index)?,
+            file_schema,
+            self.name_mapping.as_deref(),
+        )
+    }
+
+    fn map_schema(
+        &self,
+        file_schema: &ArrowSchema,
+    ) -> Result<(Arc<dyn SchemaMapper>, Vec<usize>), DataFusionError> {
+        let mut projection = self
+            .schema
+            .iter()
+            .filter_map(|field| source_index(field, file_schema, self.name_mapping.as_deref()))
+            .collect::<Vec<_>>();
+        projection.sort_unstable();
+        projection.dedup();
+        Ok((
+            Arc::new(IcebergSchemaMapper {
+                schema: self.schema.clone(),
+                name_mapping: self.name_mapping.clone(),
+            }),
+            projection,
+        ))
+    }
+}
+
+struct IcebergSchemaMapper {
+    schema: Arc<StructType>,
+    name_mapping: Option<Arc<NameMapping>>,
+}
+
+impl SchemaMapper for IcebergSchemaMapper {
+    fn map_batch(&self, batch: RecordBatch) -> Result<RecordBatch, DataFusionError> {
+        Ok(
+            project_batch(&batch, &self.schema, self.name_mapping.as_deref())
+                .map_err(Error::from)?,
+        )
+    }
+}
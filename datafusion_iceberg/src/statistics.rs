@@ -5,21 +5,45 @@ use datafusion::{
     physical_plan::{ColumnStatistics, Statistics},
     scalar::ScalarValue,
 };
-use iceberg_rust::spec::{manifest::ManifestEntry, schema::Schema, values::Value};
-use iceberg_rust::{catalog::tabular::Tabular, table::Table};
+use iceberg_rust::spec::{
+    manifest::{Content, ManifestEntry},
+    schema::Schema,
+    values::Value,
+};
+use iceberg_rust::{
+    catalog::tabular::Tabular,
+    table::{
+        incremental::{NonAppendSnapshots, StartSnapshot},
+        Table,
+    },
+};
 
 use crate::error::Error;
 
-use super::table::DataFusionTable;
+use super::table::{end_snapshot, DataFusionTable};
 
 impl DataFusionTable {
     pub(crate) async fn statistics(&self) -> Result<Statistics, Error> {
         match self.tabular.read().await.deref() {
-            Tabular::Table(table) => table_statistics(table, &self.snapshot_range).await,
+            Tabular::Table(table) => {
+                table_statistics(
+                    table,
+                    &self.snapshot_range,
+                    self.append_only,
+                    self.branch.as_deref(),
+                )
+                .await
+            }
             Tabular::View(_) => Err(Error::NotSupported("Statistics for views".to_string())),
             Tabular::MaterializedView(mv) => {
                 let table = mv.storage_table().await.map_err(Error::from)?;
-                table_statistics(&table, &self.snapshot_range).await
+                table_statistics(
+                    &table,
+                    &self.snapshot_range,
+                    self.append_only,
+                    self.branch.as_deref(),
+                )
+                .await
             }
         }
     }
@@ -28,14 +52,34 @@ impl DataFusionTable {
 pub(crate) async fn table_statistics(
     table: &Table,
     snapshot_range: &(Option<i64>, Option<i64>),
+    append_only: bool,
+    branch: Option<&str>,
 ) -> Result<Statistics, Error> {
     let schema = snapshot_range
         .1
         .and_then(|snapshot_id| table.metadata().schema(snapshot_id).ok().cloned())
         .unwrap_or_else(|| table.current_schema(None).unwrap().clone());
-    let manifests = table.manifests(snapshot_range.0, snapshot_range.1).await?;
-    let datafiles = table.datafiles(&manifests, None).await?;
-    Ok(datafiles.iter().fold(
+    let end = end_snapshot(table, snapshot_range.1, branch)?;
+    let datafiles = match (append_only, snapshot_range.0) {
+        (true, Some(start)) => {
+            table
+                .incremental_append_files(
+                    Some(StartSnapshot::Exclusive(start)),
+                    end,
+                    NonAppendSnapshots::Fail,
+                )
+                .await?
+        }
+        _ => {
+            let manifests = table.manifests(snapshot_range.0, end).await?;
+            table.datafiles(&manifests, None).await?
+        }
+    };
+    // Delete files don't describe the rows of the table, the rows they delete make the statistics of the data files inexact
+    let (datafiles, delete_files): (Vec<_>, Vec<_>) = datafiles
+        .into_iter()
+        .partition(|manifest| matches!(manifest.data_file().content(), Content::Data));
+    let statistics = datafiles.iter().fold(
         Statistics {
             num_rows: Precision::Exact(0),
             total_byte_size: Precision::Exact(0),
@@ -71,7 +115,12 @@ pub(crate) async fn table_statistics(
                     .collect(),
             }
         },
-    ))
+    );
+    if delete_files.is_empty() {
+        Ok(statistics)
+    } else {
+        Ok(statistics.into_inexact())
+    }
 }
 
 fn column_statistics<'a>(
@@ -91,21 +140,13 @@ fn column_statistics<'a>(
                 .upper_bounds()
                 .as_ref()
                 .and_then(|x| x.get(&id))
-                .and_then(|x| {
-                    Some(Precision::Exact(
-                        convert_value_to_scalar_value(x.clone()).ok()?,
-                    ))
-                })
+                .and_then(bound_statistics)
                 .unwrap_or(Precision::Absent),
             min_value: data_file
                 .lower_bounds()
                 .as_ref()
                 .and_then(|x| x.get(&id))
-                .and_then(|x| {
-                    Some(Precision::Exact(
-                        convert_value_to_scalar_value(x.clone()).ok()?,
-                    ))
-                })
+                .and_then(bound_statistics)
                 .unwrap_or(Precision::Absent),
             distinct_count: data_file
                 .distinct_counts()
@@ -117,6 +158,15 @@ fn column_statistics<'a>(
     })
 }
 
+/// String and binary bounds might be truncated by the metrics mode of the column, so they are only inexact statistics
+fn bound_statistics(value: &Value) -> Option<Precision<ScalarValue>> {
+    let scalar = convert_value_to_scalar_value(value.clone()).ok()?;
+    match value {
+        Value::String(_) | Value::Binary(_) => Some(Precision::Inexact(scalar)),
+        _ => Some(Precision::Exact(scalar)),
+    }
+}
+
 pub(crate) fn manifest_statistics(schema: &Schema, manifest: &ManifestEntry) -> Statistics {
     Statistics {
         num_rows: Precision::Exact(*manifest.data_file().record_count() as usize),
@@ -11,19 +11,19 @@ use std::{
     any::Any,
     collections::{HashMap, HashSet},
     fmt,
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, Range},
     sync::Arc,
 };
 use tokio::sync::{RwLock, RwLockWriteGuard};
 
 use datafusion::{
-    arrow::datatypes::{Field, SchemaRef},
-    common::{not_impl_err, plan_err, DataFusionError, SchemaExt},
+    arrow::datatypes::{DataType, Field, SchemaRef},
+    common::{plan_err, DataFusionError, SchemaExt},
+    config::TableParquetOptions,
     datasource::{
-        file_format::{parquet::ParquetFormat, FileFormat},
-        listing::PartitionedFile,
+        listing::{FileRange, PartitionedFile},
         object_store::ObjectStoreUrl,
-        physical_plan::FileScanConfig,
+        physical_plan::{FileScanConfig, ParquetExec},
         TableProvider, ViewTable,
     },
     execution::{context::SessionState, TaskContext},
@@ -33,6 +33,7 @@ use datafusion::{
     physical_plan::{
         insert::{DataSink, DataSinkExec},
         metrics::MetricsSet,
+        union::UnionExec,
         DisplayAs, DisplayFormatType, ExecutionPlan, SendableRecordBatchStream, Statistics,
     },
     prelude::Expr,
@@ -41,20 +42,32 @@ use datafusion::{
 };
 
 use crate::{
+    delete::{DeleteScanExec, DATA_FILE_PATH_COLUMN},
     error::Error,
+    file_scan::FileScanExec,
     pruning_statistics::{PruneDataFiles, PruneManifests},
+    schema_adapter::IcebergSchemaAdapterFactory,
     statistics::manifest_statistics,
 };
 
 use iceberg_rust::spec::util;
 use iceberg_rust::spec::{
+    manifest::{Content, FileFormat as DataFileFormat, ManifestEntry},
     schema::Schema,
     types::{StructField, StructType},
     view_metadata::ViewRepresentation,
 };
 use iceberg_rust::{
-    arrow::write::write_parquet_partitioned, catalog::tabular::Tabular,
-    materialized_view::MaterializedView, table::Table, view::View,
+    arrow::{delete::applies_to, projection::name_mapping, write::write_parquet_partitioned},
+    catalog::tabular::Tabular,
+    materialized_view::MaterializedView,
+    table::{
+        incremental::{NonAppendSnapshots, StartSnapshot},
+        split::SplitConfig,
+        transaction::overwrite::OverwriteMode,
+        Table,
+    },
+    view::View,
 };
 // mod value;
 
@@ -65,6 +78,8 @@ pub struct DataFusionTable {
     pub schema: SchemaRef,
     pub snapshot_range: (Option<i64>, Option<i64>),
     pub branch: Option<String>,
+    /// Only scan the data files that were appended in the snapshot range
+    pub append_only: bool,
 }
 
 impl From<Tabular> for DataFusionTable {
@@ -123,6 +138,7 @@ impl DataFusionTable {
             snapshot_range: (start, end),
             schema,
             branch: branch.map(ToOwned::to_owned),
+            append_only: false,
         }
     }
     #[inline]
@@ -135,6 +151,52 @@ impl DataFusionTable {
         Self::new(Tabular::Table(table), start, end, branch)
     }
 
+    /// Create a table provider that only scans the data files appended after the start snapshot up to the end snapshot.
+    /// The end snapshot defaults to the current snapshot of the branch. The scan fails if the range contains overwrite or delete snapshots.
+    pub fn new_incremental_append(
+        table: Table,
+        start: i64,
+        end: Option<i64>,
+        branch: Option<&str>,
+    ) -> Self {
+        DataFusionTable {
+            append_only: true,
+            ..Self::new(Tabular::Table(table), Some(start), end, branch)
+        }
+    }
+
+    /// Create a table provider for the state of a table or a view at the timestamp in milliseconds
+    pub fn new_as_of(
+        tabular: Tabular,
+        timestamp_ms: i64,
+        branch: Option<&str>,
+    ) -> Result<Self, Error> {
+        let end = match &tabular {
+            Tabular::Table(table) => *table
+                .snapshot_as_of(timestamp_ms, branch)?
+                .ok_or(Error::NotFound(
+                    "Snapshot as of".to_owned(),
+                    timestamp_ms.to_string(),
+                ))?
+                .snapshot_id(),
+            Tabular::View(view) => {
+                view.metadata()
+                    .version_as_of(timestamp_ms)
+                    .ok_or(Error::NotFound(
+                        "Version as of".to_owned(),
+                        timestamp_ms.to_string(),
+                    ))?
+                    .version_id
+            }
+            Tabular::MaterializedView(_) => {
+                return Err(Error::NotSupported(
+                    "Time travel for materialized views".to_owned(),
+                ))
+            }
+        };
+        Ok(Self::new(tabular, None, Some(end), branch))
+    }
+
     pub async fn inner_mut(&self) -> RwLockWriteGuard<'_, Tabular> {
         self.tabular.write().await
     }
@@ -181,6 +243,8 @@ impl TableProvider for DataFusionTable {
                 table_scan(
                     table,
                     &self.snapshot_range,
+                    self.append_only,
+                    self.branch.as_deref(),
                     schema,
                     statistics,
                     session,
@@ -197,6 +261,8 @@ impl TableProvider for DataFusionTable {
                 table_scan(
                     &table,
                     &self.snapshot_range,
+                    self.append_only,
+                    self.branch.as_deref(),
                     schema,
                     statistics,
                     session,
@@ -219,12 +285,9 @@ impl TableProvider for DataFusionTable {
         if !self.schema().equivalent_names_and_types(&input.schema()) {
             return plan_err!("Inserting query must have the same schema with the table.");
         }
-        if overwrite {
-            return not_impl_err!("Overwrite not implemented for MemoryTable yet");
-        }
         Ok(Arc::new(DataSinkExec::new(
             input,
-            Arc::new(self.clone().into_data_sink()),
+            Arc::new(self.clone().into_data_sink(overwrite)),
             self.schema.clone(),
             None,
         )))
@@ -244,6 +307,8 @@ impl TableProvider for DataFusionTable {
 async fn table_scan(
     table: &Table,
     snapshot_range: &(Option<i64>, Option<i64>),
+    append_only: bool,
+    branch: Option<&str>,
     arrow_schema: SchemaRef,
     statistics: Statistics,
     session: &SessionState,
@@ -264,9 +329,9 @@ async fn table_scan(
         .runtime_env()
         .register_object_store(object_store_url.as_ref(), table.object_store());
 
-    // All files have to be grouped according to their partition values. This is done by using a HashMap with the partition values as the key.
-    // This way data files with the same partition value are mapped to the same vector.
-    let mut file_groups: HashMap<Vec<ScalarValue>, Vec<PartitionedFile>> = HashMap::new();
+    // Data files are cut into splits at their row group offsets, the splits are bin-packed into file groups that are read in parallel.
+    let split_config = SplitConfig::new(&table.metadata().properties);
+    let mut splits: Vec<PartitionedFile> = Vec::new();
 
     let partition_column_names = table
         .metadata()
@@ -297,57 +362,76 @@ async fn table_scan(
     } else {
         None
     };
-    if let Some(physical_predicate) = physical_predicate.clone() {
-        let partition_predicates = conjunction(
-            filters
-                .iter()
-                .filter(|expr| {
-                    if let Ok(set) = expr.to_columns() {
-                        let set: HashSet<String> =
-                            set.into_iter().map(|x| x.name.clone()).collect();
-                        set.is_subset(&partition_column_names)
-                    } else {
-                        false
-                    }
-                })
-                .cloned(),
-        );
-
-        let manifests = table
-            .manifests(snapshot_range.0, snapshot_range.1)
-            .await
-            .map_err(Into::<Error>::into)?;
-
-        // If there is a filter expression on the partition column, the manifest files to read are pruned.
-        let data_files = if let Some(predicate) = partition_predicates {
-            let physical_partition_predicate = create_physical_expr(
-                &predicate,
-                &arrow_schema.as_ref().clone().try_into()?,
-                session.execution_props(),
-            )?;
-            let pruning_predicate =
-                PruningPredicate::try_new(physical_partition_predicate, arrow_schema.clone())?;
-            let partition_spec = table
-                .metadata()
-                .default_partition_spec()
-                .map_err(Error::from)?;
-            let manifests_to_prune = pruning_predicate.prune(&PruneManifests::new(
-                &schema,
-                &partition_spec,
-                &manifests,
-            ))?;
-
-            table
-                .datafiles(&manifests, Some(manifests_to_prune))
-                .await
-                .map_err(Into::<Error>::into)?
-        } else {
-            table
-                .datafiles(&manifests, None)
+    let end = end_snapshot(table, snapshot_range.1, branch)?;
+    let entries = match (append_only, snapshot_range.0) {
+        // An incremental append scan only reads the files that were appended after the start snapshot
+        (true, Some(start)) => table
+            .incremental_append_files(
+                Some(StartSnapshot::Exclusive(start)),
+                end,
+                NonAppendSnapshots::Fail,
+            )
+            .await
+            .map_err(Into::<Error>::into)?,
+        _ => {
+            let manifests = table
+                .manifests(snapshot_range.0, end)
                 .await
-                .map_err(Into::<Error>::into)?
-        };
+                .map_err(Into::<Error>::into)?;
+
+            let partition_predicates = conjunction(
+                filters
+                    .iter()
+                    .filter(|expr| {
+                        if let Ok(set) = expr.to_columns() {
+                            let set: HashSet<String> =
+                                set.into_iter().map(|x| x.name.clone()).collect();
+                            set.is_subset(&partition_column_names)
+                        } else {
+                            false
+                        }
+                    })
+                    .cloned(),
+            );
+
+            // If there is a filter expression on the partition column, the manifest files to read are pruned.
+            if let Some(predicate) = partition_predicates {
+                let physical_partition_predicate = create_physical_expr(
+                    &predicate,
+                    &arrow_schema.as_ref().clone().try_into()?,
+                    session.execution_props(),
+                )?;
+                let pruning_predicate =
+                    PruningPredicate::try_new(physical_partition_predicate, arrow_schema.clone())?;
+                let partition_spec = table
+                    .metadata()
+                    .default_partition_spec()
+                    .map_err(Error::from)?;
+                let manifests_to_prune = pruning_predicate.prune(&PruneManifests::new(
+                    &schema,
+                    &partition_spec,
+                    &manifests,
+                ))?;
+
+                table
+                    .datafiles(&manifests, Some(manifests_to_prune))
+                    .await
+                    .map_err(Into::<Error>::into)?
+            } else {
+                table
+                    .datafiles(&manifests, None)
+                    .await
+                    .map_err(Into::<Error>::into)?
+            }
+        }
+    };
+
+    // The statistics of delete files don't describe the table columns, therefore only data files are pruned.
+    let (data_files, delete_files): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|manifest| matches!(manifest.data_file().content(), Content::Data));
 
+    let data_files = if let Some(physical_predicate) = physical_predicate.clone() {
         let pruning_predicate =
             PruningPredicate::try_new(physical_predicate, arrow_schema.clone())?;
         // After the first pruning stage the data_files are pruned again based on the pruning statistics in the manifest files.
@@ -357,88 +441,116 @@ async fn table_scan(
         data_files
             .into_iter()
             .zip(files_to_prune.into_iter())
-            .for_each(|(manifest, prune_file)| {
-                if prune_file {
-                    let partition_values = manifest
-                        .data_file()
-                        .partition()
-                        .iter()
-                        .map(|value| match value {
-                            Some(v) => ScalarValue::Utf8(Some(serde_json::to_string(v).unwrap())),
-                            None => ScalarValue::Null,
-                        })
-                        .collect::<Vec<ScalarValue>>();
-                    let object_meta = ObjectMeta {
-                        location: util::strip_prefix(manifest.data_file().file_path()).into(),
-                        size: *manifest.data_file().file_size_in_bytes() as usize,
-                        last_modified: {
-                            let last_updated_ms = table.metadata().last_updated_ms;
-                            let secs = last_updated_ms / 1000;
-                            let nsecs = (last_updated_ms % 1000) as u32 * 1000000;
-                            DateTime::from_timestamp(secs, nsecs).unwrap()
-                        },
-                        e_tag: None,
-                        version: None,
-                    };
-                    let manifest_statistics = manifest_statistics(&schema, &manifest);
-                    let file = PartitionedFile {
-                        object_meta,
-                        partition_values,
-                        range: None,
-                        statistics: Some(manifest_statistics),
-                        extensions: None,
-                    };
-                    file_groups
-                        .entry(file.partition_values.clone())
-                        .or_default()
-                        .push(file);
-                };
-            });
+            .filter_map(|(manifest, prune_file)| prune_file.then_some(manifest))
+            .collect()
     } else {
-        let manifests = table
-            .manifests(snapshot_range.0, snapshot_range.1)
-            .await
-            .map_err(Into::<Error>::into)?;
-        let data_files = table
-            .datafiles(&manifests, None)
-            .await
-            .map_err(Into::<Error>::into)?;
-        data_files.into_iter().for_each(|manifest| {
-            let partition_values = manifest
-                .data_file()
-                .partition()
-                .iter()
-                .map(|value| match value {
-                    Some(v) => ScalarValue::Utf8(Some(serde_json::to_string(v).unwrap())),
-                    None => ScalarValue::Null,
-                })
-                .collect::<Vec<ScalarValue>>();
-            let object_meta = ObjectMeta {
-                location: util::strip_prefix(manifest.data_file().file_path()).into(),
-                size: *manifest.data_file().file_size_in_bytes() as usize,
-                last_modified: {
-                    let last_updated_ms = table.metadata().last_updated_ms;
-                    let secs = last_updated_ms / 1000;
-                    let nsecs = (last_updated_ms % 1000) as u32 * 1000000;
-                    DateTime::from_timestamp(secs, nsecs).unwrap()
-                },
-                e_tag: None,
-                version: None,
-            };
-            let manifest_statistics = manifest_statistics(&schema, &manifest);
-            let file = PartitionedFile {
+        data_files
+    };
+
+    // Delete files only apply to data files of the same partition. Equality delete files of an unpartitioned spec apply to all data files.
+    let mut delete_files_by_partition: HashMap<Vec<ScalarValue>, Vec<ManifestEntry>> =
+        HashMap::new();
+    let mut global_delete_files: Vec<ManifestEntry> = Vec::new();
+    for manifest in delete_files {
+        if manifest.data_file().partition().fields.is_empty() {
+            global_delete_files.push(manifest);
+        } else {
+            delete_files_by_partition
+                .entry(partition_values(&manifest))
+                .or_default()
+                .push(manifest);
+        }
+    }
+
+    // Data files with applicable delete files are scanned separately, the deletes are applied on top of their parquet scan.
+    // The parquet scan of datafusion can't read other file formats, they are read with the iceberg reader, which also applies their deletes.
+    let mut file_format_groups: HashMap<Vec<ScalarValue>, Vec<ManifestEntry>> = HashMap::new();
+    let mut equality_delete_scan = DeleteScan::default();
+    let mut position_delete_scan = DeleteScan::default();
+
+    for manifest in data_files {
+        let partition_values = partition_values(&manifest);
+        if !matches!(manifest.data_file().file_format(), DataFileFormat::Parquet) {
+            file_format_groups
+                .entry(partition_values)
+                .or_default()
+                .push(manifest);
+            continue;
+        }
+        let deletes = delete_files_by_partition
+            .get(&partition_values)
+            .into_iter()
+            .flatten()
+            .chain(global_delete_files.iter())
+            .filter(|delete| applies_to(delete, &manifest))
+            .cloned()
+            .collect::<Vec<_>>();
+        let object_meta = ObjectMeta {
+            location: util::strip_prefix(manifest.data_file().file_path()).into(),
+            size: *manifest.data_file().file_size_in_bytes() as usize,
+            last_modified: {
+                let last_updated_ms = table.metadata().last_updated_ms;
+                let secs = last_updated_ms / 1000;
+                let nsecs = (last_updated_ms % 1000) as u32 * 1000000;
+                DateTime::from_timestamp(secs, nsecs).unwrap()
+            },
+            e_tag: None,
+            version: None,
+        };
+        let statistics = manifest_statistics(&schema, &manifest);
+        if deletes.is_empty() {
+            splits.extend(partitioned_files(
+                object_meta,
+                partition_values,
+                statistics,
+                split_config.split(manifest.data_file()),
+            ));
+            continue;
+        }
+        // The delete scan appends the path of the data file as an additional partition column
+        let mut partition_values = partition_values;
+        partition_values.push(ScalarValue::Utf8(Some(
+            manifest.data_file().file_path().clone(),
+        )));
+        if deletes
+            .iter()
+            .any(|delete| matches!(delete.data_file().content(), Content::PositionDeletes))
+        {
+            // The positions of the rows are only known if the whole file is read
+            position_delete_scan.files.push(PartitionedFile {
                 object_meta,
                 partition_values,
                 range: None,
-                statistics: Some(manifest_statistics),
+                statistics: Some(statistics),
                 extensions: None,
-            };
-            file_groups
-                .entry(file.partition_values.clone())
-                .or_default()
-                .push(file);
-        });
+            });
+            position_delete_scan.add(manifest, deletes);
+        } else {
+            equality_delete_scan.files.extend(partitioned_files(
+                object_meta,
+                partition_values,
+                statistics,
+                split_config.split(manifest.data_file()),
+            ));
+            equality_delete_scan.add(manifest, deletes);
+        }
+    }
+    let split_size = |file: &PartitionedFile| match &file.range {
+        Some(range) => range.end - range.start,
+        None => file.object_meta.size as i64,
     };
+    let file_groups = split_config.pack(splits, split_size);
+
+    for (partition_values, group) in file_format_groups.iter_mut() {
+        let deletes = delete_files_by_partition
+            .get(partition_values)
+            .into_iter()
+            .flatten()
+            .chain(global_delete_files.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        group.extend(deletes);
+    }
 
     // Get all partition columns
     let table_partition_cols: Vec<Field> = table
@@ -497,20 +609,190 @@ async fn table_scan(
 
     let file_schema: SchemaRef = Arc::new((file_schema.fields()).try_into().unwrap());
 
+    let name_mapping = name_mapping(&table.metadata().properties).map_err(Error::from)?;
+
+    // The projection only selects columns of the table schema
+    let projection = projection
+        .cloned()
+        .unwrap_or_else(|| (0..arrow_schema.fields().len()).collect());
+
     let file_scan_config = FileScanConfig {
-        object_store_url,
-        file_schema,
-        file_groups: file_groups.into_values().collect(),
+        object_store_url: object_store_url.clone(),
+        file_schema: file_schema.clone(),
+        file_groups,
         statistics,
-        projection: projection.cloned(),
+        projection: Some(projection.clone()),
         limit,
-        table_partition_cols,
+        table_partition_cols: table_partition_cols.clone(),
         output_ordering: vec![],
     };
 
-    ParquetFormat::default()
-        .create_physical_plan(session, file_scan_config, physical_predicate.as_ref())
-        .await
+    // Row groups are pruned with the bloom filters of the columns for equality and in predicates
+    let mut options = TableParquetOptions::default();
+    options.global.bloom_filter_on_read = true;
+    // The columns of the data files are matched to the table schema by field id
+    let schema_adapter_factory = Arc::new(IcebergSchemaAdapterFactory::new(
+        schema.fields().clone(),
+        name_mapping.clone(),
+    ));
+
+    let mut plans: Vec<Arc<dyn ExecutionPlan>> = Vec::new();
+
+    // Files with position deletes are scanned without the predicate, because pruned row groups would change the positions of the rows
+    for (delete_scan, predicate) in [
+        (equality_delete_scan, physical_predicate.clone()),
+        (position_delete_scan, None),
+    ] {
+        if delete_scan.files.is_empty() {
+            continue;
+        }
+        // The columns of the equality deletes are scanned in addition to the projected columns, followed by the path of the data file
+        let mut delete_projection = projection.clone();
+        for id in delete_scan.equality_ids() {
+            if let Some(index) = schema.fields().iter().position(|field| field.id == id) {
+                if !delete_projection.contains(&index) {
+                    delete_projection.push(index);
+                }
+            }
+        }
+        delete_projection.push(file_schema.fields().len() + table_partition_cols.len());
+        let mut partition_cols = table_partition_cols.clone();
+        partition_cols.push(Field::new(DATA_FILE_PATH_COLUMN, DataType::Utf8, false));
+
+        let config = FileScanConfig {
+            object_store_url: object_store_url.clone(),
+            file_schema: file_schema.clone(),
+            file_groups: split_config.pack(delete_scan.files, split_size),
+            // The statistics of the data files don't account for the deleted rows
+            statistics: Statistics::new_unknown(&file_schema),
+            projection: Some(delete_projection),
+            // Deleted rows must not count towards the limit
+            limit: None,
+            table_partition_cols: partition_cols,
+            output_ordering: vec![],
+        };
+        let input = Arc::new(
+            ParquetExec::new(config, predicate, None, options.clone())
+                .with_schema_adapter_factory(schema_adapter_factory.clone()),
+        );
+        plans.push(Arc::new(DeleteScanExec::try_new(
+            input,
+            delete_scan.data_files,
+            delete_scan.delete_files.into_values().collect(),
+            table.object_store(),
+            projection.len(),
+        )?));
+    }
+
+    if !file_format_groups.is_empty() {
+        plans.push(Arc::new(FileScanExec::new(
+            file_format_groups.into_values().collect(),
+            table.object_store(),
+            file_scan_config.project().0,
+            schema.fields().clone(),
+            name_mapping,
+        )));
+    }
+
+    if plans.is_empty() || !file_scan_config.file_groups.is_empty() {
+        plans.push(Arc::new(
+            ParquetExec::new(file_scan_config, physical_predicate, None, options)
+                .with_schema_adapter_factory(schema_adapter_factory),
+        ));
+    }
+
+    if plans.len() == 1 {
+        Ok(plans.remove(0))
+    } else {
+        Ok(Arc::new(UnionExec::new(plans)))
+    }
+}
+
+/// Parquet data files that are scanned together with the delete files that apply to them
+#[derive(Default)]
+struct DeleteScan {
+    files: Vec<PartitionedFile>,
+    data_files: Vec<ManifestEntry>,
+    delete_files: HashMap<String, ManifestEntry>,
+}
+
+impl DeleteScan {
+    fn add(&mut self, data_file: ManifestEntry, delete_files: Vec<ManifestEntry>) {
+        self.data_files.push(data_file);
+        self.delete_files.extend(
+            delete_files
+                .into_iter()
+                .map(|delete| (delete.data_file().file_path().clone(), delete)),
+        );
+    }
+
+    /// Field ids of the columns of the equality delete files
+    fn equality_ids(&self) -> Vec<i32> {
+        self.delete_files
+            .values()
+            .filter_map(|delete| delete.data_file().equality_ids().clone())
+            .flatten()
+            .collect()
+    }
+}
+
+/// Create the partitioned files for the byte ranges of a data file. The statistics of a data file don't describe its splits.
+fn partitioned_files(
+    object_meta: ObjectMeta,
+    partition_values: Vec<ScalarValue>,
+    statistics: Statistics,
+    ranges: Vec<Range<i64>>,
+) -> Vec<PartitionedFile> {
+    if ranges.len() <= 1 {
+        return vec![PartitionedFile {
+            object_meta,
+            partition_values,
+            range: None,
+            statistics: Some(statistics),
+            extensions: None,
+        }];
+    }
+    ranges
+        .into_iter()
+        .map(|range| PartitionedFile {
+            object_meta: object_meta.clone(),
+            partition_values: partition_values.clone(),
+            range: Some(FileRange {
+                start: range.start,
+                end: range.end,
+            }),
+            statistics: None,
+            extensions: None,
+        })
+        .collect()
+}
+
+fn partition_values(manifest: &ManifestEntry) -> Vec<ScalarValue> {
+    manifest
+        .data_file()
+        .partition()
+        .iter()
+        .map(|value| match value {
+            Some(v) => ScalarValue::Utf8(Some(serde_json::to_string(v).unwrap())),
+            None => ScalarValue::Null,
+        })
+        .collect()
+}
+
+/// End snapshot of a scan, defaults to the current snapshot of the branch
+pub(crate) fn end_snapshot(
+    table: &Table,
+    end: Option<i64>,
+    branch: Option<&str>,
+) -> Result<Option<i64>, Error> {
+    match end {
+        Some(end) => Ok(Some(end)),
+        None => Ok(table
+            .metadata()
+            .current_snapshot(branch)
+            .map_err(Error::from)?
+            .map(|snapshot| *snapshot.snapshot_id())),
+    }
 }
 
 impl DisplayAs for DataFusionTable {
@@ -523,12 +805,13 @@ impl DisplayAs for DataFusionTable {
     }
 }
 
+/// Writes data to the table. If the second field is true, the written data replaces the partitions it is written to.
 #[derive(Debug)]
-pub(crate) struct IcebergDataSink(DataFusionTable);
+pub(crate) struct IcebergDataSink(DataFusionTable, bool);
 
 impl DataFusionTable {
-    pub(crate) fn into_data_sink(self) -> IcebergDataSink {
-        IcebergDataSink(self)
+    pub(crate) fn into_data_sink(self, overwrite: bool) -> IcebergDataSink {
+        IcebergDataSink(self, overwrite)
     }
 }
 
@@ -566,12 +849,13 @@ impl DataSink for IcebergDataSink {
         )
         .await?;
 
-        table
-            .new_transaction(self.0.branch.as_deref())
-            .append(metadata_files)
-            .commit()
-            .await
-            .map_err(Into::<Error>::into)?;
+        let transaction = table.new_transaction(self.0.branch.as_deref());
+        let transaction = if self.1 {
+            transaction.overwrite(metadata_files, OverwriteMode::Dynamic)
+        } else {
+            transaction.append(metadata_files)
+        };
+        transaction.commit().await.map_err(Into::<Error>::into)?;
 
         Ok(0)
     }
@@ -585,23 +869,44 @@ mod tests {
 
     use datafusion::{
         arrow::{
-            array::{Float32Array, Int64Array},
+            array::{ArrayRef, Float32Array, Int32Array, Int64Array, StringArray},
             record_batch::RecordBatch,
         },
+        common::stats::Precision,
+        physical_plan::{collect, displayable},
         prelude::SessionContext,
     };
+    use futures::{future, stream, TryStreamExt};
     use iceberg_rust::spec::{
         partition::{PartitionField, Transform},
         schema::Schema,
         types::{PrimitiveType, StructField, StructType, Type},
     };
     use iceberg_rust::{
+        arrow::{
+            read::{read, read_tasks},
+            upsert::write_upsert,
+            write::{
+                write_equality_deletes, write_parquet_partitioned, write_position_deletes,
+                WRITE_FORMAT_DEFAULT,
+            },
+        },
         catalog::{identifier::Identifier, tabular::Tabular, Catalog},
+        error::Error as IcebergError,
+        file_format::metrics::WRITE_METADATA_METRICS_COLUMN,
         spec::{
+            manifest::{FileFormat, Status},
             partition::PartitionSpec,
+            util::strip_prefix,
+            values::Value,
             view_metadata::{Version, ViewRepresentation},
         },
-        table::Table,
+        table::{
+            incremental::{NonAppendSnapshots, StartSnapshot},
+            scan::Predicate,
+            transaction::{conflict::IsolationLevel, overwrite::OverwriteMode},
+            Table,
+        },
         view::View,
     };
     use iceberg_sql_catalog::SqlCatalog;
@@ -1378,4 +1683,2250 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    pub async fn test_datafusion_table_row_delta() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "amount".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Int),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let partition_spec = PartitionSpec::builder()
+            .build()
+            .expect("Failed to create partition spec");
+
+        let table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(partition_spec)
+            .build(&["test".to_owned()], catalog)
+            .await
+            .expect("Failed to create table");
+
+        let table = Arc::new(DataFusionTable::from(table));
+
+        let ctx = SessionContext::new();
+
+        ctx.register_table("orders", table.clone()).unwrap();
+
+        ctx.sql(
+            "INSERT INTO orders (id, amount) VALUES 
+                (1, 1),
+                (2, 2),
+                (3, 3);",
+        )
+        .await
+        .expect("Failed to create query plan for insert")
+        .collect()
+        .await
+        .expect("Failed to insert values into table");
+
+        if let Tabular::Table(table) = &mut *table.tabular.write().await {
+            let manifests = table.manifests(None, None).await.unwrap();
+            let data_files = table.datafiles(&manifests, None).await.unwrap();
+            let data_file = data_files
+                .into_iter()
+                .find(|entry| *entry.data_file().record_count() == 3)
+                .expect("Failed to find data file")
+                .data_file()
+                .clone();
+
+            let delete_files = write_position_deletes(
+                table.metadata(),
+                vec![(data_file, vec![0])],
+                table.object_store(),
+            )
+            .await
+            .expect("Failed to write position deletes");
+
+            table
+                .new_transaction(None)
+                .row_delta(Vec::new(), delete_files)
+                .commit()
+                .await
+                .expect("Failed to commit row delta");
+        };
+
+        let batches = ctx
+            .sql("select sum(amount) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let amounts = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(amounts.value(0), 5);
+
+        // The deletes are applied on top of the parquet scan of the data file
+        let plan = ctx
+            .sql("select id from orders where amount > 1 order by id;")
+            .await
+            .expect("Failed to create plan for select")
+            .create_physical_plan()
+            .await
+            .expect("Failed to create physical plan");
+        let display = displayable(plan.as_ref()).indent(true).to_string();
+        assert!(display.contains("DeleteScanExec"));
+        assert!(display.contains("ParquetExec"));
+
+        let batches = collect(plan, ctx.task_ctx())
+            .await
+            .expect("Failed to execute select query");
+        let ids = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap()
+                    .iter()
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![Some(2), Some(3)]);
+    }
+
+    #[tokio::test]
+    pub async fn test_statistics_with_delete_files() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog)
+            .await
+            .expect("Failed to create table");
+
+        let table = Arc::new(DataFusionTable::from(table));
+
+        // The equality deletes are older than the data files and don't apply to them
+        if let Tabular::Table(table) = &mut *table.tabular.write().await {
+            let deletes = RecordBatch::try_from_iter(vec![(
+                "id",
+                Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef,
+            )])
+            .unwrap();
+            let delete_files = write_equality_deletes(
+                table.metadata(),
+                stream::iter(vec![Ok(deletes)]),
+                &[1],
+                table.object_store(),
+                None,
+            )
+            .await
+            .expect("Failed to write equality deletes");
+
+            table
+                .new_transaction(None)
+                .row_delta(Vec::new(), delete_files)
+                .commit()
+                .await
+                .expect("Failed to commit row delta");
+        };
+
+        let ctx = SessionContext::new();
+
+        ctx.register_table("orders", table.clone()).unwrap();
+
+        ctx.sql("INSERT INTO orders (id) VALUES (1), (2), (3);")
+            .await
+            .expect("Failed to create query plan for insert")
+            .collect()
+            .await
+            .expect("Failed to insert values into table");
+
+        let statistics = table.statistics().await.unwrap();
+        assert_eq!(statistics.num_rows, Precision::Inexact(3));
+
+        let batches = ctx
+            .sql("select count(*) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let counts = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(counts.value(0), 3);
+    }
+
+    #[tokio::test]
+    pub async fn test_transaction_with_append_and_row_delta() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "amount".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Int),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog)
+            .await
+            .expect("Failed to create table");
+
+        let write = |table: &Table, ids: Vec<i64>, amounts: Vec<i32>| {
+            let metadata = table.metadata().clone();
+            let object_store = table.object_store();
+            async move {
+                let batch = RecordBatch::try_from_iter(vec![
+                    ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                    ("amount", Arc::new(Int32Array::from(amounts)) as ArrayRef),
+                ])
+                .unwrap();
+                write_parquet_partitioned(
+                    &metadata,
+                    stream::iter(vec![Ok(batch)]),
+                    object_store,
+                    None,
+                )
+                .await
+                .expect("Failed to write data files")
+            }
+        };
+
+        let files = write(&table, vec![1, 2, 3], vec![1, 2, 3]).await;
+        table
+            .new_transaction(None)
+            .append(files.clone())
+            .commit()
+            .await
+            .expect("Failed to commit append");
+        let first_snapshot_id = table.metadata().current_snapshot_id.unwrap();
+
+        // Append new rows and delete an existing row in the same transaction
+        let new_files = write(&table, vec![4], vec![4]).await;
+        let delete_files = write_position_deletes(
+            table.metadata(),
+            vec![(files[0].clone(), vec![0])],
+            table.object_store(),
+        )
+        .await
+        .expect("Failed to write position deletes");
+        table
+            .new_transaction(None)
+            .append(new_files)
+            .row_delta(Vec::new(), delete_files)
+            .commit()
+            .await
+            .expect("Failed to commit transaction");
+
+        // Both operations add a snapshot, the row delta builds on the snapshot of the append
+        let metadata = table.metadata();
+        assert_eq!(metadata.snapshots.len(), 3);
+        let row_delta = metadata.current_snapshot(None).unwrap().unwrap();
+        let append = &metadata.snapshots[&row_delta.parent_snapshot_id().unwrap()];
+        assert_eq!(*append.parent_snapshot_id(), Some(first_snapshot_id));
+        assert_eq!(*row_delta.sequence_number(), append.sequence_number() + 1);
+
+        let table = Arc::new(DataFusionTable::from(table));
+        let ctx = SessionContext::new();
+        ctx.register_table("orders", table).unwrap();
+
+        let batches = ctx
+            .sql("select sum(amount) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let sums = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(sums.value(0), 9);
+    }
+
+    #[tokio::test]
+    pub async fn test_datafusion_table_upsert() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_identifier_field_ids(vec![1])
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "amount".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Int),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let partition_spec = PartitionSpec::builder()
+            .build()
+            .expect("Failed to create partition spec");
+
+        let table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(partition_spec)
+            .build(&["test".to_owned()], catalog)
+            .await
+            .expect("Failed to create table");
+
+        let table = Arc::new(DataFusionTable::from(table));
+
+        let ctx = SessionContext::new();
+
+        ctx.register_table("orders", table.clone()).unwrap();
+
+        ctx.sql(
+            "INSERT INTO orders (id, amount) VALUES 
+                (1, 1),
+                (2, 2),
+                (3, 3);",
+        )
+        .await
+        .expect("Failed to create query plan for insert")
+        .collect()
+        .await
+        .expect("Failed to insert values into table");
+
+        let changes = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int64Array::from(vec![1, 2, 4, 4])) as ArrayRef,
+            ),
+            (
+                "amount",
+                Arc::new(Int32Array::from(vec![10, 0, 5, 4])) as ArrayRef,
+            ),
+            (
+                "_op",
+                Arc::new(StringArray::from(vec!["U", "D", "I", "U"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        if let Tabular::Table(table) = &mut *table.tabular.write().await {
+            let (files, delete_files) = write_upsert(
+                table.metadata(),
+                stream::iter(vec![Ok(changes)]),
+                "_op",
+                table.object_store(),
+                None,
+            )
+            .await
+            .expect("Failed to write upsert");
+
+            table
+                .new_transaction(None)
+                .upsert(files, delete_files)
+                .commit()
+                .await
+                .expect("Failed to commit upsert");
+        };
+
+        let batches = ctx
+            .sql("select sum(amount), count(id) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let amounts = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let counts = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(amounts.value(0), 17);
+        assert_eq!(counts.value(0), 3);
+    }
+
+    #[tokio::test]
+    pub async fn test_datafusion_table_insert_overwrite() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "date".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Date),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 3,
+                        name: "amount".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Int),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let partition_spec = PartitionSpec::builder()
+            .with_partition_field(PartitionField::new(2, 1000, "day", Transform::Day))
+            .build()
+            .expect("Failed to create partition spec");
+
+        let table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(partition_spec)
+            .build(&["test".to_owned()], catalog)
+            .await
+            .expect("Failed to create table");
+
+        let table = Arc::new(DataFusionTable::from(table));
+
+        let ctx = SessionContext::new();
+
+        ctx.register_table("orders", table.clone()).unwrap();
+
+        ctx.sql(
+            "INSERT INTO orders (id, date, amount) VALUES 
+                (1, '2020-01-01', 1),
+                (2, '2020-01-01', 2),
+                (3, '2020-01-02', 3);",
+        )
+        .await
+        .expect("Failed to create query plan for insert")
+        .collect()
+        .await
+        .expect("Failed to insert values into table");
+
+        // Only the partition of the new rows is replaced
+        ctx.sql(
+            "INSERT OVERWRITE orders (id, date, amount) VALUES 
+                (4, '2020-01-01', 10);",
+        )
+        .await
+        .expect("Failed to create query plan for insert overwrite")
+        .collect()
+        .await
+        .expect("Failed to overwrite values in table");
+
+        let batches = ctx
+            .sql("select sum(amount), count(id) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let amounts = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let counts = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(amounts.value(0), 13);
+        assert_eq!(counts.value(0), 2);
+
+        // The replaced data file is tracked as deleted in the manifests but isn't returned as a data file of the table
+        if let Tabular::Table(table) = &*table.tabular.read().await {
+            let manifests = table.manifests(None, None).await.unwrap();
+            let data_files = table.datafiles(&manifests, None).await.unwrap();
+            assert_eq!(data_files.len(), 2);
+            assert!(data_files
+                .iter()
+                .all(|entry| !matches!(entry.status(), Status::Deleted)));
+            assert_eq!(
+                data_files
+                    .iter()
+                    .map(|entry| *entry.data_file().record_count())
+                    .sum::<i64>(),
+                2
+            );
+        } else {
+            panic!("Tabular is not a table");
+        }
+    }
+
+    #[tokio::test]
+    pub async fn test_remove_orphan_files() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        let table = Arc::new(DataFusionTable::from(table));
+
+        let ctx = SessionContext::new();
+
+        ctx.register_table("orders", table.clone()).unwrap();
+
+        ctx.sql("INSERT INTO orders (id) VALUES (1), (2), (3);")
+            .await
+            .expect("Failed to create query plan for insert")
+            .collect()
+            .await
+            .expect("Failed to insert values into table");
+
+        object_store
+            .put(
+                &"test/orders/data/orphan.parquet".into(),
+                vec![0u8; 8].into(),
+            )
+            .await
+            .unwrap();
+
+        if let Tabular::Table(table) = &*table.tabular.read().await {
+            // Files younger than the threshold are kept
+            let orphans = table.remove_orphan_files(None, true).await.unwrap();
+            assert!(orphans.is_empty());
+
+            // The metadata file of the table creation is part of the metadata log
+            let orphans = table
+                .remove_orphan_files(Some(i64::MAX), true)
+                .await
+                .unwrap();
+            assert_eq!(orphans, vec!["test/orders/data/orphan.parquet".to_owned()]);
+
+            table
+                .remove_orphan_files(Some(i64::MAX), false)
+                .await
+                .unwrap();
+            assert!(object_store
+                .head(&"test/orders/data/orphan.parquet".into())
+                .await
+                .is_err());
+
+            let metadata_log = &table.metadata().metadata_log;
+            assert_eq!(metadata_log.len(), 1);
+            assert!(metadata_log[0]
+                .metadata_file
+                .starts_with("/test/orders/metadata/0-"));
+            assert!(object_store
+                .head(&strip_prefix(&metadata_log[0].metadata_file).into())
+                .await
+                .is_ok());
+        };
+
+        // The current metadata file is kept
+        catalog
+            .clone()
+            .load_tabular(&Identifier::parse("test.orders").unwrap())
+            .await
+            .expect("Failed to load table");
+
+        let batches = ctx
+            .sql("select sum(id) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let sums = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(sums.value(0), 6);
+    }
+
+    #[tokio::test]
+    pub async fn test_rewrite_manifests() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "date".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Date),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let partition_spec = PartitionSpec::builder()
+            .with_partition_field(PartitionField::new(2, 1000, "day", Transform::Day))
+            .build()
+            .expect("Failed to create partition spec");
+
+        let table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(partition_spec)
+            .build(&["test".to_owned()], catalog)
+            .await
+            .expect("Failed to create table");
+
+        let table = Arc::new(DataFusionTable::from(table));
+
+        let ctx = SessionContext::new();
+
+        ctx.register_table("orders", table.clone()).unwrap();
+
+        for values in [
+            "(1, '2020-01-01'), (2, '2020-01-02')",
+            "(3, '2020-01-03'), (4, '2020-01-01')",
+        ] {
+            ctx.sql(&format!("INSERT INTO orders (id, date) VALUES {};", values))
+                .await
+                .expect("Failed to create query plan for insert")
+                .collect()
+                .await
+                .expect("Failed to insert values into table");
+        }
+
+        if let Tabular::Table(table) = &mut *table.tabular.write().await {
+            // Every manifest can only hold a single entry
+            table
+                .new_transaction(None)
+                .update_properties(vec![(
+                    "commit.manifest.target-size-bytes".to_owned(),
+                    "1".to_owned(),
+                )])
+                .commit()
+                .await
+                .unwrap();
+
+            table
+                .new_transaction(None)
+                .rewrite_manifests()
+                .commit()
+                .await
+                .unwrap();
+
+            let manifests = table.manifests(None, None).await.unwrap();
+            assert_eq!(manifests.len(), 4);
+            for manifest in manifests {
+                let partitions = manifest.partitions.unwrap();
+                assert_eq!(partitions[0].lower_bound, partitions[0].upper_bound);
+                assert_eq!(manifest.existing_files_count, Some(1));
+            }
+        };
+
+        let batches = ctx
+            .sql("select sum(id) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let sums = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(sums.value(0), 10);
+    }
+
+    #[tokio::test]
+    pub async fn test_cherry_pick_and_rollback() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog)
+            .await
+            .expect("Failed to create table");
+
+        let table = Arc::new(DataFusionTable::from(table));
+
+        let ctx = SessionContext::new();
+
+        ctx.register_table("orders", table.clone()).unwrap();
+
+        ctx.sql("INSERT INTO orders (id) VALUES (1), (2), (3);")
+            .await
+            .expect("Failed to create query plan for insert")
+            .collect()
+            .await
+            .expect("Failed to insert values into table");
+
+        let (first_snapshot_id, staged_snapshot_id) =
+            if let Tabular::Table(table) = &mut *table.tabular.write().await {
+                let first_snapshot_id = *table
+                    .metadata()
+                    .current_snapshot(None)
+                    .unwrap()
+                    .unwrap()
+                    .snapshot_id();
+
+                table
+                    .new_transaction(None)
+                    .create_branch("staging", first_snapshot_id, None, None, None)
+                    .commit()
+                    .await
+                    .expect("Failed to create branch");
+
+                let batch = RecordBatch::try_from_iter(vec![(
+                    "id",
+                    Arc::new(Int64Array::from(vec![10])) as ArrayRef,
+                )])
+                .unwrap();
+
+                let files = write_parquet_partitioned(
+                    table.metadata(),
+                    stream::iter(vec![Ok(batch)]),
+                    table.object_store(),
+                    Some("staging"),
+                )
+                .await
+                .expect("Failed to write data files");
+
+                table
+                    .new_transaction(Some("staging"))
+                    .append(files)
+                    .commit()
+                    .await
+                    .expect("Failed to append to branch");
+
+                let staged_snapshot_id = *table
+                    .metadata()
+                    .current_snapshot(Some("staging"))
+                    .unwrap()
+                    .unwrap()
+                    .snapshot_id();
+
+                (first_snapshot_id, staged_snapshot_id)
+            } else {
+                panic!("Expected a table")
+            };
+
+        ctx.sql("INSERT INTO orders (id) VALUES (4);")
+            .await
+            .expect("Failed to create query plan for insert")
+            .collect()
+            .await
+            .expect("Failed to insert values into table");
+
+        let sum = |ctx: SessionContext| async move {
+            let batches = ctx
+                .sql("select sum(id) from orders;")
+                .await
+                .expect("Failed to create plan for select")
+                .collect()
+                .await
+                .expect("Failed to execute select query");
+
+            let batch = batches
+                .into_iter()
+                .find(|batch| batch.num_rows() > 0)
+                .expect("All record batches are empty");
+
+            batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .value(0)
+        };
+
+        if let Tabular::Table(table) = &mut *table.tabular.write().await {
+            table
+                .new_transaction(None)
+                .cherry_pick(staged_snapshot_id)
+                .commit()
+                .await
+                .expect("Failed to cherry-pick snapshot");
+
+            // A snapshot can only be cherry-picked once
+            assert!(table
+                .new_transaction(None)
+                .cherry_pick(staged_snapshot_id)
+                .commit()
+                .await
+                .is_err());
+        };
+
+        assert_eq!(sum(ctx.clone()).await, 20);
+
+        if let Tabular::Table(table) = &mut *table.tabular.write().await {
+            // The staged snapshot is not an ancestor of the main branch
+            assert!(table
+                .new_transaction(None)
+                .rollback_to(staged_snapshot_id)
+                .commit()
+                .await
+                .is_err());
+
+            table
+                .new_transaction(None)
+                .rollback_to(first_snapshot_id)
+                .commit()
+                .await
+                .expect("Failed to roll back");
+        };
+
+        assert_eq!(sum(ctx).await, 6);
+    }
+
+    #[tokio::test]
+    pub async fn test_concurrent_commits() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from(vec![4])) as ArrayRef,
+        )])
+        .unwrap();
+        let files = write_parquet_partitioned(
+            table.metadata(),
+            stream::iter(vec![Ok(batch)]),
+            table.object_store(),
+            None,
+        )
+        .await
+        .expect("Failed to write data files");
+        table
+            .new_transaction(None)
+            .append(files)
+            .commit()
+            .await
+            .expect("Failed to commit append");
+
+        let identifier = Identifier::parse("test.orders").unwrap();
+
+        // Two writers that load the table before either of them commits
+        let mut writers = Vec::new();
+        for ids in [vec![1, 2], vec![3]] {
+            let Tabular::Table(writer) = catalog.clone().load_tabular(&identifier).await.unwrap()
+            else {
+                panic!("Expected a table")
+            };
+            let batch = RecordBatch::try_from_iter(vec![(
+                "id",
+                Arc::new(Int64Array::from(ids)) as ArrayRef,
+            )])
+            .unwrap();
+            let files = write_parquet_partitioned(
+                writer.metadata(),
+                stream::iter(vec![Ok(batch)]),
+                writer.object_store(),
+                None,
+            )
+            .await
+            .expect("Failed to write data files");
+            writers.push((writer, files));
+        }
+
+        for (mut writer, files) in writers {
+            writer
+                .new_transaction(None)
+                .append(files)
+                .commit()
+                .await
+                .expect("Failed to commit append");
+        }
+
+        // The overwrite would remove the files that were appended concurrently
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from(vec![10])) as ArrayRef,
+        )])
+        .unwrap();
+        let files = write_parquet_partitioned(
+            table.metadata(),
+            stream::iter(vec![Ok(batch)]),
+            table.object_store(),
+            None,
+        )
+        .await
+        .expect("Failed to write data files");
+        let result = table
+            .new_transaction(None)
+            .overwrite(files, OverwriteMode::Dynamic)
+            .commit()
+            .await;
+        assert!(matches!(result, Err(IcebergError::CommitConflict(_))));
+
+        let Tabular::Table(table) = catalog.clone().load_tabular(&identifier).await.unwrap() else {
+            panic!("Expected a table")
+        };
+
+        // The manifest list of the conflicting attempt was removed
+        let manifest_lists = object_store
+            .list(Some(&"test/orders/metadata".into()))
+            .try_filter(|file| {
+                future::ready(
+                    file.location
+                        .filename()
+                        .is_some_and(|name| name.starts_with("snap-")),
+                )
+            })
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(manifest_lists.len(), table.metadata().snapshots.len());
+
+        let ctx = SessionContext::new();
+
+        ctx.register_table("orders", Arc::new(DataFusionTable::from(table)))
+            .unwrap();
+
+        let batches = ctx
+            .sql("select sum(id) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let sums = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(sums.value(0), 10);
+    }
+
+    #[tokio::test]
+    pub async fn test_isolation_levels() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        let identifier = Identifier::parse("test.orders").unwrap();
+
+        let write = |table: &Table, ids: Vec<i64>| {
+            let metadata = table.metadata().clone();
+            let object_store = table.object_store();
+            async move {
+                let batch = RecordBatch::try_from_iter(vec![(
+                    "id",
+                    Arc::new(Int64Array::from(ids)) as ArrayRef,
+                )])
+                .unwrap();
+                write_parquet_partitioned(
+                    &metadata,
+                    stream::iter(vec![Ok(batch)]),
+                    object_store,
+                    None,
+                )
+                .await
+                .expect("Failed to write data files")
+            }
+        };
+
+        let files = write(&table, vec![1]).await;
+        table
+            .new_transaction(None)
+            .append(files)
+            .commit()
+            .await
+            .expect("Failed to commit append");
+
+        let Tabular::Table(mut writer) = catalog.clone().load_tabular(&identifier).await.unwrap()
+        else {
+            panic!("Expected a table")
+        };
+        let files = write(&writer, vec![2]).await;
+        writer
+            .new_transaction(None)
+            .append(files)
+            .commit()
+            .await
+            .expect("Failed to commit append");
+
+        // With snapshot isolation the concurrently appended file is overwritten as well
+        let files = write(&table, vec![10]).await;
+        table
+            .new_transaction(None)
+            .isolation_level(IsolationLevel::Snapshot)
+            .overwrite(files, OverwriteMode::Dynamic)
+            .commit()
+            .await
+            .expect("Failed to commit overwrite");
+
+        let manifests = table.manifests(None, None).await.unwrap();
+        let data_files = table.datafiles(&manifests, None).await.unwrap();
+        assert_eq!(data_files.len(), 1);
+        let data_file = data_files[0].data_file().clone();
+
+        // A delete that is committed while the data file is rewritten conflicts with the rewrite
+        let Tabular::Table(mut writer) = catalog.clone().load_tabular(&identifier).await.unwrap()
+        else {
+            panic!("Expected a table")
+        };
+        let delete_files = write_position_deletes(
+            writer.metadata(),
+            vec![(data_file.clone(), vec![0])],
+            writer.object_store(),
+        )
+        .await
+        .expect("Failed to write position deletes");
+        writer
+            .new_transaction(None)
+            .row_delta(Vec::new(), delete_files)
+            .commit()
+            .await
+            .expect("Failed to commit row delta");
+
+        let files = write(&table, vec![10]).await;
+        let result = table
+            .new_transaction(None)
+            .rewrite_files(vec![data_file], files)
+            .commit()
+            .await;
+        assert!(matches!(result, Err(IcebergError::CommitConflict(_))));
+
+        // Position deletes only conflict with concurrent deletes of the same data files
+        let Tabular::Table(mut table) = catalog.clone().load_tabular(&identifier).await.unwrap()
+        else {
+            panic!("Expected a table")
+        };
+        let first = write(&table, vec![20]).await;
+        let second = write(&table, vec![30]).await;
+        table
+            .new_transaction(None)
+            .append(first.iter().chain(second.iter()).cloned().collect())
+            .commit()
+            .await
+            .expect("Failed to commit append");
+
+        let Tabular::Table(mut writer) = catalog.clone().load_tabular(&identifier).await.unwrap()
+        else {
+            panic!("Expected a table")
+        };
+        let files = write(&writer, vec![40]).await;
+        let delete_files = write_position_deletes(
+            writer.metadata(),
+            vec![(second[0].clone(), vec![0])],
+            writer.object_store(),
+        )
+        .await
+        .expect("Failed to write position deletes");
+        writer
+            .new_transaction(None)
+            .row_delta(files, delete_files)
+            .commit()
+            .await
+            .expect("Failed to commit row delta");
+
+        let delete_files = write_position_deletes(
+            table.metadata(),
+            vec![(first[0].clone(), vec![0])],
+            table.object_store(),
+        )
+        .await
+        .expect("Failed to write position deletes");
+        table
+            .new_transaction(None)
+            .row_delta(Vec::new(), delete_files)
+            .commit()
+            .await
+            .expect("Failed to commit row delta");
+
+        let delete_files = write_position_deletes(
+            writer.metadata(),
+            vec![(first[0].clone(), vec![0])],
+            writer.object_store(),
+        )
+        .await
+        .expect("Failed to write position deletes");
+        let result = writer
+            .new_transaction(None)
+            .row_delta(Vec::new(), delete_files)
+            .commit()
+            .await;
+        assert!(matches!(result, Err(IcebergError::CommitConflict(_))));
+    }
+
+    #[tokio::test]
+    pub async fn test_incremental_append_scan() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        let mut snapshot_ids = Vec::new();
+        for ids in [vec![1], vec![2, 3], vec![4]] {
+            let batch = RecordBatch::try_from_iter(vec![(
+                "id",
+                Arc::new(Int64Array::from(ids)) as ArrayRef,
+            )])
+            .unwrap();
+            let files = write_parquet_partitioned(
+                table.metadata(),
+                stream::iter(vec![Ok(batch)]),
+                table.object_store(),
+                None,
+            )
+            .await
+            .expect("Failed to write data files");
+            table
+                .new_transaction(None)
+                .append(files)
+                .commit()
+                .await
+                .expect("Failed to commit append");
+            snapshot_ids.push(table.metadata().current_snapshot_id.unwrap());
+        }
+
+        // The arrow reader consumes the files of the second and third append
+        let files = table
+            .incremental_append_files(
+                Some(StartSnapshot::Inclusive(snapshot_ids[1])),
+                None,
+                NonAppendSnapshots::Fail,
+            )
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 2);
+        let batches: Vec<RecordBatch> = read(files.into_iter(), table.object_store())
+            .await
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            batches.iter().map(|batch| batch.num_rows()).sum::<usize>(),
+            3
+        );
+
+        // The start snapshot of the range is excluded
+        let identifier = Identifier::parse("test.orders").unwrap();
+        let Tabular::Table(loaded) = catalog.clone().load_tabular(&identifier).await.unwrap()
+        else {
+            panic!("Expected a table")
+        };
+        let ctx = SessionContext::new();
+        ctx.register_table(
+            "orders",
+            Arc::new(DataFusionTable::new_incremental_append(
+                loaded,
+                snapshot_ids[0],
+                Some(snapshot_ids[1]),
+                None,
+            )),
+        )
+        .unwrap();
+
+        let batches = ctx
+            .sql("select sum(id) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let sums = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(sums.value(0), 5);
+
+        // Overwrite snapshots fail the scan unless they are skipped
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from(vec![10])) as ArrayRef,
+        )])
+        .unwrap();
+        let files = write_parquet_partitioned(
+            table.metadata(),
+            stream::iter(vec![Ok(batch)]),
+            table.object_store(),
+            None,
+        )
+        .await
+        .expect("Failed to write data files");
+        table
+            .new_transaction(None)
+            .overwrite(files, OverwriteMode::Dynamic)
+            .commit()
+            .await
+            .expect("Failed to commit overwrite");
+
+        let start = Some(StartSnapshot::Exclusive(snapshot_ids[2]));
+        assert!(matches!(
+            table
+                .incremental_append_files(start, None, NonAppendSnapshots::Fail)
+                .await,
+            Err(IcebergError::NotSupported(_))
+        ));
+        assert!(table
+            .incremental_append_files(start, None, NonAppendSnapshots::Skip)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // A snapshot range scan reads the changes of the overwrite, an incremental append scan fails
+        let Tabular::Table(loaded) = catalog.clone().load_tabular(&identifier).await.unwrap()
+        else {
+            panic!("Expected a table")
+        };
+        let ctx = SessionContext::new();
+        ctx.register_table(
+            "orders",
+            Arc::new(DataFusionTable::new_table(
+                loaded,
+                Some(snapshot_ids[2]),
+                None,
+                None,
+            )),
+        )
+        .unwrap();
+        let Tabular::Table(loaded) = catalog.clone().load_tabular(&identifier).await.unwrap()
+        else {
+            panic!("Expected a table")
+        };
+        ctx.register_table(
+            "appended_orders",
+            Arc::new(DataFusionTable::new_incremental_append(
+                loaded,
+                snapshot_ids[2],
+                None,
+                None,
+            )),
+        )
+        .unwrap();
+
+        let batches = ctx
+            .sql("select sum(id) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let sums = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(sums.value(0), 10);
+
+        assert!(ctx
+            .sql("select sum(id) from appended_orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    pub async fn test_time_travel() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        let mut timestamps = Vec::new();
+        for ids in [vec![1], vec![2, 3]] {
+            // Snapshots are logged with a millisecond precision
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            let batch = RecordBatch::try_from_iter(vec![(
+                "id",
+                Arc::new(Int64Array::from(ids)) as ArrayRef,
+            )])
+            .unwrap();
+            let files = write_parquet_partitioned(
+                table.metadata(),
+                stream::iter(vec![Ok(batch)]),
+                table.object_store(),
+                None,
+            )
+            .await
+            .expect("Failed to write data files");
+            table
+                .new_transaction(None)
+                .append(files)
+                .commit()
+                .await
+                .expect("Failed to commit append");
+            timestamps.push(table.metadata().snapshot_log.last().unwrap().timestamp_ms);
+        }
+
+        assert!(table
+            .snapshot_as_of(timestamps[0] - 1, None)
+            .unwrap()
+            .is_none());
+
+        let ctx = SessionContext::new();
+        ctx.register_table(
+            "orders",
+            Arc::new(
+                DataFusionTable::new_as_of(Tabular::Table(table), timestamps[1] - 1, None).unwrap(),
+            ),
+        )
+        .unwrap();
+
+        let batches = ctx
+            .sql("select sum(id) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let sums = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(sums.value(0), 1);
+    }
+
+    #[tokio::test]
+    pub async fn test_table_scan() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "name".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::String),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        let mut snapshot_ids = Vec::new();
+        let mut data_files = Vec::new();
+        for (ids, names) in [
+            (vec![1, 2, 3], vec!["a", "b", "c"]),
+            (vec![4, 5, 6], vec!["d", "e", "f"]),
+        ] {
+            let batch = RecordBatch::try_from_iter(vec![
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("name", Arc::new(StringArray::from(names)) as ArrayRef),
+            ])
+            .unwrap();
+            let files = write_parquet_partitioned(
+                table.metadata(),
+                stream::iter(vec![Ok(batch)]),
+                table.object_store(),
+                None,
+            )
+            .await
+            .expect("Failed to write data files");
+            data_files.extend(files.iter().cloned());
+            table
+                .new_transaction(None)
+                .append(files)
+                .commit()
+                .await
+                .expect("Failed to commit append");
+            snapshot_ids.push(table.metadata().current_snapshot_id.unwrap());
+        }
+
+        // Delete the row with id 4
+        let delete_files = write_position_deletes(
+            table.metadata(),
+            vec![(data_files[1].clone(), vec![0])],
+            table.object_store(),
+        )
+        .await
+        .expect("Failed to write position deletes");
+        table
+            .new_transaction(None)
+            .row_delta(Vec::new(), delete_files)
+            .commit()
+            .await
+            .expect("Failed to commit row delta");
+
+        // The column metrics prune the first data file
+        let tasks = table
+            .scan()
+            .select(["NAME"])
+            .filter(Predicate::GtEq("ID".to_owned(), Value::LongInt(4)))
+            .filter(Predicate::Lt("id".to_owned(), Value::LongInt(6)))
+            .case_sensitive(false)
+            .plan_files()
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].deletes.len(), 1);
+        assert_eq!(
+            tasks[0].residual,
+            vec![Predicate::Lt("id".to_owned(), Value::LongInt(6))]
+        );
+
+        let batches: Vec<RecordBatch> = read_tasks(tasks, table.object_store())
+            .await
+            .try_collect()
+            .await
+            .unwrap();
+        let names = batches
+            .iter()
+            .flat_map(|batch| {
+                assert_eq!(batch.num_columns(), 1);
+                batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .unwrap()
+                    .iter()
+                    .map(|name| name.unwrap().to_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["e".to_owned()]);
+
+        // Column names are case sensitive by default
+        let result = table
+            .scan()
+            .filter(Predicate::IsNull("ID".to_owned()))
+            .plan_files()
+            .await;
+        assert!(matches!(result, Err(IcebergError::NotFound(_, _))));
+
+        // Older snapshots only contain the first data file
+        let tasks = table
+            .scan()
+            .snapshot_id(snapshot_ids[0])
+            .plan_files()
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert!(tasks[0].deletes.is_empty());
+        assert!(tasks[0].residual.is_empty());
+    }
+
+    #[tokio::test]
+    pub async fn test_orc_data_files() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        table
+            .new_transaction(None)
+            .update_properties(vec![(WRITE_FORMAT_DEFAULT.to_owned(), "orc".to_owned())])
+            .commit()
+            .await
+            .unwrap();
+
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])
+        .unwrap();
+        let files = write_parquet_partitioned(
+            table.metadata(),
+            stream::iter(vec![Ok(batch)]),
+            table.object_store(),
+            None,
+        )
+        .await
+        .expect("Failed to write data files");
+        assert_eq!(files.len(), 1);
+        assert_eq!(*files[0].file_format(), FileFormat::Orc);
+        assert_eq!(*files[0].record_count(), 3);
+        assert_eq!(
+            files[0].lower_bounds().as_ref().unwrap()[&1],
+            Value::LongInt(1)
+        );
+        assert_eq!(
+            files[0].upper_bounds().as_ref().unwrap()[&1],
+            Value::LongInt(3)
+        );
+        table
+            .new_transaction(None)
+            .append(files)
+            .commit()
+            .await
+            .expect("Failed to commit append");
+
+        let identifier = Identifier::parse("test.orders").unwrap();
+        let Tabular::Table(loaded) = catalog.clone().load_tabular(&identifier).await.unwrap()
+        else {
+            panic!("Expected a table")
+        };
+        let ctx = SessionContext::new();
+        ctx.register_table(
+            "orders",
+            Arc::new(DataFusionTable::new_table(loaded, None, None, None)),
+        )
+        .unwrap();
+
+        let batches = ctx
+            .sql("select sum(id) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let sums = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(sums.value(0), 6);
+    }
+
+    #[tokio::test]
+    pub async fn test_avro_data_files() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        table
+            .new_transaction(None)
+            .update_properties(vec![(WRITE_FORMAT_DEFAULT.to_owned(), "avro".to_owned())])
+            .commit()
+            .await
+            .unwrap();
+
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from(vec![Some(1), None, Some(3)])) as ArrayRef,
+        )])
+        .unwrap();
+        let files = write_parquet_partitioned(
+            table.metadata(),
+            stream::iter(vec![Ok(batch)]),
+            table.object_store(),
+            None,
+        )
+        .await
+        .expect("Failed to write data files");
+        assert_eq!(files.len(), 1);
+        assert_eq!(*files[0].file_format(), FileFormat::Avro);
+        assert_eq!(*files[0].record_count(), 3);
+        assert_eq!(files[0].null_value_counts().as_ref().unwrap()[&1], 1);
+        assert_eq!(
+            files[0].lower_bounds().as_ref().unwrap()[&1],
+            Value::LongInt(1)
+        );
+        assert_eq!(
+            files[0].upper_bounds().as_ref().unwrap()[&1],
+            Value::LongInt(3)
+        );
+        table
+            .new_transaction(None)
+            .append(files)
+            .commit()
+            .await
+            .expect("Failed to commit append");
+
+        let identifier = Identifier::parse("test.orders").unwrap();
+        let Tabular::Table(loaded) = catalog.clone().load_tabular(&identifier).await.unwrap()
+        else {
+            panic!("Expected a table")
+        };
+        let ctx = SessionContext::new();
+        ctx.register_table(
+            "orders",
+            Arc::new(DataFusionTable::new_table(loaded, None, None, None)),
+        )
+        .unwrap();
+
+        let batches = ctx
+            .sql("select sum(id) from orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let sums = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(sums.value(0), 4);
+    }
+
+    #[tokio::test]
+    pub async fn test_metrics_modes() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "name".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::String),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        table
+            .new_transaction(None)
+            .update_properties(vec![
+                (
+                    WRITE_METADATA_METRICS_COLUMN.to_owned() + "id",
+                    "none".to_owned(),
+                ),
+                (
+                    WRITE_METADATA_METRICS_COLUMN.to_owned() + "name",
+                    "truncate(2)".to_owned(),
+                ),
+            ])
+            .commit()
+            .await
+            .unwrap();
+
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "name",
+                Arc::new(StringArray::from(vec!["apple", "banana"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let files = write_parquet_partitioned(
+            table.metadata(),
+            stream::iter(vec![Ok(batch)]),
+            table.object_store(),
+            None,
+        )
+        .await
+        .expect("Failed to write data files");
+
+        assert_eq!(files.len(), 1);
+        let value_counts = files[0].value_counts().as_ref().unwrap();
+        assert!(!value_counts.contains_key(&1));
+        assert_eq!(value_counts[&2], 2);
+        assert!(!files[0].lower_bounds().as_ref().unwrap().contains_key(&1));
+        assert_eq!(
+            files[0].lower_bounds().as_ref().unwrap()[&2],
+            Value::String("ap".to_owned())
+        );
+        assert_eq!(
+            files[0].upper_bounds().as_ref().unwrap()[&2],
+            Value::String("bb".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    pub async fn test_schema_evolution() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(
+            SqlCatalog::new("sqlite://", "test", object_store.clone())
+                .await
+                .unwrap(),
+        );
+
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Int),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "name".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::String),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let table = Table::builder()
+            .with_name("orders")
+            .with_location("/test/orders")
+            .with_schema(schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "name",
+                Arc::new(StringArray::from(vec!["apple", "banana"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let files = write_parquet_partitioned(
+            table.metadata(),
+            stream::iter(vec![Ok(batch)]),
+            table.object_store(),
+            None,
+        )
+        .await
+        .expect("Failed to write data files");
+
+        // The column "id" is promoted to long, "name" is renamed to "customer" and the column "amount" is added
+        let evolved_schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 3,
+                        name: "amount".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Double),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "customer".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::String),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut evolved = Table::builder()
+            .with_name("evolved_orders")
+            .with_location("/test/evolved_orders")
+            .with_schema(evolved_schema)
+            .with_partition_spec(PartitionSpec::builder().build().unwrap())
+            .build(&["test".to_owned()], catalog.clone())
+            .await
+            .expect("Failed to create table");
+
+        evolved
+            .new_transaction(None)
+            .append(files)
+            .commit()
+            .await
+            .expect("Failed to commit append");
+
+        let ctx = SessionContext::new();
+        ctx.register_table(
+            "evolved_orders",
+            Arc::new(DataFusionTable::new_table(evolved, None, None, None)),
+        )
+        .unwrap();
+
+        let batches = ctx
+            .sql("select sum(id), count(amount), max(customer) from evolved_orders;")
+            .await
+            .expect("Failed to create plan for select")
+            .collect()
+            .await
+            .expect("Failed to execute select query");
+
+        let batch = batches
+            .into_iter()
+            .find(|batch| batch.num_rows() > 0)
+            .expect("All record batches are empty");
+
+        let sums = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(sums.value(0), 3);
+        let counts = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(counts.value(0), 0);
+        let customers = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(customers.value(0), "banana");
+    }
 }
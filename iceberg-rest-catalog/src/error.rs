@@ -13,6 +13,9 @@ impl<T> Into<Error> for apis::Error<T> {
             apis::Error::ReqwestMiddleware(err) => Error::InvalidFormat(err.to_string()),
             apis::Error::Serde(err) => Error::JSONSerde(err),
             apis::Error::Io(err) => Error::IO(err),
+            apis::Error::ResponseError(err) if err.status == reqwest::StatusCode::CONFLICT => {
+                Error::CommitConflict(err.content)
+            }
             apis::Error::ResponseError(err) => Error::InvalidFormat(format!(
                 "Response status: {}, Response content: {}",
                 err.status, err.content
@@ -13,6 +13,7 @@ use serde_repr::{Deserialize_repr, Serialize_repr};
 use crate::error::Error;
 
 use super::{
+    manifest_list::ManifestListEntry,
     partition::{PartitionField, PartitionSpec},
     schema::Schema,
     table_metadata::FormatVersion,
@@ -44,6 +45,16 @@ impl ManifestEntry {
     pub fn builder() -> ManifestEntryBuilder {
         ManifestEntryBuilder::default()
     }
+
+    /// Set the snapshot id and the sequence number from the manifest that contains the entry if they are null.
+    pub fn inherit(&mut self, manifest: &ManifestListEntry) {
+        if self.snapshot_id.is_none() {
+            self.snapshot_id = Some(manifest.added_snapshot_id);
+        }
+        if self.sequence_number.is_none() {
+            self.sequence_number = Some(manifest.sequence_number);
+        }
+    }
 }
 
 impl ManifestEntry {
@@ -443,22 +454,30 @@ impl<'de, T: Serialize + DeserializeOwned + Clone> Deserialize<'de> for AvroMap<
     }
 }
 
+/// Field id of the reserved file_path column of position delete files
+static DELETE_FILE_PATH_FIELD_ID: i32 = 2147483546;
+/// Field id of the reserved pos column of position delete files
+static DELETE_POS_FIELD_ID: i32 = 2147483545;
+
 impl AvroMap<ByteBuf> {
+    /// Bounds for columns that are not part of the schema, like dropped columns, are skipped.
+    /// The bounds of the reserved columns of position delete files are kept to match delete files to data files.
     fn into_value_map(self, schema: &StructType) -> Result<HashMap<i32, Value>, Error> {
         Ok(HashMap::from_iter(
             self.0
                 .into_iter()
-                .map(|(k, v)| {
-                    Ok((
-                        k,
-                        Value::try_from_bytes(
-                            &v,
-                            &schema
-                                .get(k as usize)
-                                .ok_or(Error::Schema(k.to_string(), format!("{:?}", schema)))?
-                                .field_type,
-                        )?,
-                    ))
+                .filter_map(|(k, v)| {
+                    let value = match schema.get(k as usize) {
+                        Some(field) => Value::try_from_bytes(&v, &field.field_type),
+                        None if k == DELETE_FILE_PATH_FIELD_ID => {
+                            Value::try_from_bytes(&v, &Type::Primitive(PrimitiveType::String))
+                        }
+                        None if k == DELETE_POS_FIELD_ID => {
+                            Value::try_from_bytes(&v, &Type::Primitive(PrimitiveType::Long))
+                        }
+                        None => return None,
+                    };
+                    Some(value.map(|value| (k, value)))
                 })
                 .collect::<Result<Vec<_>, Error>>()?,
         ))
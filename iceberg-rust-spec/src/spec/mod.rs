@@ -4,6 +4,7 @@
 pub mod manifest;
 pub mod manifest_list;
 pub mod materialized_view_metadata;
+pub mod name_mapping;
 pub mod partition;
 pub mod schema;
 pub mod snapshot;
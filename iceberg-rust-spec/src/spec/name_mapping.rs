@@ -0,0 +1,86 @@
+/*!
+ * Name mappings that assign field ids to the columns of data files that were written without field ids
+*/
+use std::str;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "kebab-case")]
+/// Maps the names of a column to a field id
+pub struct MappedField {
+    /// Field id of the column, columns without a field id are not projected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field_id: Option<i32>,
+    /// Names of the column, current and former names
+    pub names: Vec<String>,
+    /// Mappings of the nested fields of a struct, list or map column. List elements are named `element`, map keys and values `key` and `value`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<MappedField>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+#[serde(transparent)]
+/// Mapping of the top-level column names of data files to field ids
+pub struct NameMapping(pub Vec<MappedField>);
+
+impl NameMapping {
+    /// Find the mapping of a column by one of its names
+    pub fn find(&self, name: &str) -> Option<&MappedField> {
+        find(&self.0, name)
+    }
+}
+
+impl MappedField {
+    /// Find the mapping of a nested field by one of its names
+    pub fn find(&self, name: &str) -> Option<&MappedField> {
+        find(&self.fields, name)
+    }
+}
+
+fn find<'mapping>(fields: &'mapping [MappedField], name: &str) -> Option<&'mapping MappedField> {
+    fields
+        .iter()
+        .find(|field| field.names.iter().any(|x| x == name))
+}
+
+impl str::FromStr for NameMapping {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NameMapping;
+
+    #[test]
+    fn test_name_mapping() {
+        let mapping: NameMapping = r#"[
+            { "field-id": 1, "names": ["id", "record_id"] },
+            { "field-id": 2, "names": ["data"] },
+            { "field-id": 3, "names": ["location"], "fields": [
+                { "field-id": 4, "names": ["latitude", "lat"] },
+                { "field-id": 5, "names": ["longitude", "long"] }
+            ] },
+            { "names": ["unmapped"] }
+        ]"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(mapping.find("record_id").unwrap().field_id, Some(1));
+        assert_eq!(
+            mapping
+                .find("location")
+                .and_then(|field| field.find("long"))
+                .unwrap()
+                .field_id,
+            Some(5)
+        );
+        assert_eq!(mapping.find("unmapped").unwrap().field_id, None);
+        assert!(mapping.find("missing").is_none());
+    }
+}
@@ -4,9 +4,12 @@
 
 use std::{
     fmt::{self, Display},
+    io::Cursor,
     str,
 };
 
+use chrono::{Datelike, NaiveDate};
+
 use derive_getters::Getters;
 use serde::{
     de::{Error as SerdeError, IntoDeserializer},
@@ -131,6 +134,60 @@ impl Display for Transform {
     }
 }
 
+/// Microseconds per hour, the unit of the hour transform
+pub const MICROS_PER_HOUR: i64 = 3_600_000_000;
+/// Microseconds per day, the unit of the day transform
+pub const MICROS_PER_DAY: i64 = 86_400_000_000;
+/// Days from 0001-01-01 to 1970-01-01
+const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// Bucket of the 32-bit murmur3 hash of the bytes, which is `(hash & Integer.MAX_VALUE) % n` as required by the bucket transform
+pub fn bucket(bytes: &[u8], n: u32) -> i32 {
+    // Reading from a slice doesn't fail
+    let hash = murmur3::murmur3_32(&mut Cursor::new(bytes), 0).unwrap();
+    ((hash & i32::MAX as u32) % n) as i32
+}
+
+/// Unscaled value of a decimal as two's-complement big-endian bytes with the minimum number of bytes, which are hashed by the bucket transform
+pub fn decimal_bytes(unscaled: i128) -> Vec<u8> {
+    let bytes = unscaled.to_be_bytes();
+    // Leading bytes can be dropped as long as the sign bit of the next byte stays the same
+    let start = (0..15)
+        .find(|&i| {
+            !(bytes[i] == 0x00 && bytes[i + 1] & 0x80 == 0
+                || bytes[i] == 0xff && bytes[i + 1] & 0x80 != 0)
+        })
+        .unwrap_or(15);
+    bytes[start..].to_vec()
+}
+
+/// Hours from 1970-01-01 00:00:00 of a timestamp in microseconds
+pub fn hours_from_micros(micros: i64) -> i32 {
+    micros.div_euclid(MICROS_PER_HOUR) as i32
+}
+
+/// Days from 1970-01-01 of a timestamp in microseconds
+pub fn days_from_micros(micros: i64) -> i32 {
+    micros.div_euclid(MICROS_PER_DAY) as i32
+}
+
+/// Months from 1970-01-01 of a date in days from 1970-01-01
+pub fn months_from_days(days: i32) -> Result<i32, Error> {
+    let date = date_from_days(days)?;
+    Ok((date.year() - 1970) * 12 + date.month0() as i32)
+}
+
+/// Years from 1970 of a date in days from 1970-01-01
+pub fn years_from_days(days: i32) -> Result<i32, Error> {
+    Ok(date_from_days(days)?.year() - 1970)
+}
+
+fn date_from_days(days: i32) -> Result<NaiveDate, Error> {
+    days.checked_add(UNIX_EPOCH_DAYS_FROM_CE)
+        .and_then(NaiveDate::from_num_days_from_ce_opt)
+        .ok_or_else(|| Error::InvalidFormat(format!("date {} days from 1970-01-01", days)))
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Getters)]
 #[serde(rename_all = "kebab-case")]
 /// Partition fields capture the transform from table data to partition values.
@@ -54,7 +54,7 @@ pub struct TableMetadata {
     /// The tables highest sequence number
     pub last_sequence_number: i64,
     #[builder(
-        default = "SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_micros() as i64"
+        default = "SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64"
     )]
     /// Timestamp in milliseconds from the unix epoch when the table was last updated.
     pub last_updated_ms: i64,
@@ -110,6 +110,13 @@ pub struct TableMetadata {
     /// keep a fixed-size log of the most recent entries after a commit.
     #[builder(default)]
     pub metadata_log: Vec<MetadataLog>,
+    /// A list (optional) of table statistics files. Statistics are informational and
+    /// readers can ignore them, but the files must be kept as long as they are listed.
+    #[builder(default)]
+    pub statistics: Vec<StatisticsFile>,
+    /// A list (optional) of partition statistics files.
+    #[builder(default)]
+    pub partition_statistics: Vec<PartitionStatisticsFile>,
     #[builder(
         setter(each(name = "with_sort_order")),
         default = "HashMap::from_iter(vec![(0, SortOrder::default())])"
@@ -188,6 +195,37 @@ impl TableMetadata {
         }
     }
 
+    /// Get the snapshot that was the current snapshot of the branch at the timestamp in milliseconds. Defaults to main.
+    /// The main branch uses the snapshot log, other branches and snapshots before the start of the log use the ancestry of the current snapshot.
+    /// Returns None if the branch had no snapshot at the timestamp or if the logged snapshot has expired.
+    pub fn snapshot_as_of(
+        &self,
+        timestamp_ms: i64,
+        branch: Option<&str>,
+    ) -> Result<Option<&Snapshot>, Error> {
+        if matches!(branch, None | Some("main")) {
+            if let Some(entry) = self
+                .snapshot_log
+                .iter()
+                .take_while(|entry| entry.timestamp_ms <= timestamp_ms)
+                .last()
+            {
+                return Ok(self.snapshots.get(&entry.snapshot_id));
+            }
+        }
+        let mut ancestor = self.current_snapshot(branch)?;
+        while let Some(snapshot) = ancestor {
+            if *snapshot.timestamp_ms() <= timestamp_ms {
+                return Ok(Some(snapshot));
+            }
+            ancestor = snapshot
+                .parent_snapshot_id()
+                .as_ref()
+                .and_then(|id| self.snapshots.get(id));
+        }
+        Ok(None)
+    }
+
     /// Get current snapshot
     #[inline]
     pub fn current_snapshot_mut(
@@ -277,8 +315,8 @@ pub mod _serde {
     };
 
     use super::{
-        FormatVersion, MetadataLog, SnapshotLog, TableMetadata, VersionNumber,
-        DEFAULT_SORT_ORDER_ID, DEFAULT_SPEC_ID, MAIN_BRANCH,
+        FormatVersion, MetadataLog, PartitionStatisticsFile, SnapshotLog, StatisticsFile,
+        TableMetadata, VersionNumber, DEFAULT_SORT_ORDER_ID, DEFAULT_SPEC_ID, MAIN_BRANCH,
     };
 
     /// Metadata of an iceberg table
@@ -349,6 +387,12 @@ pub mod _serde {
         /// keep a fixed-size log of the most recent entries after a commit.
         #[serde(skip_serializing_if = "Vec::is_empty", default)]
         pub metadata_log: Vec<MetadataLog>,
+        /// A list (optional) of table statistics files.
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        pub statistics: Vec<StatisticsFile>,
+        /// A list (optional) of partition statistics files.
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        pub partition_statistics: Vec<PartitionStatisticsFile>,
 
         /// A list of sort orders, stored as full sort order objects.
         pub sort_orders: Vec<sort::SortOrder>,
@@ -431,6 +475,12 @@ pub mod _serde {
         /// keep a fixed-size log of the most recent entries after a commit.
         #[serde(skip_serializing_if = "Vec::is_empty", default)]
         pub metadata_log: Vec<MetadataLog>,
+        /// A list (optional) of table statistics files.
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        pub statistics: Vec<StatisticsFile>,
+        /// A list (optional) of partition statistics files.
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        pub partition_statistics: Vec<PartitionStatisticsFile>,
 
         /// A list of sort orders, stored as full sort order objects.
         pub sort_orders: Option<Vec<sort::SortOrder>>,
@@ -510,6 +560,8 @@ pub mod _serde {
                     .unwrap_or_default(),
                 snapshot_log: value.snapshot_log,
                 metadata_log: value.metadata_log,
+                statistics: value.statistics,
+                partition_statistics: value.partition_statistics,
                 sort_orders: HashMap::from_iter(
                     value.sort_orders.into_iter().map(|x| (x.order_id, x)),
                 ),
@@ -600,6 +652,8 @@ pub mod _serde {
                     .unwrap_or_default(),
                 snapshot_log: value.snapshot_log,
                 metadata_log: value.metadata_log,
+                statistics: value.statistics,
+                partition_statistics: value.partition_statistics,
                 sort_orders: match value.sort_orders {
                     Some(sort_orders) => {
                         HashMap::from_iter(sort_orders.into_iter().map(|x| (x.order_id, x)))
@@ -641,6 +695,8 @@ pub mod _serde {
                 snapshots: Some(v.snapshots.into_values().map(|x| x.into()).collect()),
                 snapshot_log: v.snapshot_log,
                 metadata_log: v.metadata_log,
+                statistics: v.statistics,
+                partition_statistics: v.partition_statistics,
                 sort_orders: v.sort_orders.into_values().collect(),
                 default_sort_order_id: v.default_sort_order_id,
                 refs: v.refs,
@@ -672,6 +728,8 @@ pub mod _serde {
                 snapshots: Some(v.snapshots.into_values().map(|x| x.into()).collect()),
                 snapshot_log: v.snapshot_log,
                 metadata_log: v.metadata_log,
+                statistics: v.statistics,
+                partition_statistics: v.partition_statistics,
                 sort_orders: Some(v.sort_orders.into_values().collect()),
                 default_sort_order_id: Some(v.default_sort_order_id),
             }
@@ -716,6 +774,54 @@ pub struct MetadataLog {
     pub timestamp_ms: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "kebab-case")]
+/// Statistics of a snapshot stored in a puffin file
+pub struct StatisticsFile {
+    /// Id of the snapshot the statistics were computed from
+    pub snapshot_id: i64,
+    /// Path of the statistics file
+    pub statistics_path: String,
+    /// Size of the statistics file
+    pub file_size_in_bytes: i64,
+    /// Size of the puffin footer
+    pub file_footer_size_in_bytes: i64,
+    /// Base64 encoded implementation specific key metadata for encryption
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_metadata: Option<String>,
+    /// Blobs contained in the statistics file
+    pub blob_metadata: Vec<BlobMetadata>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "kebab-case")]
+/// Metadata of a blob in a statistics file
+pub struct BlobMetadata {
+    /// Type of the blob
+    pub r#type: String,
+    /// Id of the snapshot the blob was computed from
+    pub snapshot_id: i64,
+    /// Sequence number of the snapshot the blob was computed from
+    pub sequence_number: i64,
+    /// Ids of the fields the blob was computed for
+    pub fields: Vec<i32>,
+    /// Additional properties of the blob
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub properties: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "kebab-case")]
+/// Partition statistics of a snapshot
+pub struct PartitionStatisticsFile {
+    /// Id of the snapshot the statistics were computed from
+    pub snapshot_id: i64,
+    /// Path of the statistics file
+    pub statistics_path: String,
+    /// Size of the statistics file
+    pub file_size_in_bytes: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 #[serde(rename_all = "kebab-case")]
 /// A log of when each snapshot was made.
@@ -843,12 +949,37 @@ mod tests {
                         "timestamp-ms": 1515100
                     }
                 ],
+                "statistics": [
+                    {
+                        "snapshot-id": 3055729675574597004,
+                        "statistics-path": "s3://b/wh/data.db/table/metadata/stats.puffin",
+                        "file-size-in-bytes": 413,
+                        "file-footer-size-in-bytes": 42,
+                        "blob-metadata": [
+                            {
+                                "type": "apache-datasketches-theta-v1",
+                                "snapshot-id": 3055729675574597004,
+                                "sequence-number": 1,
+                                "fields": [1]
+                            }
+                        ]
+                    }
+                ],
+                "partition-statistics": [
+                    {
+                        "snapshot-id": 3055729675574597004,
+                        "statistics-path": "s3://b/wh/data.db/table/metadata/partition-stats.parquet",
+                        "file-size-in-bytes": 43
+                    }
+                ],
                 "sort-orders": [],
                 "default-sort-order-id": 0
             }
         "#;
         let metadata =
             serde_json::from_str::<TableMetadata>(data).expect("Failed to deserialize json");
+        assert_eq!(metadata.statistics[0].blob_metadata[0].fields, vec![1]);
+        assert_eq!(metadata.partition_statistics[0].file_size_in_bytes, 43);
         //test serialise deserialise works.
         let metadata_two: TableMetadata = serde_json::from_str(
             &serde_json::to_string(&metadata).expect("Failed to serialize metadata"),
@@ -1136,6 +1267,8 @@ mod tests {
                 },
             ],
             metadata_log: Vec::new(),
+            statistics: Vec::new(),
+            partition_statistics: Vec::new(),
             refs: HashMap::from_iter(vec![(
                 "main".to_string(),
                 SnapshotReference {
@@ -1229,6 +1362,8 @@ mod tests {
             properties: HashMap::new(),
             snapshot_log: vec![],
             metadata_log: Vec::new(),
+            statistics: Vec::new(),
+            partition_statistics: Vec::new(),
             refs: HashMap::new(),
         };
 
@@ -1295,6 +1430,8 @@ mod tests {
             properties: HashMap::new(),
             snapshot_log: vec![],
             metadata_log: Vec::new(),
+            statistics: Vec::new(),
+            partition_statistics: Vec::new(),
             refs: HashMap::from_iter(vec![(
                 "main".to_string(),
                 SnapshotReference {
@@ -1324,4 +1461,86 @@ mod tests {
             "data did not match any variant of untagged enum TableMetadataEnum"
         )
     }
+
+    #[test]
+    fn test_snapshot_as_of() {
+        let snapshot = |id: i64, parent: Option<i64>, timestamp_ms: i64| {
+            let mut builder = SnapshotBuilder::default();
+            builder
+                .with_snapshot_id(id)
+                .with_sequence_number(id)
+                .with_timestamp_ms(timestamp_ms)
+                .with_manifest_list(format!("/test/snap-{}.avro", id));
+            if let Some(parent) = parent {
+                builder.with_parent_snapshot_id(parent);
+            }
+            (id, builder.build().unwrap())
+        };
+        let metadata = TableMetadata {
+            snapshots: HashMap::from_iter(vec![
+                snapshot(1, None, 100),
+                snapshot(2, Some(1), 200),
+                snapshot(3, Some(2), 300),
+            ]),
+            current_snapshot_id: Some(1),
+            // Snapshot 3 was rolled back to snapshot 1 at 400
+            snapshot_log: vec![
+                SnapshotLog {
+                    snapshot_id: 1,
+                    timestamp_ms: 100,
+                },
+                SnapshotLog {
+                    snapshot_id: 2,
+                    timestamp_ms: 200,
+                },
+                SnapshotLog {
+                    snapshot_id: 3,
+                    timestamp_ms: 300,
+                },
+                SnapshotLog {
+                    snapshot_id: 1,
+                    timestamp_ms: 400,
+                },
+            ],
+            refs: HashMap::from_iter(vec![(
+                "staging".to_owned(),
+                SnapshotReference {
+                    snapshot_id: 3,
+                    retention: SnapshotRetention::default(),
+                },
+            )]),
+            ..Default::default()
+        };
+
+        let snapshot_id = |timestamp_ms: i64, branch: Option<&str>| {
+            metadata
+                .snapshot_as_of(timestamp_ms, branch)
+                .unwrap()
+                .map(|snapshot| *snapshot.snapshot_id())
+        };
+        assert_eq!(snapshot_id(50, None), None);
+        assert_eq!(snapshot_id(250, None), Some(2));
+        assert_eq!(snapshot_id(350, None), Some(3));
+        assert_eq!(snapshot_id(450, None), Some(1));
+        assert_eq!(snapshot_id(250, Some("staging")), Some(2));
+        assert_eq!(snapshot_id(1000, Some("staging")), Some(3));
+
+        // Snapshot 2 expired, its ancestor must not be returned instead
+        let mut expired = metadata.clone();
+        expired.snapshots.remove(&2);
+        assert_eq!(
+            expired
+                .snapshot_as_of(250, None)
+                .unwrap()
+                .map(|s| *s.snapshot_id()),
+            None
+        );
+        assert_eq!(
+            expired
+                .snapshot_as_of(350, None)
+                .unwrap()
+                .map(|s| *s.snapshot_id()),
+            Some(3)
+        );
+    }
 }
@@ -350,7 +350,7 @@ impl Type {
             Transform::Month => Ok(Type::Primitive(PrimitiveType::Int)),
             Transform::Day => Ok(Type::Primitive(PrimitiveType::Int)),
             Transform::Hour => Ok(Type::Primitive(PrimitiveType::Int)),
-            Transform::Void => Err(Error::NotSupported("void transform".to_string())),
+            Transform::Void => Ok(self.clone()),
         }
     }
 }
@@ -8,12 +8,11 @@ use std::{
     collections::{btree_map::Keys, BTreeMap, HashMap},
     fmt,
     hash::{DefaultHasher, Hash, Hasher},
-    io::Cursor,
     ops::Sub,
     slice::Iter,
 };
 
-use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use rust_decimal::Decimal;
@@ -29,7 +28,10 @@ use uuid::Uuid;
 use crate::error::Error;
 
 use super::{
-    partition::{PartitionField, Transform},
+    partition::{
+        bucket, days_from_micros, decimal_bytes, hours_from_micros, months_from_days,
+        years_from_days, PartitionField, Transform,
+    },
     types::{PrimitiveType, StructType, Type},
 };
 
@@ -102,6 +104,7 @@ impl From<Value> for ByteBuf {
             Value::UUID(val) => ByteBuf::from(val.as_u128().to_be_bytes()),
             Value::Fixed(_, val) => ByteBuf::from(val),
             Value::Binary(val) => ByteBuf::from(val),
+            Value::Decimal(val) => ByteBuf::from(decimal_bytes(val.mantissa())),
             _ => todo!(),
         }
     }
@@ -287,150 +290,85 @@ impl Hash for Struct {
 }
 
 impl Value {
-    /// Perform a partition transformation for the given value
-    pub fn tranform(&self, transform: &Transform) -> Result<Value, Error> {
-        match transform {
+    /// Perform a partition transformation for the given value. The void transform maps every value to null.
+    pub fn tranform(&self, transform: &Transform) -> Result<Option<Value>, Error> {
+        let value = match transform {
             Transform::Identity => Ok(self.clone()),
-            Transform::Bucket(n) => {
-                let mut bytes = Cursor::new(<Value as Into<ByteBuf>>::into(self.clone()));
-                let hash = murmur3::murmur3_32(&mut bytes, 0).unwrap();
-                Ok(Value::Int((hash % n) as i32))
-            }
+            Transform::Bucket(n) => Ok(Value::Int(bucket(&self.bucket_bytes()?, *n))),
             Transform::Truncate(w) => match self {
                 Value::Int(i) => Ok(Value::Int(i - i.rem_euclid(*w as i32))),
                 Value::LongInt(i) => Ok(Value::LongInt(i - i.rem_euclid(*w as i64))),
-                Value::String(s) => {
-                    let mut s = s.clone();
-                    s.truncate(*w as usize);
-                    Ok(Value::String(s))
+                Value::Decimal(d) => {
+                    let unscaled = d.mantissa();
+                    Ok(Value::Decimal(Decimal::from_i128_with_scale(
+                        unscaled - unscaled.rem_euclid(*w as i128),
+                        d.scale(),
+                    )))
+                }
+                // Strings are truncated to a number of code points
+                Value::String(s) => Ok(Value::String(s.chars().take(*w as usize).collect())),
+                Value::Binary(b) => {
+                    Ok(Value::Binary(b.iter().take(*w as usize).copied().collect()))
                 }
                 _ => Err(Error::NotSupported(
                     "Datatype for truncate partition transform.".to_string(),
                 )),
             },
             Transform::Year => match self {
-                Value::Date(date) => Ok(Value::Int(*date / 365)),
-                Value::Timestamp(time) => Ok(Value::Int(
-                    (DateTime::from_timestamp_millis(time / 1000)
-                        .unwrap()
-                        .naive_utc()
-                        .signed_duration_since(
-                            NaiveDate::from_ymd_opt(1970, 1, 1)
-                                .unwrap()
-                                .and_hms_opt(0, 0, 0)
-                                .unwrap(),
-                        )
-                        .num_days()
-                        / 364) as i32,
-                )),
-                Value::TimestampTZ(time) => Ok(Value::Int(
-                    (DateTime::from_timestamp_millis(time / 1000)
-                        .unwrap()
-                        .naive_utc()
-                        .signed_duration_since(
-                            NaiveDate::from_ymd_opt(1970, 1, 1)
-                                .unwrap()
-                                .and_hms_opt(0, 0, 0)
-                                .unwrap(),
-                        )
-                        .num_days()
-                        / 364) as i32,
-                )),
+                Value::Date(date) => Ok(Value::Int(years_from_days(*date)?)),
+                Value::Timestamp(time) | Value::TimestampTZ(time) => {
+                    Ok(Value::Int(years_from_days(days_from_micros(*time))?))
+                }
                 _ => Err(Error::NotSupported(
                     "Datatype for year partition transform.".to_string(),
                 )),
             },
             Transform::Month => match self {
-                Value::Date(date) => Ok(Value::Int(*date / 30)),
-                Value::Timestamp(time) => Ok(Value::Int(
-                    (DateTime::from_timestamp_millis(time / 1000)
-                        .unwrap()
-                        .naive_utc()
-                        .signed_duration_since(
-                            NaiveDate::from_ymd_opt(1970, 1, 1)
-                                .unwrap()
-                                .and_hms_opt(0, 0, 0)
-                                .unwrap(),
-                        )
-                        .num_weeks()) as i32,
-                )),
-                Value::TimestampTZ(time) => Ok(Value::Int(
-                    (DateTime::from_timestamp_millis(time / 1000)
-                        .unwrap()
-                        .naive_utc()
-                        .signed_duration_since(
-                            NaiveDate::from_ymd_opt(1970, 1, 1)
-                                .unwrap()
-                                .and_hms_opt(0, 0, 0)
-                                .unwrap(),
-                        )
-                        .num_weeks()) as i32,
-                )),
+                Value::Date(date) => Ok(Value::Int(months_from_days(*date)?)),
+                Value::Timestamp(time) | Value::TimestampTZ(time) => {
+                    Ok(Value::Int(months_from_days(days_from_micros(*time))?))
+                }
                 _ => Err(Error::NotSupported(
                     "Datatype for month partition transform.".to_string(),
                 )),
             },
             Transform::Day => match self {
                 Value::Date(date) => Ok(Value::Int(*date)),
-                Value::Timestamp(time) => Ok(Value::Int(
-                    (DateTime::from_timestamp_millis(time / 1000)
-                        .unwrap()
-                        .naive_utc()
-                        .signed_duration_since(
-                            NaiveDate::from_ymd_opt(1970, 1, 1)
-                                .unwrap()
-                                .and_hms_opt(0, 0, 0)
-                                .unwrap(),
-                        )
-                        .num_days()) as i32,
-                )),
-                Value::TimestampTZ(time) => Ok(Value::Int(
-                    (DateTime::from_timestamp_millis(time / 1000)
-                        .unwrap()
-                        .naive_utc()
-                        .signed_duration_since(
-                            NaiveDate::from_ymd_opt(1970, 1, 1)
-                                .unwrap()
-                                .and_hms_opt(0, 0, 0)
-                                .unwrap(),
-                        )
-                        .num_days()) as i32,
-                )),
+                Value::Timestamp(time) | Value::TimestampTZ(time) => {
+                    Ok(Value::Int(days_from_micros(*time)))
+                }
                 _ => Err(Error::NotSupported(
                     "Datatype for day partition transform.".to_string(),
                 )),
             },
             Transform::Hour => match self {
-                Value::Timestamp(time) => Ok(Value::Int(
-                    (DateTime::from_timestamp_millis(time / 1000)
-                        .unwrap()
-                        .naive_utc()
-                        .signed_duration_since(
-                            NaiveDate::from_ymd_opt(1970, 1, 1)
-                                .unwrap()
-                                .and_hms_opt(0, 0, 0)
-                                .unwrap(),
-                        )
-                        .num_hours()) as i32,
-                )),
-                Value::TimestampTZ(time) => Ok(Value::Int(
-                    (DateTime::from_timestamp_millis(time / 1000)
-                        .unwrap()
-                        .naive_utc()
-                        .signed_duration_since(
-                            NaiveDate::from_ymd_opt(1970, 1, 1)
-                                .unwrap()
-                                .and_hms_opt(0, 0, 0)
-                                .unwrap(),
-                        )
-                        .num_hours()) as i32,
-                )),
+                Value::Timestamp(time) | Value::TimestampTZ(time) => {
+                    Ok(Value::Int(hours_from_micros(*time)))
+                }
                 _ => Err(Error::NotSupported(
                     "Datatype for hour partition transform.".to_string(),
                 )),
             },
+            Transform::Void => return Ok(None),
+        };
+        value.map(Some)
+    }
+
+    /// Bytes of the value that are hashed by the bucket transform. Ints and dates are hashed like longs,
+    /// decimals as their minimal two's-complement unscaled value and uuids as 16 big-endian bytes.
+    fn bucket_bytes(&self) -> Result<Vec<u8>, Error> {
+        match self {
+            Value::Int(val) | Value::Date(val) => Ok((*val as i64).to_le_bytes().to_vec()),
+            Value::LongInt(val)
+            | Value::Time(val)
+            | Value::Timestamp(val)
+            | Value::TimestampTZ(val) => Ok(val.to_le_bytes().to_vec()),
+            Value::Decimal(val) => Ok(decimal_bytes(val.mantissa())),
+            Value::String(val) => Ok(val.as_bytes().to_vec()),
+            Value::UUID(val) => Ok(val.as_u128().to_be_bytes().to_vec()),
+            Value::Fixed(_, val) | Value::Binary(val) => Ok(val.clone()),
             _ => Err(Error::NotSupported(
-                "Partition transform operation".to_string(),
+                "Datatype for bucket partition transform.".to_string(),
             )),
         }
     }
@@ -469,7 +407,22 @@ impl Value {
                 )))),
                 PrimitiveType::Fixed(len) => Ok(Value::Fixed(*len as usize, Vec::from(bytes))),
                 PrimitiveType::Binary => Ok(Value::Binary(Vec::from(bytes))),
-                _ => Err(Error::Type("decimal".to_string(), "bytes".to_string())),
+                PrimitiveType::Decimal { scale, .. } => {
+                    // The unscaled value is stored as two's-complement big-endian bytes, which are sign extended
+                    if bytes.len() > 16 {
+                        return Err(Error::Type("decimal".to_string(), "bytes".to_string()));
+                    }
+                    let fill = match bytes.first() {
+                        Some(byte) if byte & 0x80 != 0 => 0xff,
+                        _ => 0x00,
+                    };
+                    let mut unscaled = [fill; 16];
+                    unscaled[16 - bytes.len()..].copy_from_slice(bytes);
+                    Ok(Value::Decimal(
+                        Decimal::try_from_i128_with_scale(i128::from_be_bytes(unscaled), *scale)
+                            .map_err(|_| Error::Type("decimal".to_string(), "bytes".to_string()))?,
+                    ))
+                }
             },
             _ => Err(Error::NotSupported("Complex types as bytes".to_string())),
         }
@@ -1214,4 +1167,169 @@ mod tests {
             &Type::Primitive(PrimitiveType::String),
         );
     }
+
+    #[test]
+    fn avro_bytes_decimal() {
+        let bytes = vec![255u8, 127u8];
+
+        check_avro_bytes_serde(
+            bytes.clone(),
+            Value::Decimal(Decimal::from_i128_with_scale(-129, 2)),
+            &Type::Primitive(PrimitiveType::Decimal {
+                precision: 9,
+                scale: 2,
+            }),
+        );
+        assert_eq!(
+            ByteBuf::from(Value::Decimal(Decimal::from_i128_with_scale(-129, 2))),
+            ByteBuf::from(bytes)
+        );
+    }
+
+    #[test]
+    fn bucket_transform() {
+        // The hashes of the spec test vectors, the mask of the bucket transform makes negative hashes positive
+        let transform = Transform::Bucket(1 << 31);
+        let cases = vec![
+            (Value::Int(34), 2017239379_i64),
+            (Value::LongInt(34), 2017239379),
+            (
+                Value::Decimal(Decimal::from_i128_with_scale(1420, 2)),
+                -500754589 + (1 << 31),
+            ),
+            (Value::Date(17486), -653330422 + (1 << 31)),
+            (Value::Time(81068000000), -662762989 + (1 << 31)),
+            (Value::Timestamp(1510871468000000), -2047944441 + (1 << 31)),
+            (
+                Value::TimestampTZ(1510871468000000),
+                -2047944441 + (1 << 31),
+            ),
+            (Value::String("iceberg".to_string()), 1210000089),
+            (
+                Value::UUID(Uuid::parse_str("f79c3e09-677c-4bbd-a479-3f349cb785e7").unwrap()),
+                1488055340,
+            ),
+            (Value::Fixed(4, vec![0, 1, 2, 3]), -188683207 + (1 << 31)),
+            (Value::Binary(vec![0, 1, 2, 3]), -188683207 + (1 << 31)),
+        ];
+        for (value, hash) in cases {
+            assert_eq!(
+                value.tranform(&transform).unwrap(),
+                Some(Value::Int(hash as i32))
+            );
+        }
+        assert_eq!(
+            Value::Int(34).tranform(&Transform::Bucket(16)).unwrap(),
+            Some(Value::Int(3))
+        );
+        assert_eq!(decimal_bytes(-1), vec![0xff]);
+        assert_eq!(decimal_bytes(128), vec![0x00, 0x80]);
+        assert_eq!(decimal_bytes(-129), vec![0xff, 0x7f]);
+    }
+
+    #[test]
+    fn truncate_transform() {
+        let transform = Transform::Truncate(10);
+        assert_eq!(
+            Value::Int(-1).tranform(&transform).unwrap(),
+            Some(Value::Int(-10))
+        );
+        assert_eq!(
+            Value::LongInt(19).tranform(&transform).unwrap(),
+            Some(Value::LongInt(10))
+        );
+        assert_eq!(
+            Value::Decimal(Decimal::from_i128_with_scale(-5, 2))
+                .tranform(&transform)
+                .unwrap(),
+            Some(Value::Decimal(Decimal::from_i128_with_scale(-10, 2)))
+        );
+        assert_eq!(
+            Value::String("iceberg".to_string())
+                .tranform(&Transform::Truncate(3))
+                .unwrap(),
+            Some(Value::String("ice".to_string()))
+        );
+        assert_eq!(
+            Value::String("äöü".to_string())
+                .tranform(&Transform::Truncate(2))
+                .unwrap(),
+            Some(Value::String("äö".to_string()))
+        );
+        assert_eq!(
+            Value::Binary(vec![1, 2, 3])
+                .tranform(&Transform::Truncate(2))
+                .unwrap(),
+            Some(Value::Binary(vec![1, 2]))
+        );
+    }
+
+    #[test]
+    fn temporal_transforms() {
+        let date = Value::Date(17486);
+        assert_eq!(
+            date.tranform(&Transform::Year).unwrap(),
+            Some(Value::Int(47))
+        );
+        assert_eq!(
+            date.tranform(&Transform::Month).unwrap(),
+            Some(Value::Int(574))
+        );
+        assert_eq!(
+            date.tranform(&Transform::Day).unwrap(),
+            Some(Value::Int(17486))
+        );
+
+        let timestamp = Value::TimestampTZ(1510871468000000);
+        assert_eq!(
+            timestamp.tranform(&Transform::Year).unwrap(),
+            Some(Value::Int(47))
+        );
+        assert_eq!(
+            timestamp.tranform(&Transform::Month).unwrap(),
+            Some(Value::Int(574))
+        );
+        assert_eq!(
+            timestamp.tranform(&Transform::Day).unwrap(),
+            Some(Value::Int(17486))
+        );
+        assert_eq!(
+            timestamp.tranform(&Transform::Hour).unwrap(),
+            Some(Value::Int(419686))
+        );
+
+        // Values before the epoch are rounded down
+        let timestamp = Value::Timestamp(-1);
+        assert_eq!(
+            timestamp.tranform(&Transform::Year).unwrap(),
+            Some(Value::Int(-1))
+        );
+        assert_eq!(
+            timestamp.tranform(&Transform::Month).unwrap(),
+            Some(Value::Int(-1))
+        );
+        assert_eq!(
+            timestamp.tranform(&Transform::Day).unwrap(),
+            Some(Value::Int(-1))
+        );
+        assert_eq!(
+            timestamp.tranform(&Transform::Hour).unwrap(),
+            Some(Value::Int(-1))
+        );
+        assert_eq!(
+            Value::Date(-1).tranform(&Transform::Month).unwrap(),
+            Some(Value::Int(-1))
+        );
+    }
+
+    #[test]
+    fn void_transform() {
+        assert_eq!(Value::Int(34).tranform(&Transform::Void).unwrap(), None);
+        assert_eq!(
+            Value::String("iceberg".to_string())
+                .tranform(&Transform::Void)
+                .unwrap(),
+            None
+        );
+    }
 }
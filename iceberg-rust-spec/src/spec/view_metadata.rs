@@ -103,6 +103,22 @@ impl<T: Materialization> GeneralViewMetadata<T> {
             .get(&version_id)
             .ok_or_else(|| Error::InvalidFormat("view metadata".to_string()))
     }
+    /// Get the version that was the current version at the timestamp in milliseconds.
+    /// Uses the version log and falls back to the creation time of the versions for views without a log.
+    pub fn version_as_of(&self, timestamp_ms: i64) -> Option<&Version<T>> {
+        if self.version_log.is_empty() {
+            return self
+                .versions
+                .values()
+                .filter(|version| version.timestamp_ms <= timestamp_ms)
+                .max_by_key(|version| (version.timestamp_ms, version.version_id));
+        }
+        self.version_log
+            .iter()
+            .take_while(|entry| entry.timestamp_ms <= timestamp_ms)
+            .last()
+            .and_then(|entry| self.versions.get(&entry.version_id))
+    }
     /// Add schema to view metadata
     #[inline]
     pub fn add_schema(&mut self, schema: Schema) {
@@ -253,7 +269,7 @@ pub struct Version<T: Materialization> {
     #[builder(default = "DEFAULT_SCHEMA_ID")]
     pub schema_id: i32,
     #[builder(
-        default = "SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_micros() as i64"
+        default = "SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64"
     )]
     /// Timestamp expressed in ms since epoch at which the version of the view was created.
     pub timestamp_ms: i64,
@@ -0,0 +1,613 @@
+/*!
+ * Functions to apply delete files when reading arrow record batches
+*/
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use arrow::{
+    array::{ArrayRef, AsArray, BooleanArray},
+    compute::{cast, filter_record_batch, not},
+    datatypes::{Int64Type, Schema as ArrowSchema},
+    error::ArrowError,
+    record_batch::RecordBatch,
+    row::{RowConverter, SortField},
+};
+use futures::{future, StreamExt, TryStreamExt};
+use iceberg_rust_spec::{
+    arrow::schema::PARQUET_FIELD_ID_META_KEY,
+    spec::{
+        manifest::{Content, FileFormat, ManifestEntry},
+        schema::Schema,
+        types::{PrimitiveType, StructField, StructType, Type},
+        values::Struct,
+    },
+    util,
+};
+use object_store::ObjectStore;
+use parquet::arrow::{
+    arrow_reader::{RowSelection, RowSelector},
+    async_reader::ParquetObjectReader,
+    ParquetRecordBatchStreamBuilder, ProjectionMask,
+};
+
+use crate::error::Error;
+
+/// Name of the column of a position delete file that stores the path of the data file
+pub static DELETE_FILE_PATH: &str = "file_path";
+/// Name of the column of a position delete file that stores the position of the deleted row
+pub static DELETE_POS: &str = "pos";
+/// Field id of the file_path column of a position delete file
+pub static DELETE_FILE_PATH_FIELD_ID: i32 = 2147483546;
+/// Field id of the pos column of a position delete file
+pub static DELETE_POS_FIELD_ID: i32 = 2147483545;
+
+/// Iceberg schema of position delete files
+pub fn position_delete_schema() -> Result<Schema, Error> {
+    Schema::builder()
+        .with_fields(StructType::new(vec![
+            StructField {
+                id: DELETE_FILE_PATH_FIELD_ID,
+                name: DELETE_FILE_PATH.to_owned(),
+                required: true,
+                field_type: Type::Primitive(PrimitiveType::String),
+                doc: None,
+            },
+            StructField {
+                id: DELETE_POS_FIELD_ID,
+                name: DELETE_POS.to_owned(),
+                required: true,
+                field_type: Type::Primitive(PrimitiveType::Long),
+                doc: None,
+            },
+        ]))
+        .build()
+        .map_err(iceberg_rust_spec::error::Error::from)
+        .map_err(Error::from)
+}
+
+/// Deleted row positions of a set of position delete files
+#[derive(Debug, Default)]
+pub struct PositionDeletes {
+    files: Vec<PositionDeleteFile>,
+}
+
+#[derive(Debug)]
+struct PositionDeleteFile {
+    sequence_number: i64,
+    positions: HashMap<String, Vec<i64>>,
+}
+
+impl PositionDeletes {
+    /// Read the position delete files among the given manifest entries
+    pub async fn try_new(
+        delete_files: impl IntoIterator<Item = ManifestEntry>,
+        object_store: Arc<dyn ObjectStore>,
+    ) -> Result<Self, Error> {
+        let files = future::try_join_all(
+            delete_files
+                .into_iter()
+                .filter(|entry| matches!(entry.data_file().content(), Content::PositionDeletes))
+                .map(|entry| read_position_delete_file(entry, object_store.clone())),
+        )
+        .await?;
+        Ok(PositionDeletes { files })
+    }
+
+    /// Sorted positions of the deleted rows of a data file. Position deletes apply to data files with a smaller or equal sequence number.
+    pub fn positions(&self, data_file: &ManifestEntry) -> Vec<i64> {
+        let sequence_number = data_file.sequence_number().unwrap_or_default();
+        let mut positions = self
+            .files
+            .iter()
+            .filter(|file| file.sequence_number >= sequence_number)
+            .filter_map(|file| file.positions.get(data_file.data_file().file_path()))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+}
+
+async fn delete_file_reader(
+    entry: &ManifestEntry,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<ParquetRecordBatchStreamBuilder<ParquetObjectReader>, Error> {
+    if !matches!(entry.data_file().file_format(), FileFormat::Parquet) {
+        return Err(Error::NotSupported("fileformat".to_string()));
+    }
+    let object_meta = object_store
+        .head(&util::strip_prefix(entry.data_file().file_path()).into())
+        .await?;
+    let object_reader = ParquetObjectReader::new(object_store, object_meta);
+    Ok(ParquetRecordBatchStreamBuilder::new(object_reader).await?)
+}
+
+async fn read_position_delete_file(
+    entry: ManifestEntry,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<PositionDeleteFile, Error> {
+    let builder = delete_file_reader(&entry, object_store).await?;
+
+    let indices = [DELETE_FILE_PATH, DELETE_POS]
+        .iter()
+        .map(|name| {
+            builder
+                .schema()
+                .index_of(name)
+                .map_err(|_| Error::NotFound("Column".to_owned(), name.to_string()))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let mask = ProjectionMask::roots(builder.parquet_schema(), indices);
+    let mut stream = builder.with_projection(mask).build()?;
+
+    let mut positions: HashMap<String, Vec<i64>> = HashMap::new();
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        let file_paths = batch
+            .column_by_name(DELETE_FILE_PATH)
+            .and_then(|column| column.as_string_opt::<i32>())
+            .ok_or(Error::InvalidFormat("position delete file".to_string()))?;
+        let pos = batch
+            .column_by_name(DELETE_POS)
+            .and_then(|column| column.as_primitive_opt::<Int64Type>())
+            .ok_or(Error::InvalidFormat("position delete file".to_string()))?;
+        for (file_path, pos) in file_paths.iter().zip(pos.iter()) {
+            if let (Some(file_path), Some(pos)) = (file_path, pos) {
+                positions.entry(file_path.to_owned()).or_default().push(pos);
+            }
+        }
+    }
+
+    Ok(PositionDeleteFile {
+        sequence_number: entry.sequence_number().unwrap_or_default(),
+        positions,
+    })
+}
+
+/// Deleted rows of a set of equality delete files
+#[derive(Debug, Default)]
+pub struct EqualityDeletes {
+    files: Vec<Arc<EqualityDeleteFile>>,
+}
+
+/// Rows of an equality delete file, encoded in the arrow row format
+#[derive(Debug)]
+pub struct EqualityDeleteFile {
+    sequence_number: i64,
+    partition: Struct,
+    equality_ids: Vec<i32>,
+    /// Name of the equality columns in the delete file, used if the data file has no field ids
+    names: Vec<String>,
+    sort_fields: Vec<SortField>,
+    rows: HashSet<Vec<u8>>,
+}
+
+impl EqualityDeletes {
+    /// Read the equality delete files among the given manifest entries
+    pub async fn try_new(
+        delete_files: impl IntoIterator<Item = ManifestEntry>,
+        object_store: Arc<dyn ObjectStore>,
+    ) -> Result<Self, Error> {
+        let files = future::try_join_all(
+            delete_files
+                .into_iter()
+                .filter(|entry| matches!(entry.data_file().content(), Content::EqualityDeletes))
+                .map(|entry| read_equality_delete_file(entry, object_store.clone())),
+        )
+        .await?
+        .into_iter()
+        .map(Arc::new)
+        .collect();
+        Ok(EqualityDeletes { files })
+    }
+
+    /// Equality delete files that apply to a data file. Equality deletes apply to data files with a smaller sequence number
+    /// that are in the same partition. Delete files of an unpartitioned spec apply to all partitions.
+    pub fn files(&self, data_file: &ManifestEntry) -> Vec<Arc<EqualityDeleteFile>> {
+        let sequence_number = data_file.sequence_number().unwrap_or_default();
+        self.files
+            .iter()
+            .filter(|file| file.sequence_number > sequence_number)
+            .filter(|file| applies_to_partition(&file.partition, data_file.data_file().partition()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Check whether an equality delete file with the given partition applies to a data file partition
+pub fn applies_to_partition(delete_partition: &Struct, data_partition: &Struct) -> bool {
+    delete_partition.fields.is_empty() || delete_partition == data_partition
+}
+
+/// Check whether a delete file might apply to a data file based on the sequence numbers and the partitions of the files
+pub fn applies_to(delete_file: &ManifestEntry, data_file: &ManifestEntry) -> bool {
+    let delete_sequence_number = delete_file.sequence_number().unwrap_or_default();
+    let data_sequence_number = data_file.sequence_number().unwrap_or_default();
+    match delete_file.data_file().content() {
+        Content::Data => false,
+        Content::PositionDeletes => {
+            delete_sequence_number >= data_sequence_number
+                && delete_file.data_file().partition() == data_file.data_file().partition()
+        }
+        Content::EqualityDeletes => {
+            delete_sequence_number > data_sequence_number
+                && applies_to_partition(
+                    delete_file.data_file().partition(),
+                    data_file.data_file().partition(),
+                )
+        }
+    }
+}
+
+impl EqualityDeleteFile {
+    /// Remove the deleted rows from a record batch of a data file
+    pub fn apply(&self, batch: RecordBatch) -> Result<RecordBatch, ArrowError> {
+        if self.rows.is_empty() || batch.num_rows() == 0 {
+            return Ok(batch);
+        }
+        let deleted = self.deleted(&batch)?;
+        filter_record_batch(&batch, &not(&deleted)?)
+    }
+
+    /// Mark the rows of a record batch of a data file that are deleted by the delete file
+    pub fn deleted(&self, batch: &RecordBatch) -> Result<BooleanArray, ArrowError> {
+        if self.rows.is_empty() || batch.num_rows() == 0 {
+            return Ok(BooleanArray::from(vec![false; batch.num_rows()]));
+        }
+        let columns = self
+            .equality_ids
+            .iter()
+            .zip(self.names.iter())
+            .zip(self.sort_fields.iter())
+            .map(|((id, name), sort_field)| {
+                let column = column_by_field_id(batch, *id)
+                    .or_else(|| batch.column_by_name(name).cloned())
+                    .ok_or(ArrowError::SchemaError(format!(
+                        "Equality column {} not found in data file.",
+                        name
+                    )))?;
+                cast(&column, sort_field.data_type())
+            })
+            .collect::<Result<Vec<_>, ArrowError>>()?;
+        let converter = RowConverter::new(self.sort_fields.clone())?;
+        let rows = converter.convert_columns(&columns)?;
+        Ok(rows
+            .iter()
+            .map(|row| Some(self.rows.contains(row.as_ref())))
+            .collect::<BooleanArray>())
+    }
+}
+
+fn column_by_field_id(batch: &RecordBatch, id: i32) -> Option<ArrayRef> {
+    field_index(batch.schema().as_ref(), id).map(|index| batch.column(index).clone())
+}
+
+fn field_index(schema: &ArrowSchema, id: i32) -> Option<usize> {
+    schema.fields().iter().position(|field| {
+        field
+            .metadata()
+            .get(PARQUET_FIELD_ID_META_KEY)
+            .and_then(|x| x.parse::<i32>().ok())
+            == Some(id)
+    })
+}
+
+async fn read_equality_delete_file(
+    entry: ManifestEntry,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<EqualityDeleteFile, Error> {
+    let equality_ids = entry
+        .data_file()
+        .equality_ids()
+        .clone()
+        .ok_or(Error::NotFound(
+            "Equality ids".to_owned(),
+            entry.data_file().file_path().to_owned(),
+        ))?;
+    let builder = delete_file_reader(&entry, object_store).await?;
+
+    let indices = equality_ids
+        .iter()
+        .map(|id| {
+            field_index(builder.schema(), *id)
+                .ok_or(Error::NotFound("Column".to_owned(), id.to_string()))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let schema = builder.schema().project(&indices)?;
+    let names = schema
+        .fields()
+        .iter()
+        .map(|field| field.name().clone())
+        .collect();
+    let sort_fields = schema
+        .fields()
+        .iter()
+        .map(|field| SortField::new(field.data_type().clone()))
+        .collect::<Vec<_>>();
+
+    let mask = ProjectionMask::roots(builder.parquet_schema(), indices);
+    let batches: Vec<RecordBatch> = builder.with_projection(mask).build()?.try_collect().await?;
+
+    let converter = RowConverter::new(sort_fields.clone())?;
+    let mut rows = HashSet::new();
+    for batch in batches {
+        let converted = converter.convert_columns(batch.columns())?;
+        rows.extend(converted.iter().map(|row| row.as_ref().to_vec()));
+    }
+
+    Ok(EqualityDeleteFile {
+        sequence_number: entry.sequence_number().unwrap_or_default(),
+        partition: entry.data_file().partition().clone(),
+        equality_ids,
+        names,
+        sort_fields,
+        rows,
+    })
+}
+
+/// Create a row selection for a file with `num_rows` rows that skips the sorted positions
+pub fn row_selection(positions: &[i64], num_rows: i64) -> RowSelection {
+    let mut selectors = Vec::with_capacity(2 * positions.len() + 1);
+    let mut current = 0;
+    for &pos in positions {
+        if pos < current || pos >= num_rows {
+            continue;
+        }
+        if pos > current {
+            selectors.push(RowSelector::select((pos - current) as usize));
+        }
+        selectors.push(RowSelector::skip(1));
+        current = pos + 1;
+    }
+    if current < num_rows {
+        selectors.push(RowSelector::select((num_rows - current) as usize));
+    }
+    RowSelection::from(selectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, AsArray, Int64Array, StringArray},
+        datatypes::Int64Type,
+        record_batch::RecordBatch,
+    };
+    use futures::{stream, TryStreamExt};
+    use iceberg_rust_spec::spec::{
+        manifest::{Content, DataFile, FileFormat, ManifestEntry, Status},
+        schema::Schema,
+        table_metadata::{FormatVersion, TableMetadataBuilder},
+        types::{PrimitiveType, StructField, StructType, Type},
+        values::{Struct, Value},
+    };
+    use object_store::{memory::InMemory, ObjectStore};
+    use parquet::arrow::arrow_reader::{RowSelection, RowSelector};
+
+    use crate::arrow::{
+        read::read,
+        write::{write_equality_deletes, write_parquet_partitioned, write_position_deletes},
+    };
+
+    use super::{applies_to, row_selection};
+
+    fn manifest_entry(data_file: DataFile, sequence_number: i64) -> ManifestEntry {
+        ManifestEntry::builder()
+            .with_format_version(FormatVersion::V2)
+            .with_status(Status::Added)
+            .with_sequence_number(sequence_number)
+            .with_data_file(data_file)
+            .build()
+            .unwrap()
+    }
+
+    fn entry(content: Content, sequence_number: i64, partition: Option<i32>) -> ManifestEntry {
+        manifest_entry(
+            DataFile::builder()
+                .with_content(content)
+                .with_file_path("/test/file.parquet".to_owned())
+                .with_file_format(FileFormat::Parquet)
+                .with_partition(Struct::from_iter(
+                    partition.map(|x| ("day".to_owned(), Some(Value::Int(x)))),
+                ))
+                .with_record_count(1)
+                .with_file_size_in_bytes(1)
+                .with_column_sizes(None)
+                .with_value_counts(None)
+                .with_null_value_counts(None)
+                .with_nan_value_counts(None)
+                .with_distinct_counts(None)
+                .with_lower_bounds(None)
+                .with_upper_bounds(None)
+                .build()
+                .unwrap(),
+            sequence_number,
+        )
+    }
+
+    #[test]
+    fn test_applies_to() {
+        let data = entry(Content::Data, 2, Some(1));
+
+        assert!(applies_to(
+            &entry(Content::PositionDeletes, 2, Some(1)),
+            &data
+        ));
+        assert!(!applies_to(
+            &entry(Content::PositionDeletes, 1, Some(1)),
+            &data
+        ));
+        assert!(!applies_to(
+            &entry(Content::PositionDeletes, 3, Some(2)),
+            &data
+        ));
+
+        assert!(applies_to(
+            &entry(Content::EqualityDeletes, 3, Some(1)),
+            &data
+        ));
+        assert!(applies_to(&entry(Content::EqualityDeletes, 3, None), &data));
+        assert!(!applies_to(
+            &entry(Content::EqualityDeletes, 2, Some(1)),
+            &data
+        ));
+        assert!(!applies_to(
+            &entry(Content::EqualityDeletes, 3, Some(2)),
+            &data
+        ));
+    }
+
+    #[test]
+    fn test_row_selection() {
+        assert_eq!(
+            row_selection(&[0, 1, 4, 9], 10),
+            RowSelection::from(vec![
+                RowSelector::skip(2),
+                RowSelector::select(2),
+                RowSelector::skip(1),
+                RowSelector::select(4),
+                RowSelector::skip(1),
+            ])
+        );
+        assert_eq!(
+            row_selection(&[], 3),
+            RowSelection::from(vec![RowSelector::select(3)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_with_deletes() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let schema = Schema::builder()
+            .with_fields(StructType::new(vec![
+                StructField {
+                    id: 1,
+                    name: "id".to_owned(),
+                    required: true,
+                    field_type: Type::Primitive(PrimitiveType::Long),
+                    doc: None,
+                },
+                StructField {
+                    id: 2,
+                    name: "name".to_owned(),
+                    required: false,
+                    field_type: Type::Primitive(PrimitiveType::String),
+                    doc: None,
+                },
+            ]))
+            .build()
+            .unwrap();
+        let metadata = TableMetadataBuilder::default()
+            .location("/test/orders")
+            .with_schema((0, schema))
+            .current_schema_id(0)
+            .build()
+            .unwrap();
+
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5, 6])) as ArrayRef,
+            ),
+            (
+                "name",
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d", "e", "f"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let data_files = write_parquet_partitioned(
+            &metadata,
+            stream::iter(vec![Ok(batch)]),
+            object_store.clone(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(data_files.len(), 1);
+        let data_file = data_files[0].clone();
+
+        // The rows with the ids 1 and 3 are deleted by position
+        let position_deletes = write_position_deletes(
+            &metadata,
+            vec![(data_file.clone(), vec![0, 2])],
+            object_store.clone(),
+        )
+        .await
+        .unwrap();
+
+        let equality_deletes = |ids: Vec<i64>| {
+            let metadata = &metadata;
+            let object_store = object_store.clone();
+            async move {
+                let batch = RecordBatch::try_from_iter(vec![(
+                    "id",
+                    Arc::new(Int64Array::from(ids)) as ArrayRef,
+                )])
+                .unwrap();
+                write_equality_deletes(
+                    metadata,
+                    stream::iter(vec![Ok(batch)]),
+                    &[1],
+                    object_store,
+                    None,
+                )
+                .await
+                .unwrap()
+            }
+        };
+        // The rows with the ids 4 and 6 are deleted by value, the delete of the id 5 has the same sequence number as the data file
+        let later_deletes = equality_deletes(vec![4, 6]).await;
+        let concurrent_deletes = equality_deletes(vec![5]).await;
+
+        let entries = std::iter::once(manifest_entry(data_file, 1))
+            .chain(
+                position_deletes
+                    .into_iter()
+                    .chain(later_deletes)
+                    .map(|file| manifest_entry(file, 2)),
+            )
+            .chain(
+                concurrent_deletes
+                    .into_iter()
+                    .map(|file| manifest_entry(file, 1)),
+            );
+
+        let batches: Vec<RecordBatch> = read(entries, object_store)
+            .await
+            .try_collect()
+            .await
+            .unwrap();
+        let mut ids = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column_by_name("id")
+                    .unwrap()
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![2, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_read_missing_data_file() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+
+        // The data file doesn't exist in the object store, which fails the read instead of skipping the file
+        let result: Result<Vec<RecordBatch>, _> =
+            read(std::iter::once(entry(Content::Data, 1, None)), object_store)
+                .await
+                .try_collect()
+                .await;
+        assert!(result.is_err());
+    }
+}
@@ -1,7 +1,10 @@
 /*!
  * Conversion from arrow to iceberg and vice-versa
 */
+pub mod delete;
 pub mod partition;
+pub mod projection;
 pub mod read;
 pub mod transform;
+pub mod upsert;
 pub mod write;
@@ -3,31 +3,37 @@
 */
 
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
-    hash::Hash,
+    collections::{hash_map::Entry, HashMap},
     pin::Pin,
     sync::Arc,
 };
 
 use arrow::{
-    array::{
-        as_primitive_array, as_string_array, ArrayIter, ArrayRef, BooleanArray,
-        BooleanBufferBuilder, PrimitiveArray, StringArray,
+    array::{Array, ArrayRef, AsArray, UInt32Array},
+    compute::take,
+    datatypes::{
+        DataType, Date32Type, Decimal128Type, Float32Type, Float64Type, Int32Type, Int64Type,
+        Time64MicrosecondType, TimeUnit, TimestampMicrosecondType,
     },
-    compute::kernels::cmp::eq,
-    compute::{and, filter_record_batch},
-    datatypes::{ArrowPrimitiveType, DataType, Int32Type, Int64Type},
     error::ArrowError,
     record_batch::RecordBatch,
+    row::{Row, RowConverter, SortField},
 };
 use futures::{
     channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
     lock::Mutex,
     stream, SinkExt, Stream, StreamExt, TryStreamExt,
 };
-use itertools::{iproduct, Itertools};
+use uuid::Uuid;
 
-use iceberg_rust_spec::spec::{partition::PartitionSpec, schema::Schema, values::Value};
+use iceberg_rust_spec::spec::{
+    partition::{decimal_bytes, PartitionSpec},
+    schema::Schema,
+    types::{PrimitiveType, Type},
+    values::Value,
+};
+
+use crate::error::Error;
 
 use super::transform::transform_arrow;
 
@@ -36,96 +42,32 @@ type SendableRecordBatchStream =
 
 type RecordBatchSender = UnboundedSender<Result<RecordBatch, ArrowError>>;
 
-/// Partition stream of record batches according to partition spec
+/// Partition stream of record batches according to partition spec. The rows are grouped by the tuple of their partition values,
+/// null partition values are `None`.
 pub async fn partition_record_batches(
     record_batches: impl Stream<Item = Result<RecordBatch, ArrowError>> + Send,
     partition_spec: &PartitionSpec,
     schema: &Schema,
 ) -> Result<
     Vec<(
-        Vec<Value>,
+        Vec<Option<Value>>,
         impl Stream<Item = Result<RecordBatch, ArrowError>> + Send,
     )>,
     ArrowError,
 > {
     let (partition_sender, partition_reciever): (
-        UnboundedSender<(Vec<Value>, SendableRecordBatchStream)>,
-        UnboundedReceiver<(Vec<Value>, SendableRecordBatchStream)>,
+        UnboundedSender<(Vec<Option<Value>>, SendableRecordBatchStream)>,
+        UnboundedReceiver<(Vec<Option<Value>>, SendableRecordBatchStream)>,
     ) = unbounded();
-    let partition_streams: Arc<Mutex<HashMap<Vec<Value>, RecordBatchSender>>> =
+    let partition_streams: Arc<Mutex<HashMap<Vec<Option<Value>>, RecordBatchSender>>> =
         Arc::new(Mutex::new(HashMap::new()));
     record_batches
         .try_for_each_concurrent(None, |record_batch| {
             let partition_streams = partition_streams.clone();
             let partition_sender = partition_sender.clone();
             async move {
-                let partition_columns: Vec<ArrayRef> = partition_spec
-                    .fields()
-                    .iter()
-                    .map(|field| {
-                        let column_name = &schema
-                            .fields()
-                            .get(*field.source_id() as usize)
-                            .ok_or(ArrowError::SchemaError("Column doesn't exist".to_string()))?
-                            .name;
-                        let array = record_batch
-                            .column_by_name(column_name)
-                            .ok_or(ArrowError::SchemaError("Column doesn't exist".to_string()))?;
-                        transform_arrow(array.clone(), field.transform())
-                    })
-                    .collect::<Result<_, ArrowError>>()?;
-                let distinct_values: Vec<DistinctValues> = partition_columns
-                    .iter()
-                    .map(|x| distinct_values(x.clone()))
-                    .collect::<Result<Vec<_>, ArrowError>>()?;
-                let mut true_buffer = BooleanBufferBuilder::new(record_batch.num_rows());
-                true_buffer.append_n(record_batch.num_rows(), true);
-                let predicates = distinct_values
-                    .into_iter()
-                    .zip(partition_columns.iter())
-                    .map(|(distinct, value)| match distinct {
-                        DistinctValues::Int(set) => set
-                            .into_iter()
-                            .map(|x| {
-                                Ok((
-                                    Value::Int(x),
-                                    eq(&PrimitiveArray::<Int32Type>::new_scalar(x), value)?,
-                                ))
-                            })
-                            .collect::<Result<Vec<_>, ArrowError>>(),
-                        DistinctValues::Long(set) => set
-                            .into_iter()
-                            .map(|x| {
-                                Ok((
-                                    Value::LongInt(x),
-                                    eq(&PrimitiveArray::<Int64Type>::new_scalar(x), value)?,
-                                ))
-                            })
-                            .collect::<Result<Vec<_>, ArrowError>>(),
-                        DistinctValues::String(set) => set
-                            .into_iter()
-                            .map(|x| {
-                                let res = eq(&StringArray::new_scalar(&x), value)?;
-                                Ok((Value::String(x), res))
-                            })
-                            .collect::<Result<Vec<_>, ArrowError>>(),
-                    })
-                    .try_fold(
-                        vec![(vec![], BooleanArray::new(true_buffer.finish(), None))],
-                        |acc, predicates| {
-                            iproduct!(acc, predicates?.iter())
-                                .map(|((mut values, x), (value, y))| {
-                                    values.push(value.clone());
-                                    Ok((values, and(&x, y)?))
-                                })
-                                .filter_ok(|x| x.1.true_count() != 0)
-                                .collect::<Result<Vec<(Vec<Value>, _)>, ArrowError>>()
-                        },
-                    )?;
-                stream::iter(predicates.into_iter())
-                    .map(|(values, predicate)| {
-                        Ok((values, filter_record_batch(&record_batch, &predicate)?))
-                    })
+                let partitions = partition_batch(&record_batch, partition_spec, schema)?;
+                stream::iter(partitions.into_iter().map(Ok::<_, ArrowError>))
                     .try_for_each_concurrent(None, |(values, batch)| {
                         let partition_streams = partition_streams.clone();
                         let mut partition_sender = partition_sender.clone();
@@ -169,51 +111,147 @@ pub async fn partition_record_batches(
     Ok(recievers)
 }
 
-fn distinct_values(array: ArrayRef) -> Result<DistinctValues, ArrowError> {
-    match array.data_type() {
-        DataType::Int32 => Ok(DistinctValues::Int(distinct_values_primitive::<
-            i32,
-            Int32Type,
-        >(array))),
-        DataType::Int64 => Ok(DistinctValues::Long(distinct_values_primitive::<
-            i64,
-            Int64Type,
-        >(array))),
-        DataType::Utf8 => Ok(DistinctValues::String(distinct_values_string(array))),
-        _ => Err(ArrowError::ComputeError(
-            "Datatype not supported for transform.".to_string(),
-        )),
+/// Split a record batch into one record batch per partition. The transformed partition columns are converted to the arrow row format,
+/// so that rows with the same tuple of partition values have equal byte representations regardless of the datatypes.
+fn partition_batch(
+    record_batch: &RecordBatch,
+    partition_spec: &PartitionSpec,
+    schema: &Schema,
+) -> Result<Vec<(Vec<Option<Value>>, RecordBatch)>, ArrowError> {
+    if partition_spec.fields().is_empty() {
+        return Ok(vec![(Vec::new(), record_batch.clone())]);
     }
-}
+    let (partition_columns, partition_types): (Vec<ArrayRef>, Vec<Type>) = partition_spec
+        .fields()
+        .iter()
+        .map(|field| {
+            let source = schema
+                .fields()
+                .get(*field.source_id() as usize)
+                .ok_or(ArrowError::SchemaError("Column doesn't exist".to_string()))?;
+            let array = record_batch
+                .column_by_name(&source.name)
+                .ok_or(ArrowError::SchemaError("Column doesn't exist".to_string()))?;
+            let partition_type = source
+                .field_type
+                .tranform(field.transform())
+                .map_err(Error::from)?;
+            Ok((
+                transform_arrow(array.clone(), field.transform(), &source.field_type)?,
+                partition_type,
+            ))
+        })
+        .collect::<Result<Vec<_>, ArrowError>>()?
+        .into_iter()
+        .unzip();
 
-fn distinct_values_primitive<T: Eq + Hash, P: ArrowPrimitiveType<Native = T>>(
-    array: ArrayRef,
-) -> HashSet<P::Native> {
-    let mut set = HashSet::new();
-    let array = as_primitive_array::<P>(&array);
-    for value in ArrayIter::new(array).flatten() {
-        if !set.contains(&value) {
-            set.insert(value);
-        }
-    }
-    set
-}
+    let converter = RowConverter::new(
+        partition_columns
+            .iter()
+            .map(|column| SortField::new(column.data_type().clone()))
+            .collect(),
+    )?;
+    let rows = converter.convert_columns(&partition_columns)?;
 
-fn distinct_values_string(array: ArrayRef) -> HashSet<String> {
-    let mut set = HashSet::new();
-    let array = as_string_array(&array);
-    for value in ArrayIter::new(array).flatten() {
-        if !set.contains(value) {
-            set.insert(value.to_owned());
-        }
+    let mut partitions: HashMap<Row, Vec<u32>> = HashMap::new();
+    for (index, row) in rows.iter().enumerate() {
+        partitions.entry(row).or_default().push(index as u32);
     }
-    set
+
+    let single_partition = partitions.len() == 1;
+    partitions
+        .into_values()
+        .map(|indices| {
+            let values = partition_columns
+                .iter()
+                .zip(partition_types.iter())
+                .map(|(column, data_type)| value(column, indices[0] as usize, data_type))
+                .collect::<Result<Vec<_>, ArrowError>>()?;
+            if single_partition {
+                return Ok((values, record_batch.clone()));
+            }
+            let indices = UInt32Array::from(indices);
+            let columns = record_batch
+                .columns()
+                .iter()
+                .map(|column| take(column, &indices, None))
+                .collect::<Result<Vec<_>, ArrowError>>()?;
+            Ok((
+                values,
+                RecordBatch::try_new(record_batch.schema(), columns)?,
+            ))
+        })
+        .collect()
 }
 
-enum DistinctValues {
-    Int(HashSet<i32>),
-    Long(HashSet<i64>),
-    String(HashSet<String>),
+/// Partition value of a row of a transformed partition column
+fn value(array: &ArrayRef, index: usize, data_type: &Type) -> Result<Option<Value>, ArrowError> {
+    if array.is_null(index) {
+        return Ok(None);
+    }
+    // The values are created from their binary single-value serialization
+    let bytes = match array.data_type() {
+        DataType::Boolean => vec![array.as_boolean().value(index) as u8],
+        DataType::Int32 => array
+            .as_primitive::<Int32Type>()
+            .value(index)
+            .to_le_bytes()
+            .to_vec(),
+        DataType::Date32 => array
+            .as_primitive::<Date32Type>()
+            .value(index)
+            .to_le_bytes()
+            .to_vec(),
+        DataType::Int64 => array
+            .as_primitive::<Int64Type>()
+            .value(index)
+            .to_le_bytes()
+            .to_vec(),
+        DataType::Time64(TimeUnit::Microsecond) => array
+            .as_primitive::<Time64MicrosecondType>()
+            .value(index)
+            .to_le_bytes()
+            .to_vec(),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => array
+            .as_primitive::<TimestampMicrosecondType>()
+            .value(index)
+            .to_le_bytes()
+            .to_vec(),
+        DataType::Float32 => array
+            .as_primitive::<Float32Type>()
+            .value(index)
+            .to_le_bytes()
+            .to_vec(),
+        DataType::Float64 => array
+            .as_primitive::<Float64Type>()
+            .value(index)
+            .to_le_bytes()
+            .to_vec(),
+        DataType::Decimal128(_, _) => {
+            decimal_bytes(array.as_primitive::<Decimal128Type>().value(index))
+        }
+        DataType::Utf8 => {
+            let value = array.as_string::<i32>().value(index);
+            match data_type {
+                Type::Primitive(PrimitiveType::Uuid) => Uuid::parse_str(value)
+                    .map_err(Error::from)?
+                    .as_bytes()
+                    .to_vec(),
+                _ => value.as_bytes().to_vec(),
+            }
+        }
+        DataType::Binary => array.as_binary::<i32>().value(index).to_vec(),
+        DataType::FixedSizeBinary(_) => array.as_fixed_size_binary().value(index).to_vec(),
+        data_type => {
+            return Err(ArrowError::ComputeError(format!(
+                "Datatype {} not supported for partitioning.",
+                data_type
+            )))
+        }
+    };
+    Ok(Some(
+        Value::try_from_bytes(&bytes, data_type).map_err(Error::from)?,
+    ))
 }
 
 #[cfg(test)]
@@ -222,7 +260,7 @@ mod tests {
     use std::sync::Arc;
 
     use arrow::{
-        array::{ArrayRef, Int64Array, StringArray},
+        array::{ArrayRef, BooleanArray, Date32Array, Decimal128Array, Int64Array, StringArray},
         error::ArrowError,
         record_batch::RecordBatch,
     };
@@ -231,7 +269,9 @@ mod tests {
         partition::{PartitionField, PartitionSpec, Transform},
         schema::Schema,
         types::{PrimitiveType, StructField, StructType, Type},
+        values::Value,
     };
+    use uuid::Uuid;
 
     use super::partition_record_batches;
 
@@ -320,4 +360,142 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_partition_tuples() {
+        let uuid = "f79c3e09-677c-4bbd-a479-3f349cb785e7";
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "b",
+                Arc::new(BooleanArray::from(vec![
+                    true, true, false, true, true, false,
+                ])) as ArrayRef,
+            ),
+            (
+                "d",
+                Arc::new(Date32Array::from(vec![
+                    Some(17486),
+                    Some(17486),
+                    Some(17486),
+                    None,
+                    Some(17486),
+                    Some(17487),
+                ])) as ArrayRef,
+            ),
+            (
+                "dec",
+                Arc::new(
+                    Decimal128Array::from(vec![1420; 6])
+                        .with_precision_and_scale(9, 2)
+                        .unwrap(),
+                ) as ArrayRef,
+            ),
+            (
+                "u",
+                Arc::new(StringArray::from(vec![
+                    Some(uuid),
+                    Some(uuid),
+                    Some(uuid),
+                    Some(uuid),
+                    None,
+                    Some(uuid),
+                ])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let schema = Schema::builder()
+            .with_schema_id(0)
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "b".to_string(),
+                        field_type: Type::Primitive(PrimitiveType::Boolean),
+                        required: true,
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "d".to_string(),
+                        field_type: Type::Primitive(PrimitiveType::Date),
+                        required: false,
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 3,
+                        name: "dec".to_string(),
+                        field_type: Type::Primitive(PrimitiveType::Decimal {
+                            precision: 9,
+                            scale: 2,
+                        }),
+                        required: true,
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 4,
+                        name: "u".to_string(),
+                        field_type: Type::Primitive(PrimitiveType::Uuid),
+                        required: false,
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let partition_spec = PartitionSpec::builder()
+            .with_partition_field(PartitionField::new(1, 1001, "b", Transform::Identity))
+            .with_partition_field(PartitionField::new(2, 1002, "d", Transform::Identity))
+            .with_partition_field(PartitionField::new(3, 1003, "dec", Transform::Identity))
+            .with_partition_field(PartitionField::new(
+                4,
+                1004,
+                "u_bucket",
+                Transform::Bucket(16),
+            ))
+            .build()
+            .unwrap();
+        let streams = partition_record_batches(
+            stream::iter(vec![Ok::<_, ArrowError>(batch)]),
+            &partition_spec,
+            &schema,
+        )
+        .await
+        .unwrap();
+        let output = stream::iter(streams.into_iter())
+            .then(|(values, batches)| async move {
+                let rows = batches
+                    .map(|batch| batch.unwrap().num_rows())
+                    .collect::<Vec<_>>()
+                    .await
+                    .into_iter()
+                    .sum::<usize>();
+                (values, rows)
+            })
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(output.len(), 5);
+        assert_eq!(output.iter().map(|(_, rows)| rows).sum::<usize>(), 6);
+
+        let bucket = Value::UUID(Uuid::parse_str(uuid).unwrap())
+            .tranform(&Transform::Bucket(16))
+            .unwrap();
+        let (values, rows) = output
+            .iter()
+            .find(|(_, rows)| *rows == 2)
+            .expect("Rows with the same partition values are grouped");
+        assert_eq!(*rows, 2);
+        assert_eq!(values[0], Some(Value::Boolean(true)));
+        assert_eq!(values[1], Some(Value::Date(17486)));
+        assert_eq!(values[2].as_ref().unwrap().to_string(), "14.20");
+        assert_eq!(values[3], bucket);
+
+        assert!(output
+            .iter()
+            .any(|(values, _)| values[1].is_none() && values[0] == Some(Value::Boolean(true))));
+        assert!(output.iter().any(|(values, _)| values[3].is_none()));
+    }
 }
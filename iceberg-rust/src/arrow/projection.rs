@@ -0,0 +1,376 @@
+/*!
+ * Projection of record batches that were read from data files onto the schema of the table.
+ * Columns are matched by their field ids, so renamed, added, dropped and promoted columns are read correctly.
+*/
+
+use std::{collections::HashMap, sync::Arc};
+
+use arrow::{
+    array::{new_null_array, Array, ArrayRef, AsArray, ListArray, MapArray, StructArray},
+    compute::cast,
+    datatypes::{DataType, Field, Fields, Schema as ArrowSchema},
+    error::ArrowError,
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use iceberg_rust_spec::{
+    arrow::schema::PARQUET_FIELD_ID_META_KEY,
+    spec::{
+        name_mapping::{MappedField, NameMapping},
+        types::{StructField, StructType, Type},
+    },
+};
+
+use crate::error::Error;
+
+/// Table property for the name mapping that assigns field ids to the columns of data files without field ids
+pub static SCHEMA_NAME_MAPPING_DEFAULT: &str = "schema.name-mapping.default";
+
+/// Read the name mapping of the table from the table properties
+pub fn name_mapping(properties: &HashMap<String, String>) -> Result<Option<NameMapping>, Error> {
+    properties
+        .get(SCHEMA_NAME_MAPPING_DEFAULT)
+        .map(|mapping| Ok(mapping.parse()?))
+        .transpose()
+}
+
+/// Project a record batch that was read from a data file onto the schema of the table. Columns are matched by their field ids, which are read from
+/// the `PARQUET:field_id` metadata of the file columns or assigned through the name mapping. Columns without any field id are matched by name.
+/// Columns that are missing in the file are filled with nulls and columns with a different type are cast to the promoted type.
+pub fn project_batch(
+    batch: &RecordBatch,
+    schema: &StructType,
+    name_mapping: Option<&NameMapping>,
+) -> Result<RecordBatch, Error> {
+    let arrow_schema: ArrowSchema = schema.try_into()?;
+    let source = batch.schema();
+    let columns = project_fields(
+        source.fields(),
+        batch.columns(),
+        schema,
+        name_mapping.map(|mapping| mapping.0.as_slice()),
+        batch.num_rows(),
+    )?;
+    Ok(RecordBatch::try_new_with_options(
+        Arc::new(arrow_schema),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+    )?)
+}
+
+/// Find the column of the file schema that corresponds to the field of the table schema
+pub fn source_index(
+    field: &StructField,
+    file_schema: &ArrowSchema,
+    name_mapping: Option<&NameMapping>,
+) -> Option<usize> {
+    source_position(
+        field,
+        file_schema.fields(),
+        name_mapping.map(|mapping| mapping.0.as_slice()),
+    )
+}
+
+fn project_fields(
+    source_fields: &Fields,
+    source_columns: &[ArrayRef],
+    schema: &StructType,
+    mapping: Option<&[MappedField]>,
+    num_rows: usize,
+) -> Result<Vec<ArrayRef>, Error> {
+    schema
+        .iter()
+        .map(
+            |field| match source_position(field, source_fields, mapping) {
+                Some(position) => project_array(
+                    &source_columns[position],
+                    &field.field_type,
+                    mapping
+                        .and_then(|mapping| mapped_field(mapping, source_fields[position].name())),
+                ),
+                None if !field.required => {
+                    Ok(new_null_array(&(&field.field_type).try_into()?, num_rows))
+                }
+                None => Err(Error::InvalidFormat(format!(
+                    "Required column {} is missing in the data file",
+                    field.name
+                ))),
+            },
+        )
+        .collect()
+}
+
+/// Position of the source field that corresponds to the target field. Source fields with a field id only match target fields with the same id.
+fn source_position(
+    field: &StructField,
+    source_fields: &Fields,
+    mapping: Option<&[MappedField]>,
+) -> Option<usize> {
+    let source_ids = source_fields
+        .iter()
+        .map(|source| {
+            field_id(source).or_else(|| {
+                mapping
+                    .and_then(|mapping| mapped_field(mapping, source.name()))
+                    .and_then(|mapped| mapped.field_id)
+            })
+        })
+        .collect::<Vec<_>>();
+    source_ids
+        .iter()
+        .position(|id| *id == Some(field.id))
+        .or_else(|| {
+            source_fields
+                .iter()
+                .zip(&source_ids)
+                .position(|(source, id)| id.is_none() && *source.name() == field.name)
+        })
+}
+
+/// Project an array of a data file onto the iceberg type
+fn project_array(
+    array: &ArrayRef,
+    field_type: &Type,
+    mapping: Option<&MappedField>,
+) -> Result<ArrayRef, Error> {
+    let data_type: DataType = field_type.try_into()?;
+    let children = mapping.map(|mapping| mapping.fields.as_slice());
+    match (array.data_type(), field_type, &data_type) {
+        (DataType::Struct(source_fields), Type::Struct(struct_type), DataType::Struct(fields)) => {
+            let array = array.as_struct();
+            let columns = project_fields(
+                source_fields,
+                array.columns(),
+                struct_type,
+                children,
+                array.len(),
+            )?;
+            Ok(Arc::new(StructArray::try_new(
+                fields.clone(),
+                columns,
+                array.nulls().cloned(),
+            )?))
+        }
+        // The element of a list is matched by position
+        (DataType::List(_), Type::List(list_type), DataType::List(element)) => {
+            let array = array.as_list::<i32>();
+            let values = project_array(
+                array.values(),
+                &list_type.element,
+                children.and_then(|children| mapped_field(children, "element")),
+            )?;
+            Ok(Arc::new(ListArray::try_new(
+                element.clone(),
+                array.offsets().clone(),
+                values,
+                array.nulls().cloned(),
+            )?))
+        }
+        // Keys and values of a map are matched by position
+        (DataType::Map(_, _), Type::Map(map_type), DataType::Map(entries, sorted)) => {
+            let array = array.as_map();
+            let DataType::Struct(entry_fields) = entries.data_type() else {
+                return Err(Error::InvalidFormat("map entries".to_owned()));
+            };
+            let keys = project_array(
+                array.keys(),
+                &map_type.key,
+                children.and_then(|children| mapped_field(children, "key")),
+            )?;
+            let values = project_array(
+                array.values(),
+                &map_type.value,
+                children.and_then(|children| mapped_field(children, "value")),
+            )?;
+            Ok(Arc::new(MapArray::try_new(
+                entries.clone(),
+                array.offsets().clone(),
+                StructArray::try_new(entry_fields.clone(), vec![keys, values], None)?,
+                array.nulls().cloned(),
+                *sorted,
+            )?))
+        }
+        (source, _, target) if source == target => Ok(array.clone()),
+        // Promoted primitive types like int to long, float to double and wider decimals
+        (_, Type::Primitive(_), target) => Ok(cast(array, target)?),
+        (source, _, target) => Err(Error::Arrow(ArrowError::SchemaError(format!(
+            "Cannot project {} onto {}",
+            source, target
+        )))),
+    }
+}
+
+/// Field id of a column of a data file
+fn field_id(field: &Field) -> Option<i32> {
+    field
+        .metadata()
+        .get(PARQUET_FIELD_ID_META_KEY)
+        .and_then(|id| id.parse().ok())
+}
+
+fn mapped_field<'mapping>(
+    mapping: &'mapping [MappedField],
+    name: &str,
+) -> Option<&'mapping MappedField> {
+    mapping
+        .iter()
+        .find(|field| field.names.iter().any(|x| x == name))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use arrow::{
+        array::{Array, ArrayRef, AsArray, Float32Array, Int32Array, StructArray},
+        datatypes::{
+            DataType, Field, Fields, Float64Type, Int32Type, Int64Type, Schema as ArrowSchema,
+        },
+        record_batch::RecordBatch,
+    };
+    use iceberg_rust_spec::{
+        arrow::schema::PARQUET_FIELD_ID_META_KEY,
+        spec::{
+            name_mapping::NameMapping,
+            types::{PrimitiveType, StructField, StructType, Type},
+        },
+    };
+
+    use super::project_batch;
+
+    fn with_id(field: Field, id: i32) -> Field {
+        field.with_metadata(HashMap::from_iter(vec![(
+            PARQUET_FIELD_ID_META_KEY.to_owned(),
+            id.to_string(),
+        )]))
+    }
+
+    fn target_schema() -> StructType {
+        // The column "id" was promoted to long, "value" was renamed to "amount" and promoted to double,
+        // the nested field "point.y" and the column "comment" were added
+        StructType::new(vec![
+            StructField {
+                id: 1,
+                name: "id".to_owned(),
+                required: true,
+                field_type: Type::Primitive(PrimitiveType::Long),
+                doc: None,
+            },
+            StructField {
+                id: 2,
+                name: "amount".to_owned(),
+                required: false,
+                field_type: Type::Primitive(PrimitiveType::Double),
+                doc: None,
+            },
+            StructField {
+                id: 3,
+                name: "point".to_owned(),
+                required: false,
+                field_type: Type::Struct(StructType::new(vec![
+                    StructField {
+                        id: 4,
+                        name: "x".to_owned(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Int),
+                        doc: None,
+                    },
+                    StructField {
+                        id: 5,
+                        name: "y".to_owned(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Int),
+                        doc: None,
+                    },
+                ])),
+                doc: None,
+            },
+            StructField {
+                id: 6,
+                name: "comment".to_owned(),
+                required: false,
+                field_type: Type::Primitive(PrimitiveType::String),
+                doc: None,
+            },
+        ])
+    }
+
+    fn check(batch: RecordBatch, name_mapping: Option<&NameMapping>) {
+        let projected = project_batch(&batch, &target_schema(), name_mapping).unwrap();
+        assert_eq!(projected.num_columns(), 4);
+        assert_eq!(
+            projected.column(0).as_primitive::<Int64Type>().values(),
+            &[1, 2]
+        );
+        assert_eq!(
+            projected.column(1).as_primitive::<Float64Type>().values(),
+            &[1.5, 2.5]
+        );
+        let point = projected.column(2).as_struct();
+        assert_eq!(
+            point.column(0).as_primitive::<Int32Type>().values(),
+            &[3, 4]
+        );
+        assert_eq!(point.column(1).null_count(), 2);
+        assert_eq!(projected.column(3).null_count(), 2);
+    }
+
+    #[test]
+    fn test_project_by_field_id() {
+        let point_fields = Fields::from(vec![with_id(Field::new("x", DataType::Int32, true), 4)]);
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                with_id(Field::new("value", DataType::Float32, true), 2),
+                with_id(Field::new("id", DataType::Int32, false), 1),
+                with_id(Field::new("dropped", DataType::Int32, true), 7),
+                with_id(
+                    Field::new("point", DataType::Struct(point_fields.clone()), true),
+                    3,
+                ),
+            ])),
+            vec![
+                Arc::new(Float32Array::from(vec![1.5, 2.5])) as ArrayRef,
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+                Arc::new(Int32Array::from(vec![8, 9])) as ArrayRef,
+                Arc::new(StructArray::new(
+                    point_fields,
+                    vec![Arc::new(Int32Array::from(vec![3, 4])) as ArrayRef],
+                    None,
+                )) as ArrayRef,
+            ],
+        )
+        .unwrap();
+        check(batch, None);
+    }
+
+    #[test]
+    fn test_project_by_name_mapping() {
+        let point_fields = Fields::from(vec![Field::new("px", DataType::Int32, true)]);
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                Field::new("id", DataType::Int32, false),
+                Field::new("value", DataType::Float32, true),
+                Field::new("point", DataType::Struct(point_fields.clone()), true),
+            ])),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+                Arc::new(Float32Array::from(vec![1.5, 2.5])) as ArrayRef,
+                Arc::new(StructArray::new(
+                    point_fields,
+                    vec![Arc::new(Int32Array::from(vec![3, 4])) as ArrayRef],
+                    None,
+                )) as ArrayRef,
+            ],
+        )
+        .unwrap();
+        let mapping: NameMapping = r#"[
+            { "field-id": 1, "names": ["id"] },
+            { "field-id": 2, "names": ["amount", "value"] },
+            { "field-id": 3, "names": ["point"], "fields": [
+                { "field-id": 4, "names": ["x", "px"] }
+            ] }
+        ]"#
+        .parse()
+        .unwrap();
+        check(batch, Some(&mapping));
+    }
+}
@@ -2,47 +2,434 @@
  * Functions to read arrow record batches from an iceberg table
 */
 
-use std::{convert, sync::Arc};
+use std::sync::Arc;
 
-use arrow::record_batch::RecordBatch;
-use futures::{stream, Stream, StreamExt};
-use iceberg_rust_spec::util;
+use arrow::{
+    array::{
+        ArrayRef, BinaryArray, BooleanArray, Date32Array, Decimal128Array, FixedSizeBinaryArray,
+        Float32Array, Float64Array, Int32Array, Int64Array, Scalar, StringArray,
+        Time64MicrosecondArray, TimestampMicrosecondArray,
+    },
+    compute::{
+        and, cast, filter_record_batch, is_not_null, is_null,
+        kernels::cmp::{eq, gt, gt_eq, lt, lt_eq},
+        not, or,
+    },
+    datatypes::{DataType, Field, Schema as ArrowSchema},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use futures::{future, stream, stream::BoxStream, Stream, StreamExt, TryStreamExt};
+use iceberg_rust_spec::{
+    spec::{schema::Schema, values::Value},
+    util,
+};
 use object_store::ObjectStore;
 use parquet::{
-    arrow::{async_reader::ParquetObjectReader, ParquetRecordBatchStreamBuilder},
+    arrow::{
+        arrow_reader::{RowSelection, RowSelector},
+        async_reader::ParquetObjectReader,
+        ParquetRecordBatchStreamBuilder,
+    },
     errors::ParquetError,
 };
 
-use crate::error::Error;
+use crate::{
+    arrow::projection::project_batch,
+    error::Error,
+    file_format::{avro::read_avro, orc::read_orc, parquet::bloom_filter_row_groups},
+    table::{
+        changelog::{ChangelogTask, FileChanges, CHANGE_ORDINAL, CHANGE_TYPE, COMMIT_SNAPSHOT_ID},
+        scan::{FileScanTask, Predicate},
+    },
+};
+
+use iceberg_rust_spec::spec::manifest::{Content, DataFile, FileFormat, ManifestEntry, Status};
 
-use iceberg_rust_spec::spec::manifest::{FileFormat, ManifestEntry};
+use super::delete::{row_selection, EqualityDeleteFile, EqualityDeletes, PositionDeletes};
 
-/// Read a parquet file into a stream of arrow recordbatches. The record batches are read asynchronously and are unordered
+/// Read data files into a stream of arrow recordbatches. The record batches are read asynchronously and are unordered.
+/// Position and equality delete files among the manifest entries are applied to the data files. The stream fails if a data file or one of its delete files can't be read.
 pub async fn read(
     manifest_files: impl Iterator<Item = ManifestEntry>,
     object_store: Arc<dyn ObjectStore>,
 ) -> impl Stream<Item = Result<RecordBatch, ParquetError>> {
-    stream::iter(manifest_files)
+    let (data_files, delete_files): (Vec<_>, Vec<_>) = manifest_files
+        .filter(|manifest| !matches!(manifest.status(), Status::Deleted))
+        .partition(|manifest| matches!(manifest.data_file().content(), Content::Data));
+
+    let deletes = match future::try_join(
+        PositionDeletes::try_new(delete_files.iter().cloned(), object_store.clone()),
+        EqualityDeletes::try_new(delete_files, object_store.clone()),
+    )
+    .await
+    {
+        Ok(deletes) => Arc::new(deletes),
+        Err(err) => {
+            return stream::once(async move { Err(ParquetError::External(Box::new(err))) }).boxed()
+        }
+    };
+
+    stream::iter(data_files)
         .then(move |manifest| {
+            let object_store = object_store.clone();
+            let deletes = deletes.clone();
+            async move {
+                let (position_deletes, equality_deletes) = deletes.as_ref();
+                read_data_file(
+                    &manifest,
+                    position_deletes.positions(&manifest),
+                    equality_deletes.files(&manifest),
+                    &[],
+                    None,
+                    object_store,
+                )
+                .await
+                .map_err(|err| ParquetError::External(Box::new(err)))
+            }
+        })
+        .try_flatten_unordered(None)
+        .boxed()
+}
+
+/// Read file scan tasks into a stream of arrow record batches. The delete files of the tasks are applied, the rows are filtered with the residual filter
+/// and the selected columns are projected. The record batches are returned in the order of the tasks.
+pub async fn read_tasks(
+    tasks: impl IntoIterator<Item = FileScanTask>,
+    object_store: Arc<dyn ObjectStore>,
+) -> impl Stream<Item = Result<RecordBatch, ParquetError>> {
+    stream::iter(tasks.into_iter().collect::<Vec<_>>())
+        .then(move |task| {
             let object_store = object_store.clone();
             async move {
-                match manifest.data_file().file_format() {
-                    FileFormat::Parquet => {
-                        let object_meta = object_store
-                            .head(&util::strip_prefix(manifest.data_file().file_path()).into())
-                            .await?;
-
-                        let object_reader = ParquetObjectReader::new(object_store, object_meta);
-                        Ok::<_, Error>(
-                            ParquetRecordBatchStreamBuilder::new(object_reader)
-                                .await?
-                                .build()?,
-                        )
-                    }
-                    _ => Err(Error::NotSupported("fileformat".to_string())),
+                read_task(task, object_store)
+                    .await
+                    .map_err(|err| ParquetError::External(Box::new(err)))
+            }
+        })
+        .try_flatten()
+        .boxed()
+}
+
+async fn read_task(
+    task: FileScanTask,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<impl Stream<Item = Result<RecordBatch, ParquetError>>, Error> {
+    let (position_deletes, equality_deletes) = future::try_join(
+        PositionDeletes::try_new(task.deletes.clone(), object_store.clone()),
+        EqualityDeletes::try_new(task.deletes, object_store.clone()),
+    )
+    .await?;
+    let stream = read_data_file(
+        &task.data_file,
+        position_deletes.positions(&task.data_file),
+        equality_deletes.files(&task.data_file),
+        &task.residual,
+        Some(&task.schema),
+        object_store,
+    )
+    .await?;
+
+    let residual = task.residual;
+    let columns = task.columns;
+    let schema = task.schema;
+    let name_mapping = task.name_mapping;
+    Ok(
+        stream.map(move |batch| -> Result<RecordBatch, ParquetError> {
+            // The columns of the data file are matched to the snapshot schema by field id
+            let batch = project_batch(&batch?, &schema, name_mapping.as_ref())
+                .map_err(|err| ParquetError::External(Box::new(err)))?;
+            let batch = if residual.is_empty() {
+                batch
+            } else {
+                filter_record_batch(&batch, &residual_mask(&batch, &residual)?)?
+            };
+            match &columns {
+                Some(columns) => {
+                    let schema = batch.schema();
+                    let indices = columns
+                        .iter()
+                        .map(|column| schema.index_of(column))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(batch.project(&indices)?)
                 }
+                None => Ok(batch),
             }
+        }),
+    )
+}
+
+/// Read the rows of a data file that are not deleted by the sorted positions or the equality delete files. Row groups of parquet files
+/// that can't contain rows matching the equality and in predicates of the filter are skipped with the help of bloom filters.
+/// Orc and avro files only read the columns of the schema if one is given.
+async fn read_data_file(
+    manifest: &ManifestEntry,
+    positions: Vec<i64>,
+    equality_deletes: Vec<Arc<EqualityDeleteFile>>,
+    filter: &[Predicate],
+    schema: Option<&Schema>,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<BoxStream<'static, Result<RecordBatch, ParquetError>>, Error> {
+    // Parquet files skip the deleted positions with a row selection
+    if matches!(manifest.data_file().file_format(), FileFormat::Parquet) {
+        let object_meta = object_store
+            .head(&util::strip_prefix(manifest.data_file().file_path()).into())
+            .await?;
+        let object_reader = ParquetObjectReader::new(object_store, object_meta);
+        let mut builder = ParquetRecordBatchStreamBuilder::new(object_reader).await?;
+        // The bloom filters can only be used if the predicate columns can be resolved to field ids
+        let row_groups = match schema {
+            Some(schema) => bloom_filter_row_groups(&mut builder, filter, schema).await?,
+            None => (0..builder.metadata().num_row_groups()).collect(),
+        };
+        let builder = if positions.is_empty() {
+            builder
+        } else {
+            // The row selection only covers the selected row groups
+            let mut selectors = Vec::new();
+            let mut start = 0;
+            for (index, row_group) in builder.metadata().row_groups().iter().enumerate() {
+                let num_rows = row_group.num_rows();
+                if row_groups.contains(&index) {
+                    let positions = positions
+                        .iter()
+                        .filter(|position| (start..start + num_rows).contains(*position))
+                        .map(|position| position - start)
+                        .collect::<Vec<_>>();
+                    selectors.extend(Vec::<RowSelector>::from(row_selection(
+                        &positions, num_rows,
+                    )));
+                }
+                start += num_rows;
+            }
+            builder.with_row_selection(RowSelection::from(selectors))
+        };
+        return Ok(builder
+            .with_row_groups(row_groups)
+            .build()?
+            .map(move |batch| {
+                equality_deletes.iter().try_fold(batch?, |batch, delete| {
+                    delete.apply(batch).map_err(ParquetError::from)
+                })
+            })
+            .boxed());
+    }
+
+    // Position of the first row of the next record batch in the data file
+    let mut offset = 0;
+    Ok(read_file(manifest.data_file(), schema, object_store)
+        .await?
+        .map(move |batch| -> Result<RecordBatch, ParquetError> {
+            let batch = batch?;
+            let start = offset;
+            offset += batch.num_rows() as i64;
+            let deleted = deleted_rows(&batch, start, &positions, &equality_deletes)?;
+            Ok(filter_record_batch(&batch, &not(&deleted)?)?)
+        })
+        .boxed())
+}
+
+/// Read all rows of a data file. Orc and avro files only read the columns of the schema, which are matched by field id.
+async fn read_file(
+    data_file: &DataFile,
+    schema: Option<&Schema>,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<BoxStream<'static, Result<RecordBatch, ParquetError>>, Error> {
+    match data_file.file_format() {
+        FileFormat::Parquet => {
+            let object_meta = object_store
+                .head(&util::strip_prefix(data_file.file_path()).into())
+                .await?;
+            let object_reader = ParquetObjectReader::new(object_store, object_meta);
+            Ok(ParquetRecordBatchStreamBuilder::new(object_reader)
+                .await?
+                .build()?
+                .boxed())
+        }
+        FileFormat::Orc => Ok(stream::iter(
+            read_orc(data_file.file_path(), object_store, schema, None).await?,
+        )
+        .map_err(ParquetError::from)
+        .boxed()),
+        FileFormat::Avro => Ok(stream::iter(
+            read_avro(data_file.file_path(), object_store, schema, None).await?,
+        )
+        .map_err(ParquetError::from)
+        .boxed()),
+        _ => Err(Error::NotSupported("fileformat".to_string())),
+    }
+}
+
+/// Mark the rows of a record batch that satisfy the conjunction of the predicates. Rows with null values don't satisfy comparisons.
+fn residual_mask(batch: &RecordBatch, residual: &[Predicate]) -> Result<BooleanArray, ArrowError> {
+    residual.iter().try_fold(
+        BooleanArray::from(vec![true; batch.num_rows()]),
+        |mask, predicate| {
+            let column = batch.column_by_name(predicate.column()).ok_or_else(|| {
+                ArrowError::SchemaError(format!("Column {} not found", predicate.column()))
+            })?;
+            let matches = match predicate {
+                Predicate::IsNull(_) => is_null(column)?,
+                Predicate::NotNull(_) => is_not_null(column)?,
+                Predicate::Eq(_, value) => eq(column, &scalar(value, column.data_type())?)?,
+                Predicate::Lt(_, value) => lt(column, &scalar(value, column.data_type())?)?,
+                Predicate::LtEq(_, value) => lt_eq(column, &scalar(value, column.data_type())?)?,
+                Predicate::Gt(_, value) => gt(column, &scalar(value, column.data_type())?)?,
+                Predicate::GtEq(_, value) => gt_eq(column, &scalar(value, column.data_type())?)?,
+                Predicate::In(_, values) => values.iter().try_fold(
+                    BooleanArray::from(vec![false; batch.num_rows()]),
+                    |matches, value| {
+                        or(&matches, &eq(column, &scalar(value, column.data_type())?)?)
+                    },
+                )?,
+            };
+            and(&mask, &matches)
+        },
+    )
+}
+
+/// Convert an iceberg value to an arrow scalar of the given datatype
+fn scalar(value: &Value, data_type: &DataType) -> Result<Scalar<ArrayRef>, ArrowError> {
+    let array: ArrayRef = match value {
+        Value::Boolean(x) => Arc::new(BooleanArray::from(vec![*x])),
+        Value::Int(x) => Arc::new(Int32Array::from(vec![*x])),
+        Value::LongInt(x) => Arc::new(Int64Array::from(vec![*x])),
+        Value::Float(x) => Arc::new(Float32Array::from(vec![x.0])),
+        Value::Double(x) => Arc::new(Float64Array::from(vec![x.0])),
+        Value::Date(x) => Arc::new(Date32Array::from(vec![*x])),
+        Value::Time(x) => Arc::new(Time64MicrosecondArray::from(vec![*x])),
+        Value::Timestamp(x) => Arc::new(TimestampMicrosecondArray::from(vec![*x])),
+        Value::TimestampTZ(x) => {
+            Arc::new(TimestampMicrosecondArray::from(vec![*x]).with_timezone("+00:00"))
+        }
+        Value::String(x) => Arc::new(StringArray::from(vec![x.as_str()])),
+        Value::UUID(x) => Arc::new(FixedSizeBinaryArray::try_from_iter(std::iter::once(
+            x.as_bytes(),
+        ))?),
+        Value::Fixed(_, x) => Arc::new(FixedSizeBinaryArray::try_from_iter(std::iter::once(x))?),
+        Value::Binary(x) => Arc::new(BinaryArray::from_vec(vec![x.as_slice()])),
+        Value::Decimal(x) => Arc::new(
+            Decimal128Array::from(vec![x.mantissa()])
+                .with_precision_and_scale(38, x.scale() as i8)?,
+        ),
+        _ => {
+            return Err(ArrowError::NotYetImplemented(format!(
+                "Predicate on value {:?}",
+                value
+            )))
+        }
+    };
+    Ok(Scalar::new(cast(&array, data_type)?))
+}
+
+/// Read the changed rows of changelog tasks into a stream of arrow record batches. The record batches contain the columns of the changelog schema
+/// followed by the change type, the change ordinal and the commit snapshot id. The record batches are returned in the order of the tasks.
+pub async fn read_changelog(
+    tasks: impl IntoIterator<Item = ChangelogTask>,
+    object_store: Arc<dyn ObjectStore>,
+) -> impl Stream<Item = Result<RecordBatch, ParquetError>> {
+    stream::iter(tasks.into_iter().collect::<Vec<_>>())
+        .then(move |task| {
+            let object_store = object_store.clone();
+            async move {
+                read_changelog_task(task, object_store)
+                    .await
+                    .map_err(|err| ParquetError::External(Box::new(err)))
+            }
+        })
+        .try_flatten()
+        .boxed()
+}
+
+async fn read_changelog_task(
+    task: ChangelogTask,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<impl Stream<Item = Result<RecordBatch, ParquetError>>, Error> {
+    let (existing_deletes, added_deletes) = match &task.changes {
+        FileChanges::Added { deletes } => (Vec::new(), deletes.clone()),
+        FileChanges::Removed { deletes } => (deletes.clone(), Vec::new()),
+        FileChanges::RowsDeleted {
+            existing_deletes,
+            added_deletes,
+        } => (existing_deletes.clone(), added_deletes.clone()),
+    };
+    let (existing_positions, existing_equality_deletes) = future::try_join(
+        PositionDeletes::try_new(existing_deletes.clone(), object_store.clone()),
+        EqualityDeletes::try_new(existing_deletes, object_store.clone()),
+    )
+    .await?;
+    let (added_positions, added_equality_deletes) = future::try_join(
+        PositionDeletes::try_new(added_deletes.clone(), object_store.clone()),
+        EqualityDeletes::try_new(added_deletes, object_store.clone()),
+    )
+    .await?;
+    let existing = (
+        existing_positions.positions(&task.data_file),
+        existing_equality_deletes.files(&task.data_file),
+    );
+    let added = (
+        added_positions.positions(&task.data_file),
+        added_equality_deletes.files(&task.data_file),
+    );
+
+    let stream = read_file(task.data_file.data_file(), Some(&task.schema), object_store).await?;
+
+    let change_type = task.change_type().as_str();
+    // Position of the first row of the next record batch in the data file
+    let mut offset = 0;
+    Ok(
+        stream.map(move |batch| -> Result<RecordBatch, ParquetError> {
+            let batch = batch?;
+            let start = offset;
+            offset += batch.num_rows() as i64;
+
+            let existing = deleted_rows(&batch, start, &existing.0, &existing.1)?;
+            let added = deleted_rows(&batch, start, &added.0, &added.1)?;
+            let mask = match task.changes {
+                FileChanges::Added { .. } => not(&added)?,
+                FileChanges::Removed { .. } => not(&existing)?,
+                FileChanges::RowsDeleted { .. } => and(&not(&existing)?, &added)?,
+            };
+            let batch = filter_record_batch(&batch, &mask)?;
+            // The columns of the data file are matched to the changelog schema by field id
+            let batch = project_batch(&batch, &task.schema, task.name_mapping.as_ref())
+                .map_err(|err| ParquetError::External(Box::new(err)))?;
+
+            let num_rows = batch.num_rows();
+            let mut fields = batch.schema().fields().to_vec();
+            fields.extend([
+                Arc::new(Field::new(CHANGE_TYPE, DataType::Utf8, false)),
+                Arc::new(Field::new(CHANGE_ORDINAL, DataType::Int64, false)),
+                Arc::new(Field::new(COMMIT_SNAPSHOT_ID, DataType::Int64, false)),
+            ]);
+            let mut columns = batch.columns().to_vec();
+            columns.extend([
+                Arc::new(StringArray::from(vec![change_type; num_rows])) as ArrayRef,
+                Arc::new(Int64Array::from(vec![task.change_ordinal; num_rows])) as ArrayRef,
+                Arc::new(Int64Array::from(vec![task.commit_snapshot_id; num_rows])) as ArrayRef,
+            ]);
+            Ok(RecordBatch::try_new(
+                Arc::new(ArrowSchema::new(fields)),
+                columns,
+            )?)
+        }),
+    )
+}
+
+/// Mark the rows of a record batch that are deleted by the sorted positions or the equality delete files.
+/// `offset` is the position of the first row of the batch in the data file.
+pub fn deleted_rows(
+    batch: &RecordBatch,
+    offset: i64,
+    positions: &[i64],
+    equality_deletes: &[Arc<EqualityDeleteFile>],
+) -> Result<BooleanArray, ArrowError> {
+    let deleted = (0..batch.num_rows() as i64)
+        .map(|row| Some(positions.binary_search(&(offset + row)).is_ok()))
+        .collect::<BooleanArray>();
+    equality_deletes
+        .iter()
+        .try_fold(deleted, |deleted, delete| {
+            or(&deleted, &delete.deleted(batch)?)
         })
-        .filter_map(|x| async move { x.ok() })
-        .flat_map_unordered(None, convert::identity)
 }
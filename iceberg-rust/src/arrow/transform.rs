@@ -5,56 +5,467 @@
 use std::sync::Arc;
 
 use arrow::{
-    array::{as_primitive_array, Array, ArrayRef},
-    compute::{date_part, DatePart},
-    datatypes::{DataType, Date32Type, TimeUnit, TimestampMicrosecondType},
+    array::{
+        new_null_array, Array, ArrayRef, AsArray, BinaryArray, FixedSizeBinaryArray, Int32Array,
+        LargeBinaryArray, LargeStringArray, PrimitiveArray, StringArray,
+    },
+    compute::{cast, try_unary, unary},
+    datatypes::{
+        ArrowPrimitiveType, DataType, Date32Type, Decimal128Type, Int32Type, Int64Type,
+        Time64MicrosecondType, TimeUnit, TimestampMicrosecondType,
+    },
     error::ArrowError,
 };
 
-use iceberg_rust_spec::spec::partition::Transform;
+use iceberg_rust_spec::spec::{
+    partition::{
+        bucket, days_from_micros, decimal_bytes, hours_from_micros, months_from_days,
+        years_from_days, Transform,
+    },
+    types::{PrimitiveType, Type},
+};
+use uuid::Uuid;
+
+use crate::error::Error;
 
-/// Perform iceberg transform on arrow array
-pub fn transform_arrow(array: ArrayRef, transform: &Transform) -> Result<ArrayRef, ArrowError> {
+/// Perform iceberg transform on an arrow array of the source type. The results are the same as the ones of the transforms of single values.
+/// Timestamps with and without timezone hold microseconds since 1970-01-01 00:00:00 UTC and uuids are stored as strings.
+pub fn transform_arrow(
+    array: ArrayRef,
+    transform: &Transform,
+    source_type: &Type,
+) -> Result<ArrayRef, ArrowError> {
     match (array.data_type(), transform) {
         (_, Transform::Identity) => Ok(array),
-        (DataType::Date32, Transform::Day) => Ok(Arc::new(date_part(
-            as_primitive_array::<Date32Type>(&array),
-            DatePart::Day,
-        )?) as Arc<dyn Array>),
-        (DataType::Date32, Transform::Month) => Ok(Arc::new(date_part(
-            as_primitive_array::<Date32Type>(&array),
-            DatePart::Month,
-        )?) as Arc<dyn Array>),
-        (DataType::Date32, Transform::Year) => Ok(Arc::new(date_part(
-            as_primitive_array::<Date32Type>(&array),
-            DatePart::Year,
-        )?) as Arc<dyn Array>),
-        (DataType::Timestamp(TimeUnit::Microsecond, None), Transform::Hour) => {
-            Ok(Arc::new(date_part(
-                as_primitive_array::<TimestampMicrosecondType>(&array),
-                DatePart::Hour,
-            )?) as Arc<dyn Array>)
-        }
-        (DataType::Timestamp(TimeUnit::Microsecond, None), Transform::Day) => {
-            Ok(Arc::new(date_part(
-                as_primitive_array::<TimestampMicrosecondType>(&array),
-                DatePart::Day,
-            )?) as Arc<dyn Array>)
-        }
-        (DataType::Timestamp(TimeUnit::Microsecond, None), Transform::Month) => {
-            Ok(Arc::new(date_part(
-                as_primitive_array::<TimestampMicrosecondType>(&array),
-                DatePart::Month,
-            )?) as Arc<dyn Array>)
-        }
-        (DataType::Timestamp(TimeUnit::Microsecond, None), Transform::Year) => {
-            Ok(Arc::new(date_part(
-                as_primitive_array::<TimestampMicrosecondType>(&array),
-                DatePart::Year,
-            )?) as Arc<dyn Array>)
-        }
-        _ => Err(ArrowError::ComputeError(
-            "Failed to perform transform for datatype".to_string(),
+        (data_type, Transform::Void) => Ok(new_null_array(data_type, array.len())),
+        // The bucket of a uuid is the hash of its 16 bytes
+        (DataType::Utf8, Transform::Bucket(n))
+            if matches!(source_type, Type::Primitive(PrimitiveType::Uuid)) =>
+        {
+            bucket_array(&uuid_array(&array)?, *n)
+        }
+        (_, Transform::Bucket(n)) => bucket_array(&array, *n),
+        (_, Transform::Truncate(width)) => truncate_array(&array, *width as usize),
+        (DataType::Timestamp(unit, tz), _) if *unit != TimeUnit::Microsecond => transform_arrow(
+            cast(
+                &array,
+                &DataType::Timestamp(TimeUnit::Microsecond, tz.clone()),
+            )?,
+            transform,
+            source_type,
+        ),
+        (DataType::Date32, Transform::Day) => Ok(Arc::new(unary::<_, _, Int32Type>(
+            array.as_primitive::<Date32Type>(),
+            |days| days,
+        ))),
+        (DataType::Date32, Transform::Month) => Ok(Arc::new(try_unary::<_, _, Int32Type>(
+            array.as_primitive::<Date32Type>(),
+            |days| months_from_days(days).map_err(transform_error),
+        )?)),
+        (DataType::Date32, Transform::Year) => Ok(Arc::new(try_unary::<_, _, Int32Type>(
+            array.as_primitive::<Date32Type>(),
+            |days| years_from_days(days).map_err(transform_error),
+        )?)),
+        (DataType::Timestamp(TimeUnit::Microsecond, _), Transform::Hour) => {
+            Ok(Arc::new(unary::<_, _, Int32Type>(
+                array.as_primitive::<TimestampMicrosecondType>(),
+                hours_from_micros,
+            )))
+        }
+        (DataType::Timestamp(TimeUnit::Microsecond, _), Transform::Day) => {
+            Ok(Arc::new(unary::<_, _, Int32Type>(
+                array.as_primitive::<TimestampMicrosecondType>(),
+                days_from_micros,
+            )))
+        }
+        (DataType::Timestamp(TimeUnit::Microsecond, _), Transform::Month) => {
+            Ok(Arc::new(try_unary::<_, _, Int32Type>(
+                array.as_primitive::<TimestampMicrosecondType>(),
+                |micros| months_from_days(days_from_micros(micros)).map_err(transform_error),
+            )?))
+        }
+        (DataType::Timestamp(TimeUnit::Microsecond, _), Transform::Year) => {
+            Ok(Arc::new(try_unary::<_, _, Int32Type>(
+                array.as_primitive::<TimestampMicrosecondType>(),
+                |micros| years_from_days(days_from_micros(micros)).map_err(transform_error),
+            )?))
+        }
+        (data_type, transform) => Err(ArrowError::ComputeError(format!(
+            "Failed to perform transform {} for datatype {}",
+            transform, data_type
+        ))),
+    }
+}
+
+/// Hash the values with the bucket transform. Ints and dates are hashed like longs.
+fn bucket_array(array: &ArrayRef, n: u32) -> Result<ArrayRef, ArrowError> {
+    match array.data_type() {
+        DataType::Int32 => Ok(bucket_primitive::<Int32Type>(array, n, |value| {
+            (value as i64).to_le_bytes().to_vec()
+        })),
+        DataType::Date32 => Ok(bucket_primitive::<Date32Type>(array, n, |value| {
+            (value as i64).to_le_bytes().to_vec()
+        })),
+        DataType::Int64 => Ok(bucket_primitive::<Int64Type>(array, n, |value| {
+            value.to_le_bytes().to_vec()
+        })),
+        DataType::Time64(TimeUnit::Microsecond) => Ok(bucket_primitive::<Time64MicrosecondType>(
+            array,
+            n,
+            |value| value.to_le_bytes().to_vec(),
+        )),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => Ok(bucket_primitive::<
+            TimestampMicrosecondType,
+        >(array, n, |value| {
+            value.to_le_bytes().to_vec()
+        })),
+        DataType::Timestamp(_, tz) => bucket_array(
+            &cast(
+                array,
+                &DataType::Timestamp(TimeUnit::Microsecond, tz.clone()),
+            )?,
+            n,
+        ),
+        DataType::Decimal128(_, _) => {
+            Ok(bucket_primitive::<Decimal128Type>(array, n, decimal_bytes))
+        }
+        DataType::Utf8 => Ok(bucket_bytes(
+            array
+                .as_string::<i32>()
+                .iter()
+                .map(|x| x.map(str::as_bytes)),
+            n,
+        )),
+        DataType::LargeUtf8 => Ok(bucket_bytes(
+            array
+                .as_string::<i64>()
+                .iter()
+                .map(|x| x.map(str::as_bytes)),
+            n,
+        )),
+        DataType::Binary => Ok(bucket_bytes(array.as_binary::<i32>().iter(), n)),
+        DataType::LargeBinary => Ok(bucket_bytes(array.as_binary::<i64>().iter(), n)),
+        DataType::FixedSizeBinary(_) => Ok(bucket_bytes(array.as_fixed_size_binary().iter(), n)),
+        data_type => Err(ArrowError::ComputeError(format!(
+            "Failed to perform bucket transform for datatype {}",
+            data_type
+        ))),
+    }
+}
+
+/// Parse the uuid strings of an arrow array into 16 byte fixed size binaries
+fn uuid_array(array: &ArrayRef) -> Result<ArrayRef, ArrowError> {
+    let uuids = array
+        .as_string::<i32>()
+        .iter()
+        .map(|uuid| {
+            uuid.map(|uuid| {
+                Ok::<_, ArrowError>(Uuid::parse_str(uuid).map_err(Error::from)?.into_bytes())
+            })
+            .transpose()
+        })
+        .collect::<Result<Vec<_>, ArrowError>>()?;
+    Ok(Arc::new(
+        FixedSizeBinaryArray::try_from_sparse_iter_with_size(uuids.into_iter(), 16)?,
+    ))
+}
+
+fn bucket_primitive<T: ArrowPrimitiveType>(
+    array: &ArrayRef,
+    n: u32,
+    bytes: impl Fn(T::Native) -> Vec<u8>,
+) -> ArrayRef {
+    Arc::new(unary::<_, _, Int32Type>(
+        array.as_primitive::<T>(),
+        |value| bucket(&bytes(value), n),
+    ))
+}
+
+fn bucket_bytes<'a>(values: impl Iterator<Item = Option<&'a [u8]>>, n: u32) -> ArrayRef {
+    Arc::new(
+        values
+            .map(|value| value.map(|bytes| bucket(bytes, n)))
+            .collect::<Int32Array>(),
+    )
+}
+
+/// Truncate the values to the width. Strings are truncated to a number of code points and decimals keep their precision and scale.
+fn truncate_array(array: &ArrayRef, width: usize) -> Result<ArrayRef, ArrowError> {
+    match array.data_type() {
+        DataType::Int32 => Ok(Arc::new(unary::<_, _, Int32Type>(
+            array.as_primitive::<Int32Type>(),
+            |value| value - value.rem_euclid(width as i32),
+        ))),
+        DataType::Int64 => Ok(Arc::new(unary::<_, _, Int64Type>(
+            array.as_primitive::<Int64Type>(),
+            |value| value - value.rem_euclid(width as i64),
+        ))),
+        DataType::Decimal128(precision, scale) => {
+            let truncated: PrimitiveArray<Decimal128Type> =
+                unary(array.as_primitive::<Decimal128Type>(), |value| {
+                    value - value.rem_euclid(width as i128)
+                });
+            Ok(Arc::new(
+                truncated.with_precision_and_scale(*precision, *scale)?,
+            ))
+        }
+        DataType::Utf8 => Ok(Arc::new(
+            array
+                .as_string::<i32>()
+                .iter()
+                .map(|value| value.map(|value| truncate_str(value, width)))
+                .collect::<StringArray>(),
+        )),
+        DataType::LargeUtf8 => Ok(Arc::new(
+            array
+                .as_string::<i64>()
+                .iter()
+                .map(|value| value.map(|value| truncate_str(value, width)))
+                .collect::<LargeStringArray>(),
         )),
+        DataType::Binary => Ok(Arc::new(
+            array
+                .as_binary::<i32>()
+                .iter()
+                .map(|value| value.map(|value| &value[..width.min(value.len())]))
+                .collect::<BinaryArray>(),
+        )),
+        DataType::LargeBinary => Ok(Arc::new(
+            array
+                .as_binary::<i64>()
+                .iter()
+                .map(|value| value.map(|value| &value[..width.min(value.len())]))
+                .collect::<LargeBinaryArray>(),
+        )),
+        data_type => Err(ArrowError::ComputeError(format!(
+            "Failed to perform truncate transform for datatype {}",
+            data_type
+        ))),
+    }
+}
+
+fn truncate_str(value: &str, width: usize) -> &str {
+    match value.char_indices().nth(width) {
+        Some((index, _)) => &value[..index],
+        None => value,
+    }
+}
+
+fn transform_error(err: iceberg_rust_spec::error::Error) -> ArrowError {
+    ArrowError::from(Error::from(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{
+            Array, ArrayRef, AsArray, BinaryArray, Date32Array, Decimal128Array, Int32Array,
+            Int64Array, StringArray, Time64MicrosecondArray, TimestampMicrosecondArray,
+            TimestampMillisecondArray,
+        },
+        datatypes::{DataType, Decimal128Type, Int32Type, Int64Type},
+    };
+    use iceberg_rust_spec::spec::{
+        partition::Transform,
+        types::{PrimitiveType, Type},
+        values::Value,
+    };
+    use uuid::Uuid;
+
+    use super::transform_arrow;
+
+    fn value(array: &ArrayRef, index: usize) -> Option<Value> {
+        if array.is_null(index) {
+            return None;
+        }
+        match array.data_type() {
+            DataType::Int32 => Some(Value::Int(array.as_primitive::<Int32Type>().value(index))),
+            DataType::Int64 => Some(Value::LongInt(
+                array.as_primitive::<Int64Type>().value(index),
+            )),
+            DataType::Utf8 => Some(Value::String(
+                array.as_string::<i32>().value(index).to_owned(),
+            )),
+            DataType::Binary => Some(Value::Binary(
+                array.as_binary::<i32>().value(index).to_vec(),
+            )),
+            _ => panic!("Unexpected datatype"),
+        }
+    }
+
+    /// Compare the vectorized transforms with the transforms of the single values
+    fn check(
+        array: ArrayRef,
+        source_type: PrimitiveType,
+        values: Vec<Option<Value>>,
+        transforms: &[Transform],
+    ) {
+        for transform in transforms {
+            let result = transform_arrow(
+                array.clone(),
+                transform,
+                &Type::Primitive(source_type.clone()),
+            )
+            .unwrap();
+            assert_eq!(result.len(), values.len());
+            for (index, expected) in values.iter().enumerate() {
+                assert_eq!(
+                    value(&result, index),
+                    expected
+                        .as_ref()
+                        .and_then(|value| value.tranform(transform).unwrap()),
+                    "{} of {:?}",
+                    transform,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_transforms_match_values() {
+        let buckets = [Transform::Bucket(16), Transform::Bucket(1 << 31)];
+        check(
+            Arc::new(Int32Array::from(vec![Some(34), None, Some(-7)])),
+            PrimitiveType::Int,
+            vec![Some(Value::Int(34)), None, Some(Value::Int(-7))],
+            &[&buckets[..], &[Transform::Truncate(10)]].concat(),
+        );
+        check(
+            Arc::new(Int64Array::from(vec![Some(34), None, Some(-7)])),
+            PrimitiveType::Long,
+            vec![Some(Value::LongInt(34)), None, Some(Value::LongInt(-7))],
+            &[&buckets[..], &[Transform::Truncate(10)]].concat(),
+        );
+        check(
+            Arc::new(Date32Array::from(vec![Some(17486), None, Some(-1)])),
+            PrimitiveType::Date,
+            vec![Some(Value::Date(17486)), None, Some(Value::Date(-1))],
+            &[
+                &buckets[..],
+                &[Transform::Year, Transform::Month, Transform::Day],
+            ]
+            .concat(),
+        );
+        check(
+            Arc::new(Time64MicrosecondArray::from(vec![Some(81068000000), None])),
+            PrimitiveType::Time,
+            vec![Some(Value::Time(81068000000)), None],
+            &buckets,
+        );
+        let timestamps = vec![Some(1510871468000000), None, Some(-1)];
+        let transforms = [
+            &buckets[..],
+            &[
+                Transform::Year,
+                Transform::Month,
+                Transform::Day,
+                Transform::Hour,
+            ],
+        ]
+        .concat();
+        check(
+            Arc::new(TimestampMicrosecondArray::from(timestamps.clone())),
+            PrimitiveType::Timestamp,
+            timestamps.iter().map(|x| x.map(Value::Timestamp)).collect(),
+            &transforms,
+        );
+        check(
+            Arc::new(TimestampMicrosecondArray::from(timestamps.clone()).with_timezone("UTC")),
+            PrimitiveType::Timestamptz,
+            timestamps
+                .iter()
+                .map(|x| x.map(Value::TimestampTZ))
+                .collect(),
+            &transforms,
+        );
+        check(
+            Arc::new(
+                TimestampMillisecondArray::from(vec![Some(1510871468000), None])
+                    .with_timezone("-08:00"),
+            ),
+            PrimitiveType::Timestamptz,
+            vec![Some(Value::TimestampTZ(1510871468000000)), None],
+            &transforms,
+        );
+        check(
+            Arc::new(StringArray::from(vec![Some("iceberg"), None, Some("äöü")])),
+            PrimitiveType::String,
+            vec![
+                Some(Value::String("iceberg".to_owned())),
+                None,
+                Some(Value::String("äöü".to_owned())),
+            ],
+            &[&buckets[..], &[Transform::Truncate(2)]].concat(),
+        );
+        check(
+            Arc::new(BinaryArray::from(vec![
+                Some([0u8, 1, 2, 3].as_slice()),
+                None,
+            ])),
+            PrimitiveType::Binary,
+            vec![Some(Value::Binary(vec![0, 1, 2, 3])), None],
+            &[&buckets[..], &[Transform::Truncate(2)]].concat(),
+        );
+        let uuid = Uuid::parse_str("f79c3e09-677c-4bbd-a479-3f349cb785e7").unwrap();
+        check(
+            Arc::new(StringArray::from(vec![Some(uuid.to_string()), None])),
+            PrimitiveType::Uuid,
+            vec![Some(Value::UUID(uuid)), None],
+            &buckets,
+        );
+    }
+
+    #[test]
+    fn test_decimal_transforms() {
+        let array: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![Some(1420), None, Some(-5)])
+                .with_precision_and_scale(9, 2)
+                .unwrap(),
+        );
+        let buckets = transform_arrow(
+            array.clone(),
+            &Transform::Bucket(1 << 31),
+            &Type::Primitive(PrimitiveType::Decimal {
+                precision: 9,
+                scale: 2,
+            }),
+        )
+        .unwrap();
+        assert_eq!(
+            buckets.as_primitive::<Int32Type>().value(0),
+            (-500754589_i64 + (1 << 31)) as i32
+        );
+        assert!(buckets.is_null(1));
+
+        let truncated = transform_arrow(
+            array,
+            &Transform::Truncate(10),
+            &Type::Primitive(PrimitiveType::Decimal {
+                precision: 9,
+                scale: 2,
+            }),
+        )
+        .unwrap();
+        assert_eq!(truncated.data_type(), &DataType::Decimal128(9, 2));
+        let truncated = truncated.as_primitive::<Decimal128Type>();
+        assert_eq!(truncated.value(0), 1420);
+        assert!(truncated.is_null(1));
+        assert_eq!(truncated.value(2), -10);
+    }
+
+    #[test]
+    fn test_void_transform() {
+        let array: ArrayRef = Arc::new(Int32Array::from(vec![1, 2]));
+        let result = transform_arrow(
+            array,
+            &Transform::Void,
+            &Type::Primitive(PrimitiveType::Int),
+        )
+        .unwrap();
+        assert_eq!(result.data_type(), &DataType::Int32);
+        assert_eq!(result.null_count(), 2);
     }
 }
@@ -0,0 +1,222 @@
+/*!
+ * Functions to write change streams with inserted, updated and deleted rows to an iceberg table
+*/
+
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, AsArray, UInt32Array},
+    compute::{cast, take},
+    datatypes::Schema as ArrowSchema,
+    error::ArrowError,
+    record_batch::RecordBatch,
+    row::{RowConverter, SortField},
+};
+use futures::{stream, Stream, TryStreamExt};
+use iceberg_rust_spec::spec::{manifest::DataFile, table_metadata::TableMetadata};
+use object_store::ObjectStore;
+
+use crate::error::Error;
+
+use super::write::{write_equality_deletes, write_parquet_partitioned};
+
+/// Operation of a row in a change stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOperation {
+    /// Row was inserted
+    Insert,
+    /// Row was updated, the row contains the new values
+    Update,
+    /// Row was deleted, only the identifier columns are required
+    Delete,
+}
+
+impl FromStr for ChangeOperation {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "i" | "insert" => Ok(ChangeOperation::Insert),
+            "u" | "update" => Ok(ChangeOperation::Update),
+            "d" | "delete" => Ok(ChangeOperation::Delete),
+            _ => Err(Error::InvalidFormat(format!("Change operation {}", s))),
+        }
+    }
+}
+
+/// Writes a change stream to data files and equality delete files keyed on the identifier fields of the schema.
+/// The operation of every row is read from the `operation_column`, see [ChangeOperation]. Only the last change of every key is written:
+/// every changed key is deleted and inserted or updated rows are written again. The stream is collected into memory.
+/// Returns the data files and the delete files, which have to be committed together, for example with [crate::table::transaction::TableTransaction::upsert].
+/// Does not perform any operation on an iceberg table.
+pub async fn write_upsert(
+    metadata: &TableMetadata,
+    batches: impl Stream<Item = Result<RecordBatch, ArrowError>> + Send,
+    operation_column: &str,
+    object_store: Arc<dyn ObjectStore>,
+    branch: Option<&str>,
+) -> Result<(Vec<DataFile>, Vec<DataFile>), ArrowError> {
+    let schema = metadata.current_schema(branch).map_err(Error::from)?;
+    let equality_ids = schema
+        .identifier_field_ids()
+        .clone()
+        .filter(|ids| !ids.is_empty())
+        .ok_or(Error::NotFound(
+            "Identifier".to_owned(),
+            "fields".to_owned(),
+        ))?;
+
+    let arrow_schema: Arc<ArrowSchema> =
+        Arc::new((schema.fields()).try_into().map_err(Error::from)?);
+    let key_fields = equality_ids
+        .iter()
+        .map(|id| {
+            let name = &schema
+                .get(*id as usize)
+                .ok_or(Error::NotFound("Field".to_owned(), id.to_string()))?
+                .name;
+            Ok(arrow_schema.field_with_name(name)?.clone())
+        })
+        .collect::<Result<Vec<_>, ArrowError>>()?;
+    let key_schema = Arc::new(ArrowSchema::new(key_fields));
+
+    let batches: Vec<RecordBatch> = batches.try_collect().await?;
+
+    let converter = RowConverter::new(
+        key_schema
+            .fields()
+            .iter()
+            .map(|field| SortField::new(field.data_type().clone()))
+            .collect(),
+    )?;
+
+    // The last change of every key determines its state after the commit
+    let mut changes: HashMap<Vec<u8>, (usize, u32, ChangeOperation)> = HashMap::new();
+    for (i, batch) in batches.iter().enumerate() {
+        let operations = batch
+            .column_by_name(operation_column)
+            .and_then(|column| column.as_string_opt::<i32>())
+            .ok_or(ArrowError::SchemaError(format!(
+                "Operation column {} doesn't exist",
+                operation_column
+            )))?;
+        let keys = key_columns(batch, &key_schema)?;
+        let rows = converter.convert_columns(&keys)?;
+        for (j, (row, operation)) in rows.iter().zip(operations.iter()).enumerate() {
+            let operation = operation
+                .ok_or(Error::InvalidFormat("Null change operation".to_owned()))?
+                .parse::<ChangeOperation>()?;
+            changes.insert(row.as_ref().to_vec(), (i, j as u32, operation));
+        }
+    }
+
+    let mut delete_indices = vec![Vec::new(); batches.len()];
+    let mut data_indices = vec![Vec::new(); batches.len()];
+    for (i, j, operation) in changes.into_values() {
+        delete_indices[i].push(j);
+        if operation != ChangeOperation::Delete {
+            data_indices[i].push(j);
+        }
+    }
+
+    let mut data_batches = Vec::new();
+    let mut delete_batches = Vec::new();
+    for ((batch, mut data_indices), mut delete_indices) in batches
+        .iter()
+        .zip(data_indices.into_iter())
+        .zip(delete_indices.into_iter())
+    {
+        if !data_indices.is_empty() {
+            data_indices.sort_unstable();
+            let indices = UInt32Array::from(data_indices);
+            let columns = arrow_schema
+                .fields()
+                .iter()
+                .map(|field| {
+                    let column =
+                        batch
+                            .column_by_name(field.name())
+                            .ok_or(ArrowError::SchemaError(format!(
+                                "Column {} doesn't exist",
+                                field.name()
+                            )))?;
+                    take(column, &indices, None)
+                })
+                .collect::<Result<Vec<_>, ArrowError>>()?;
+            data_batches.push(RecordBatch::try_new(arrow_schema.clone(), columns)?);
+        }
+        if !delete_indices.is_empty() {
+            delete_indices.sort_unstable();
+            let indices = UInt32Array::from(delete_indices);
+            let columns = key_columns(batch, &key_schema)?
+                .iter()
+                .map(|column| take(column, &indices, None))
+                .collect::<Result<Vec<_>, ArrowError>>()?;
+            delete_batches.push(RecordBatch::try_new(key_schema.clone(), columns)?);
+        }
+    }
+
+    let data_files = if data_batches.is_empty() {
+        Vec::new()
+    } else {
+        write_parquet_partitioned(
+            metadata,
+            stream::iter(data_batches.into_iter().map(Ok)),
+            object_store.clone(),
+            branch,
+        )
+        .await?
+    };
+
+    let delete_files = if delete_batches.is_empty() {
+        Vec::new()
+    } else {
+        write_equality_deletes(
+            metadata,
+            stream::iter(delete_batches.into_iter().map(Ok)),
+            &equality_ids,
+            object_store,
+            branch,
+        )
+        .await?
+    };
+
+    Ok((data_files, delete_files))
+}
+
+fn key_columns(batch: &RecordBatch, key_schema: &ArrowSchema) -> Result<Vec<ArrayRef>, ArrowError> {
+    key_schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let column = batch
+                .column_by_name(field.name())
+                .ok_or(ArrowError::SchemaError(format!(
+                    "Identifier column {} doesn't exist",
+                    field.name()
+                )))?;
+            cast(column, field.data_type())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChangeOperation;
+
+    #[test]
+    fn test_change_operation_from_str() {
+        assert_eq!(
+            "I".parse::<ChangeOperation>().unwrap(),
+            ChangeOperation::Insert
+        );
+        assert_eq!(
+            "update".parse::<ChangeOperation>().unwrap(),
+            ChangeOperation::Update
+        );
+        assert_eq!(
+            "D".parse::<ChangeOperation>().unwrap(),
+            ChangeOperation::Delete
+        );
+        assert!("x".parse::<ChangeOperation>().is_err());
+    }
+}
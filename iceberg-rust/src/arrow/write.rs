@@ -2,51 +2,377 @@
  * Functions to write arrow record batches to an iceberg table
 */
 
-use futures::{
-    channel::mpsc::{channel, unbounded, Receiver, Sender},
-    lock::Mutex,
-    stream, SinkExt, StreamExt, TryStreamExt,
-};
+use futures::{channel::mpsc::unbounded, stream, SinkExt, StreamExt, TryStreamExt};
 use object_store::ObjectStore;
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
-};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 use tokio::io::AsyncWrite;
 
-use arrow::{datatypes::Schema as ArrowSchema, error::ArrowError, record_batch::RecordBatch};
+use arrow::{
+    array::{Int64Array, StringArray},
+    datatypes::Schema as ArrowSchema,
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
 use futures::Stream;
 use iceberg_rust_spec::{
     spec::{
-        manifest::DataFile, partition::PartitionSpec, schema::Schema,
-        table_metadata::TableMetadata, values::Value,
+        manifest::{Content, DataFile, FileFormat},
+        partition::PartitionSpec,
+        schema::Schema,
+        table_metadata::TableMetadata,
+        types::StructType,
+        values::{Struct, Value},
     },
     util::strip_prefix,
 };
 use parquet::{
     arrow::AsyncArrowWriter,
-    basic::{Compression, ZstdLevel},
+    basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel},
     file::properties::WriterProperties,
+    schema::types::ColumnPath,
 };
 use uuid::Uuid;
 
-use crate::{catalog::bucket::Bucket, error::Error, file_format::parquet::parquet_to_datafile};
+use crate::{
+    catalog::bucket::Bucket,
+    error::Error,
+    file_format::{
+        avro::AvroWriter, metrics::MetricsConfig, orc::OrcWriter, parquet::parquet_to_datafile,
+    },
+};
+
+use super::{delete::position_delete_schema, partition::partition_record_batches};
+
+/// Table property for the file format of new data files, either parquet, orc or avro
+pub static WRITE_FORMAT_DEFAULT: &str = "write.format.default";
+/// Table property for the target size in bytes of new data files
+pub static WRITE_TARGET_FILE_SIZE_BYTES: &str = "write.target-file-size-bytes";
+/// Table property for the compression codec of parquet files: zstd, gzip, brotli, snappy, lz4 or uncompressed
+pub static WRITE_PARQUET_COMPRESSION_CODEC: &str = "write.parquet.compression-codec";
+/// Table property for the compression level of the zstd, gzip and brotli codecs
+pub static WRITE_PARQUET_COMPRESSION_LEVEL: &str = "write.parquet.compression-level";
+/// Table property for the size in bytes at which a parquet row group is flushed
+pub static WRITE_PARQUET_ROW_GROUP_SIZE_BYTES: &str = "write.parquet.row-group-size-bytes";
+/// Table property for the maximum size in bytes of a parquet data page
+pub static WRITE_PARQUET_PAGE_SIZE_BYTES: &str = "write.parquet.page-size-bytes";
+/// Table property that enables dictionary encoding of parquet columns
+pub static WRITE_PARQUET_DICT_ENABLED: &str = "write.parquet.dict-enabled";
+/// Table property for the maximum size in bytes of a parquet dictionary page
+pub static WRITE_PARQUET_DICT_SIZE_BYTES: &str = "write.parquet.dict-size-bytes";
+/// Prefix of the table properties that enable a bloom filter for a parquet column, followed by the column name
+pub static WRITE_PARQUET_BLOOM_FILTER_ENABLED_COLUMN: &str =
+    "write.parquet.bloom-filter-enabled.column.";
+/// Prefix of the table properties for the false positive probability of the bloom filter of a parquet column, followed by the column name
+pub static WRITE_PARQUET_BLOOM_FILTER_FPP_COLUMN: &str = "write.parquet.bloom-filter-fpp.column.";
 
-use super::partition::partition_record_batches;
+static WRITE_TARGET_FILE_SIZE_BYTES_DEFAULT: usize = 512 * 1024 * 1024;
+static WRITE_PARQUET_ROW_GROUP_SIZE_BYTES_DEFAULT: usize = 128 * 1024 * 1024;
+static WRITE_PARQUET_PAGE_SIZE_BYTES_DEFAULT: usize = 1024 * 1024;
+static WRITE_PARQUET_DICT_SIZE_BYTES_DEFAULT: usize = 2 * 1024 * 1024;
+static WRITE_PARQUET_ZSTD_LEVEL_DEFAULT: i32 = 1;
+
+/// Settings of the data file writers, read from the table properties. The fields can be changed to override the table properties for a single write.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriterConfig {
+    /// Target size in bytes of new data files. A new file is started once the estimated size of the current file exceeds it.
+    pub target_file_size: usize,
+    /// Compression of parquet files
+    pub compression: Compression,
+    /// Size in bytes at which a parquet row group is flushed
+    pub row_group_size: usize,
+    /// Maximum size in bytes of a parquet data page
+    pub page_size: usize,
+    /// Whether parquet columns are dictionary encoded
+    pub dictionary_enabled: bool,
+    /// Maximum size in bytes of a parquet dictionary page
+    pub dictionary_page_size: usize,
+    /// Columns that get a parquet bloom filter
+    pub bloom_filter_columns: Vec<String>,
+    /// False positive probabilities of the bloom filters by column, the parquet default if missing
+    pub bloom_filter_fpp: HashMap<String, f64>,
+    /// Metrics modes of the columns
+    pub metrics: MetricsConfig,
+}
+
+impl WriterConfig {
+    /// Read the writer settings from the table properties
+    pub fn new(properties: &HashMap<String, String>) -> Result<Self, Error> {
+        fn property<T: FromStr>(properties: &HashMap<String, String>, key: &str, default: T) -> T {
+            properties
+                .get(key)
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(default)
+        }
+        let level = properties
+            .get(WRITE_PARQUET_COMPRESSION_LEVEL)
+            .map(|level| {
+                level.parse::<i32>().map_err(|_| {
+                    Error::InvalidFormat(format!("Parquet compression level {}", level))
+                })
+            })
+            .transpose()?;
+        let compression = match properties
+            .get(WRITE_PARQUET_COMPRESSION_CODEC)
+            .map(|codec| codec.to_lowercase())
+            .as_deref()
+        {
+            None | Some("zstd") => Compression::ZSTD(ZstdLevel::try_new(
+                level.unwrap_or(WRITE_PARQUET_ZSTD_LEVEL_DEFAULT),
+            )?),
+            Some("gzip") => Compression::GZIP(match level {
+                Some(level) => GzipLevel::try_new(level as u32)?,
+                None => GzipLevel::default(),
+            }),
+            Some("brotli") => Compression::BROTLI(match level {
+                Some(level) => BrotliLevel::try_new(level as u32)?,
+                None => BrotliLevel::default(),
+            }),
+            Some("snappy") => Compression::SNAPPY,
+            Some("lz4") => Compression::LZ4_RAW,
+            Some("uncompressed") | Some("none") => Compression::UNCOMPRESSED,
+            Some(codec) => {
+                return Err(Error::NotSupported(format!(
+                    "Parquet compression codec {}",
+                    codec
+                )))
+            }
+        };
+        let mut bloom_filter_columns = properties
+            .iter()
+            .filter_map(|(key, value)| {
+                let column = key.strip_prefix(WRITE_PARQUET_BLOOM_FILTER_ENABLED_COLUMN)?;
+                value
+                    .eq_ignore_ascii_case("true")
+                    .then(|| column.to_owned())
+            })
+            .collect::<Vec<_>>();
+        bloom_filter_columns.sort();
+        Ok(WriterConfig {
+            target_file_size: property(
+                properties,
+                WRITE_TARGET_FILE_SIZE_BYTES,
+                WRITE_TARGET_FILE_SIZE_BYTES_DEFAULT,
+            ),
+            compression,
+            row_group_size: property(
+                properties,
+                WRITE_PARQUET_ROW_GROUP_SIZE_BYTES,
+                WRITE_PARQUET_ROW_GROUP_SIZE_BYTES_DEFAULT,
+            ),
+            page_size: property(
+                properties,
+                WRITE_PARQUET_PAGE_SIZE_BYTES,
+                WRITE_PARQUET_PAGE_SIZE_BYTES_DEFAULT,
+            ),
+            dictionary_enabled: property(properties, WRITE_PARQUET_DICT_ENABLED, true),
+            dictionary_page_size: property(
+                properties,
+                WRITE_PARQUET_DICT_SIZE_BYTES,
+                WRITE_PARQUET_DICT_SIZE_BYTES_DEFAULT,
+            ),
+            bloom_filter_columns,
+            bloom_filter_fpp: properties
+                .iter()
+                .filter_map(|(key, value)| {
+                    let column = key.strip_prefix(WRITE_PARQUET_BLOOM_FILTER_FPP_COLUMN)?;
+                    Some((column.to_owned(), value.parse().ok()?))
+                })
+                .collect(),
+            metrics: MetricsConfig::new(properties),
+        })
+    }
 
-const MAX_PARQUET_SIZE: usize = 512_000_000;
+    /// Override the target size in bytes of new data files
+    pub fn with_target_file_size(mut self, target_file_size: usize) -> Self {
+        self.target_file_size = target_file_size;
+        self
+    }
 
-/// Partitions arrow record batches and writes them to parquet files. Does not perform any operation on an iceberg table.
+    /// Properties of the parquet writer
+    pub fn writer_properties(&self) -> WriterProperties {
+        self.bloom_filter_columns
+            .iter()
+            .fold(
+                WriterProperties::builder()
+                    .set_compression(self.compression)
+                    .set_data_page_size_limit(self.page_size)
+                    .set_dictionary_enabled(self.dictionary_enabled)
+                    .set_dictionary_page_size_limit(self.dictionary_page_size),
+                |builder, column| {
+                    let path = ColumnPath::new(column.split('.').map(ToOwned::to_owned).collect());
+                    match self.bloom_filter_fpp.get(column) {
+                        Some(fpp) => builder
+                            .set_column_bloom_filter_enabled(path.clone(), true)
+                            .set_column_bloom_filter_fpp(path, *fpp),
+                        None => builder.set_column_bloom_filter_enabled(path, true),
+                    }
+                },
+            )
+            .build()
+    }
+}
+
+/// Partitions arrow record batches and writes them to data files in the format of the `write.format.default` table property, parquet by default.
+/// Does not perform any operation on an iceberg table.
 pub async fn write_parquet_partitioned(
     metadata: &TableMetadata,
     batches: impl Stream<Item = Result<RecordBatch, ArrowError>> + Send,
     object_store: Arc<dyn ObjectStore>,
     branch: Option<&str>,
 ) -> Result<Vec<DataFile>, ArrowError> {
-    let location = &metadata.location;
     let schema = metadata.current_schema(branch).map_err(Error::from)?;
     let partition_spec = metadata.default_partition_spec().map_err(Error::from)?;
 
+    write_partitioned(
+        &metadata.location,
+        schema,
+        partition_spec,
+        batches,
+        object_store,
+        write_format(&metadata.properties)?,
+        &WriterConfig::new(&metadata.properties)?,
+    )
+    .await
+}
+
+/// Partitions arrow record batches and writes them to data files in the format of the `write.format.default` table property. A new file is started once the estimated size
+/// of the current file exceeds `target_file_size` bytes. Does not perform any operation on an iceberg table.
+pub async fn write_parquet_partitioned_with_target_size(
+    metadata: &TableMetadata,
+    batches: impl Stream<Item = Result<RecordBatch, ArrowError>> + Send,
+    object_store: Arc<dyn ObjectStore>,
+    branch: Option<&str>,
+    target_file_size: usize,
+) -> Result<Vec<DataFile>, ArrowError> {
+    write_parquet_partitioned_with_config(
+        metadata,
+        batches,
+        object_store,
+        branch,
+        &WriterConfig::new(&metadata.properties)?.with_target_file_size(target_file_size),
+    )
+    .await
+}
+
+/// Partitions arrow record batches and writes them to data files in the format of the `write.format.default` table property with the given writer settings
+/// instead of the ones of the table properties. Does not perform any operation on an iceberg table.
+pub async fn write_parquet_partitioned_with_config(
+    metadata: &TableMetadata,
+    batches: impl Stream<Item = Result<RecordBatch, ArrowError>> + Send,
+    object_store: Arc<dyn ObjectStore>,
+    branch: Option<&str>,
+    config: &WriterConfig,
+) -> Result<Vec<DataFile>, ArrowError> {
+    let schema = metadata.current_schema(branch).map_err(Error::from)?;
+    let partition_spec = metadata.default_partition_spec().map_err(Error::from)?;
+
+    write_partitioned(
+        &metadata.location,
+        schema,
+        partition_spec,
+        batches,
+        object_store,
+        write_format(&metadata.properties)?,
+        config,
+    )
+    .await
+}
+
+/// Partitions arrow record batches of deleted rows and writes them to equality delete files. The record batches contain the columns of the equality ids.
+/// The source columns of the partition spec have to be part of the equality ids. Does not perform any operation on an iceberg table.
+pub async fn write_equality_deletes(
+    metadata: &TableMetadata,
+    batches: impl Stream<Item = Result<RecordBatch, ArrowError>> + Send,
+    equality_ids: &[i32],
+    object_store: Arc<dyn ObjectStore>,
+    branch: Option<&str>,
+) -> Result<Vec<DataFile>, ArrowError> {
+    let schema = metadata.current_schema(branch).map_err(Error::from)?;
+    let partition_spec = metadata.default_partition_spec().map_err(Error::from)?;
+
+    if partition_spec
+        .fields()
+        .iter()
+        .any(|field| !equality_ids.contains(field.source_id()))
+    {
+        return Err(Error::NotSupported(
+            "Equality deletes without the partition columns in the equality ids".to_owned(),
+        )
+        .into());
+    }
+
+    let delete_schema = Schema::builder()
+        .with_schema_id(*schema.schema_id())
+        .with_fields(StructType::new(
+            equality_ids
+                .iter()
+                .map(|id| {
+                    schema
+                        .get(*id as usize)
+                        .cloned()
+                        .ok_or(Error::NotFound("Field".to_owned(), id.to_string()))
+                })
+                .collect::<Result<Vec<_>, Error>>()?,
+        ))
+        .build()
+        .map_err(iceberg_rust_spec::error::Error::from)
+        .map_err(Error::from)?;
+
+    let arrow_schema: Arc<ArrowSchema> =
+        Arc::new((delete_schema.fields()).try_into().map_err(Error::from)?);
+
+    let batches = batches.map(move |batch| {
+        let batch = batch?;
+        let columns = arrow_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                batch
+                    .column_by_name(field.name())
+                    .cloned()
+                    .ok_or(ArrowError::SchemaError(format!(
+                        "Column {} doesn't exist",
+                        field.name()
+                    )))
+            })
+            .collect::<Result<Vec<_>, ArrowError>>()?;
+        RecordBatch::try_new(arrow_schema.clone(), columns)
+    });
+
+    let files = write_partitioned(
+        &metadata.location,
+        &delete_schema,
+        partition_spec,
+        batches,
+        object_store,
+        FileFormat::Parquet,
+        &WriterConfig::new(&metadata.properties)?,
+    )
+    .await?;
+
+    files
+        .into_iter()
+        .map(|file| {
+            let partition = file.partition().clone();
+            Ok(into_delete_file(
+                file,
+                partition,
+                Content::EqualityDeletes,
+                Some(equality_ids.to_vec()),
+            )?)
+        })
+        .collect()
+}
+
+async fn write_partitioned(
+    location: &str,
+    schema: &Schema,
+    partition_spec: &PartitionSpec,
+    batches: impl Stream<Item = Result<RecordBatch, ArrowError>> + Send,
+    object_store: Arc<dyn ObjectStore>,
+    format: FileFormat,
+    config: &WriterConfig,
+) -> Result<Vec<DataFile>, ArrowError> {
     let streams = partition_record_batches(batches, partition_spec, schema).await?;
 
     let arrow_schema: Arc<ArrowSchema> =
@@ -60,17 +386,36 @@ pub async fn write_parquet_partitioned(
             let arrow_schema = arrow_schema.clone();
             let object_store = object_store.clone();
             let mut sender = sender.clone();
+            let format = &format;
             async move {
-                let files = write_parquet_files(
-                    location,
-                    schema,
-                    &arrow_schema,
-                    partition_spec,
-                    &partition_values,
-                    batches,
-                    object_store.clone(),
-                )
-                .await?;
+                let files = match format {
+                    FileFormat::Orc | FileFormat::Avro => {
+                        write_data_files(
+                            format,
+                            location,
+                            schema,
+                            partition_spec,
+                            &partition_values,
+                            batches,
+                            object_store.clone(),
+                            config,
+                        )
+                        .await?
+                    }
+                    _ => {
+                        write_parquet_files(
+                            location,
+                            schema,
+                            &arrow_schema,
+                            partition_spec,
+                            &partition_values,
+                            batches,
+                            object_store.clone(),
+                            config,
+                        )
+                        .await?
+                    }
+                };
                 sender.send(files).await.map_err(Error::from)?;
                 Ok(())
             }
@@ -87,102 +432,363 @@ pub async fn write_parquet_partitioned(
         .await)
 }
 
+/// Writes position delete files for the deleted row positions of the given data files. The positions of all data files of a partition
+/// are written to one delete file, sorted by file path and position. Does not perform any operation on an iceberg table.
+pub async fn write_position_deletes(
+    metadata: &TableMetadata,
+    deletes: impl IntoIterator<Item = (DataFile, Vec<i64>)>,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<Vec<DataFile>, ArrowError> {
+    let location = &metadata.location;
+    let bucket = Bucket::from_path(location)?;
+    let partition_spec = metadata.default_partition_spec().map_err(Error::from)?;
+    let schema = position_delete_schema()?;
+    let arrow_schema: Arc<ArrowSchema> =
+        Arc::new((schema.fields()).try_into().map_err(Error::from)?);
+    let config = WriterConfig::new(&metadata.properties)?;
+
+    let mut partitions: HashMap<Struct, Vec<(String, Vec<i64>)>> = HashMap::new();
+    for (data_file, positions) in deletes {
+        partitions
+            .entry(data_file.partition().clone())
+            .or_default()
+            .push((data_file.file_path().clone(), positions));
+    }
+
+    stream::iter(partitions)
+        .then(|(partition, mut files)| {
+            let arrow_schema = arrow_schema.clone();
+            let object_store = object_store.clone();
+            let schema = &schema;
+            let config = &config;
+            let bucket = bucket.to_string();
+            async move {
+                files.sort_by(|x, y| x.0.cmp(&y.0));
+                let (file_paths, positions): (Vec<String>, Vec<i64>) = files
+                    .into_iter()
+                    .flat_map(|(file_path, mut positions)| {
+                        positions.sort_unstable();
+                        positions.dedup();
+                        positions
+                            .into_iter()
+                            .map(move |position| (file_path.clone(), position))
+                    })
+                    .unzip();
+                let batch = RecordBatch::try_new(
+                    arrow_schema.clone(),
+                    vec![
+                        Arc::new(StringArray::from(file_paths)),
+                        Arc::new(Int64Array::from(positions)),
+                    ],
+                )?;
+
+                let partition_location =
+                    generate_partition_location(location, partition_spec, &partition.fields)?;
+
+                let (path, mut writer) = create_arrow_writer(
+                    &partition_location,
+                    &arrow_schema,
+                    object_store.clone(),
+                    config,
+                )
+                .await?;
+                writer.write(&batch).await?;
+                let file_metadata = writer.close().await?;
+                let size = object_store
+                    .head(&path.as_str().into())
+                    .await
+                    .map_err(|err| ArrowError::from_external_error(err.into()))?
+                    .size;
+
+                // The bounds of the file paths are kept in full to match delete files to data files
+                let datafile = parquet_to_datafile(
+                    &(bucket + &path),
+                    size,
+                    &file_metadata,
+                    schema,
+                    &[],
+                    &MetricsConfig::full(),
+                )?;
+
+                Ok(into_delete_file(
+                    datafile,
+                    partition,
+                    Content::PositionDeletes,
+                    None,
+                )?)
+            }
+        })
+        .try_collect::<Vec<_>>()
+        .await
+}
+
+/// Change the content and the partition of a datafile that was written for deleted rows
+fn into_delete_file(
+    datafile: DataFile,
+    partition: Struct,
+    content: Content,
+    equality_ids: Option<Vec<i32>>,
+) -> Result<DataFile, Error> {
+    DataFile::builder()
+        .with_content(content)
+        .with_file_path(datafile.file_path().clone())
+        .with_file_format(FileFormat::Parquet)
+        .with_partition(partition)
+        .with_record_count(*datafile.record_count())
+        .with_file_size_in_bytes(*datafile.file_size_in_bytes())
+        .with_column_sizes(datafile.column_sizes().clone())
+        .with_value_counts(datafile.value_counts().clone())
+        .with_null_value_counts(datafile.null_value_counts().clone())
+        .with_nan_value_counts(None)
+        .with_distinct_counts(datafile.distinct_counts().clone())
+        .with_lower_bounds(datafile.lower_bounds().clone())
+        .with_upper_bounds(datafile.upper_bounds().clone())
+        .with_equality_ids(equality_ids)
+        .build()
+        .map_err(iceberg_rust_spec::error::Error::from)
+        .map_err(Error::from)
+}
+
 type SendableAsyncArrowWriter = AsyncArrowWriter<Box<dyn AsyncWrite + Send + Unpin>>;
-type ArrowSender = Sender<(String, SendableAsyncArrowWriter)>;
-type ArrowReciever = Receiver<(String, SendableAsyncArrowWriter)>;
 
-/// Write arrow record batches to parquet files. Does not perform any operation on an iceberg table.
+/// Write arrow record batches to parquet files. A row group is flushed once its encoded size exceeds the row group size and a new file is started
+/// once the compressed size of the flushed row groups plus the encoded size of the buffered row group exceeds the target file size.
+/// Does not perform any operation on an iceberg table.
 async fn write_parquet_files(
     location: &str,
     schema: &Schema,
     arrow_schema: &ArrowSchema,
     partition_spec: &PartitionSpec,
-    partiton_values: &[Value],
+    partiton_values: &[Option<Value>],
     batches: impl Stream<Item = Result<RecordBatch, ArrowError>> + Send,
     object_store: Arc<dyn ObjectStore>,
+    config: &WriterConfig,
 ) -> Result<Vec<DataFile>, ArrowError> {
     let bucket = Bucket::from_path(location)?;
     let partition_location =
         generate_partition_location(location, partition_spec, partiton_values)?;
-    let current_writer = Arc::new(Mutex::new(
-        create_arrow_writer(&partition_location, arrow_schema, object_store.clone()).await?,
-    ));
 
-    let (mut writer_sender, writer_reciever): (ArrowSender, ArrowReciever) = channel(32);
+    let close = |(path, writer): (String, SendableAsyncArrowWriter)| {
+        let object_store = object_store.clone();
+        let bucket = bucket.to_string();
+        async move {
+            let metadata = writer.close().await?;
+            let size = object_store
+                .head(&path.as_str().into())
+                .await
+                .map_err(|err| ArrowError::from_external_error(err.into()))?
+                .size;
+            Ok::<_, ArrowError>(parquet_to_datafile(
+                &(bucket + &path),
+                size,
+                &metadata,
+                schema,
+                partition_spec.fields(),
+                &config.metrics,
+            )?)
+        }
+    };
 
-    let num_bytes = Arc::new(AtomicUsize::new(0));
+    let mut batches = Box::pin(batches);
+    let mut files = Vec::new();
+    let mut writer = create_arrow_writer(
+        &partition_location,
+        arrow_schema,
+        object_store.clone(),
+        config,
+    )
+    .await?;
+    while let Some(batch) = batches.next().await {
+        if estimated_size(&writer.1) > config.target_file_size {
+            let finished = std::mem::replace(
+                &mut writer,
+                create_arrow_writer(
+                    &partition_location,
+                    arrow_schema,
+                    object_store.clone(),
+                    config,
+                )
+                .await?,
+            );
+            files.push(close(finished).await?);
+        }
+        writer.1.write(&batch?).await?;
+        if writer.1.in_progress_size() > config.row_group_size {
+            writer.1.flush().await?;
+        }
+    }
+    files.push(close(writer).await?);
+    Ok(files)
+}
 
-    batches
-        .try_for_each_concurrent(None, |batch| {
-            let current_writer = current_writer.clone();
-            let mut writer_sender = writer_sender.clone();
-            let num_bytes = num_bytes.clone();
-            let object_store = object_store.clone();
-            let partition_location = &partition_location;
-            async move {
-                let mut current_writer = current_writer.lock().await;
-                let current =
-                    num_bytes.fetch_update(Ordering::Release, Ordering::Acquire, |current| {
-                        if current > MAX_PARQUET_SIZE {
-                            Some(0)
-                        } else {
-                            None
-                        }
-                    });
-                if current.is_ok() {
-                    let finished_writer = std::mem::replace(
-                        &mut *current_writer,
-                        create_arrow_writer(partition_location, arrow_schema, object_store)
-                            .await
-                            .unwrap(),
-                    );
-                    writer_sender
-                        .try_send(finished_writer)
-                        .map_err(|err| ArrowError::ComputeError(err.to_string()))?;
-                }
-                current_writer.1.write(&batch).await?;
-                let batch_size = record_batch_size(&batch);
-                num_bytes.fetch_add(batch_size, Ordering::AcqRel);
-                Ok(())
-            }
+/// Estimated size of the parquet file: the compressed size of the flushed row groups plus the encoded size of the buffered row group
+#[inline]
+fn estimated_size(writer: &SendableAsyncArrowWriter) -> usize {
+    writer.bytes_written() + writer.in_progress_size()
+}
+
+/// Write arrow record batches to orc or avro files. Does not perform any operation on an iceberg table.
+async fn write_data_files(
+    format: &FileFormat,
+    location: &str,
+    schema: &Schema,
+    partition_spec: &PartitionSpec,
+    partiton_values: &[Option<Value>],
+    batches: impl Stream<Item = Result<RecordBatch, ArrowError>> + Send,
+    object_store: Arc<dyn ObjectStore>,
+    config: &WriterConfig,
+) -> Result<Vec<DataFile>, ArrowError> {
+    let bucket = Bucket::from_path(location)?;
+    let partition_location =
+        generate_partition_location(location, partition_spec, partiton_values)?;
+    // The partition of a data file is keyed by the names of the source columns
+    let partition = partition_spec
+        .fields()
+        .iter()
+        .zip(partiton_values)
+        .map(|(field, value)| {
+            let source = schema
+                .get(*field.source_id() as usize)
+                .ok_or(Error::NotFound(
+                    "Field".to_owned(),
+                    field.source_id().to_string(),
+                ))?;
+            Ok((source.name.clone(), value.clone()))
         })
-        .await?;
+        .collect::<Result<Struct, Error>>()?;
 
-    let last = Arc::try_unwrap(current_writer).unwrap().into_inner();
+    let close = |writer: DataFileWriter| {
+        let location = bucket.to_string() + writer.path();
+        let partition = partition.clone();
+        async move { writer.close(&location, partition).await }
+    };
 
-    writer_sender.try_send(last).unwrap();
+    let mut batches = Box::pin(batches);
+    let mut files = Vec::new();
+    let mut writer = DataFileWriter::try_new(
+        format,
+        &partition_location,
+        schema,
+        &config.metrics,
+        object_store.clone(),
+    )
+    .await?;
+    while let Some(batch) = batches.next().await {
+        if writer.bytes_written() > config.target_file_size {
+            let finished = std::mem::replace(
+                &mut writer,
+                DataFileWriter::try_new(
+                    format,
+                    &partition_location,
+                    schema,
+                    &config.metrics,
+                    object_store.clone(),
+                )
+                .await?,
+            );
+            files.push(close(finished).await?);
+        }
+        writer.write(&batch?).await?;
+    }
+    files.push(close(writer).await?);
+    Ok(files)
+}
 
-    writer_sender.close_channel();
+/// Writer for the data file formats other than parquet
+enum DataFileWriter {
+    Orc(OrcWriter),
+    Avro(AvroWriter),
+}
 
-    writer_reciever
-        .then(|writer| {
-            let object_store = object_store.clone();
-            let bucket = bucket.to_string();
-            async move {
-                let metadata = writer.1.close().await?;
-                let size = object_store
-                    .head(&writer.0.as_str().into())
-                    .await
-                    .map_err(|err| ArrowError::from_external_error(err.into()))?
-                    .size;
-                Ok(parquet_to_datafile(
-                    &(bucket + &writer.0),
-                    size,
-                    &metadata,
+impl DataFileWriter {
+    async fn try_new(
+        format: &FileFormat,
+        partition_location: &str,
+        schema: &Schema,
+        metrics: &MetricsConfig,
+        object_store: Arc<dyn ObjectStore>,
+    ) -> Result<Self, ArrowError> {
+        match format {
+            FileFormat::Orc => Ok(DataFileWriter::Orc(
+                OrcWriter::try_new(
+                    &new_file_path(partition_location, "orc")?,
                     schema,
-                    partition_spec.fields(),
-                )?)
-            }
-        })
-        .try_collect::<Vec<_>>()
-        .await
+                    metrics,
+                    object_store,
+                )
+                .await?,
+            )),
+            FileFormat::Avro => Ok(DataFileWriter::Avro(
+                AvroWriter::try_new(
+                    &new_file_path(partition_location, "avro")?,
+                    schema,
+                    metrics,
+                    object_store,
+                )
+                .await?,
+            )),
+            _ => Err(Error::NotSupported(format!("Writing {:?} data files", format)).into()),
+        }
+    }
+
+    fn path(&self) -> &str {
+        match self {
+            DataFileWriter::Orc(writer) => writer.path(),
+            DataFileWriter::Avro(writer) => writer.path(),
+        }
+    }
+
+    fn bytes_written(&self) -> usize {
+        match self {
+            DataFileWriter::Orc(writer) => writer.bytes_written(),
+            DataFileWriter::Avro(writer) => writer.bytes_written(),
+        }
+    }
+
+    async fn write(&mut self, batch: &RecordBatch) -> Result<(), Error> {
+        match self {
+            DataFileWriter::Orc(writer) => writer.write(batch).await,
+            DataFileWriter::Avro(writer) => writer.write(batch).await,
+        }
+    }
+
+    async fn close(self, location: &str, partition: Struct) -> Result<DataFile, Error> {
+        match self {
+            DataFileWriter::Orc(writer) => writer.close(location, partition).await,
+            DataFileWriter::Avro(writer) => writer.close(location, partition).await,
+        }
+    }
+}
+
+/// Get the file format of new data files from the table properties. Defaults to parquet.
+fn write_format(properties: &HashMap<String, String>) -> Result<FileFormat, Error> {
+    match properties
+        .get(WRITE_FORMAT_DEFAULT)
+        .map(|format| format.to_lowercase())
+        .as_deref()
+    {
+        None | Some("parquet") => Ok(FileFormat::Parquet),
+        Some("orc") => Ok(FileFormat::Orc),
+        Some("avro") => Ok(FileFormat::Avro),
+        Some(format) => Err(Error::NotSupported(format!(
+            "Writing {} data files",
+            format
+        ))),
+    }
+}
+
+/// Generate a unique path for a new file in the partition location
+fn new_file_path(partition_location: &str, extension: &str) -> Result<String, ArrowError> {
+    let mut rand = [0u8; 6];
+    getrandom::getrandom(&mut rand).map_err(|err| ArrowError::ExternalError(Box::new(err)))?;
+    Ok(partition_location.to_string() + &Uuid::now_v1(&rand).to_string() + "." + extension)
 }
 
 #[inline]
 fn generate_partition_location(
     location: &str,
     partition_spec: &PartitionSpec,
-    partiton_values: &[Value],
+    partiton_values: &[Option<Value>],
 ) -> Result<String, ArrowError> {
     let partition_location = strip_prefix(location)
         + "/data/"
@@ -192,7 +798,11 @@ fn generate_partition_location(
             .zip(partiton_values.iter())
             .map(|(spec, value)| {
                 let name = spec.name().clone();
-                Ok(name + "=" + &value.to_string() + "/")
+                let value = value
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| "null".to_owned());
+                Ok(name + "=" + &value + "/")
             })
             .collect::<Result<String, ArrowError>>()?;
     Ok(partition_location)
@@ -202,14 +812,9 @@ async fn create_arrow_writer(
     partition_location: &str,
     schema: &arrow::datatypes::Schema,
     object_store: Arc<dyn ObjectStore>,
-) -> Result<(String, AsyncArrowWriter<Box<dyn AsyncWrite + Send + Unpin>>), ArrowError> {
-    let mut rand = [0u8; 6];
-    getrandom::getrandom(&mut rand)
-        .map_err(|err| ArrowError::ExternalError(Box::new(err)))
-        .unwrap();
-
-    let parquet_path =
-        partition_location.to_string() + &Uuid::now_v1(&rand).to_string() + ".parquet";
+    config: &WriterConfig,
+) -> Result<(String, SendableAsyncArrowWriter), ArrowError> {
+    let parquet_path = new_file_path(partition_location, "parquet")?;
 
     let (_, writer) = object_store
         .put_multipart(&parquet_path.clone().into())
@@ -221,32 +826,69 @@ async fn create_arrow_writer(
         AsyncArrowWriter::try_new(
             writer,
             Arc::new(schema.clone()),
-            Some(
-                WriterProperties::builder()
-                    .set_compression(Compression::ZSTD(ZstdLevel::try_new(1)?))
-                    .build(),
-            ),
+            Some(config.writer_properties()),
         )?,
     ))
 }
 
-#[inline]
-fn record_batch_size(batch: &RecordBatch) -> usize {
-    batch
-        .schema()
-        .fields()
-        .iter()
-        .fold(0, |acc, x| acc + x.size())
-        * batch.num_rows()
-}
-
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+
     use crate::spec::{
         partition::{PartitionField, PartitionSpec, Transform},
         values::Value,
     };
 
+    use super::{
+        WriterConfig, WRITE_PARQUET_BLOOM_FILTER_ENABLED_COLUMN, WRITE_PARQUET_COMPRESSION_CODEC,
+        WRITE_PARQUET_COMPRESSION_LEVEL, WRITE_TARGET_FILE_SIZE_BYTES,
+    };
+
+    #[test]
+    fn test_writer_config() {
+        let config = WriterConfig::new(&HashMap::new()).unwrap();
+        assert_eq!(config.target_file_size, 512 * 1024 * 1024);
+        assert_eq!(
+            config.compression,
+            Compression::ZSTD(ZstdLevel::try_new(1).unwrap())
+        );
+        assert!(config.bloom_filter_columns.is_empty());
+
+        let config = WriterConfig::new(&HashMap::from_iter(vec![
+            (WRITE_TARGET_FILE_SIZE_BYTES.to_owned(), "1024".to_owned()),
+            (
+                WRITE_PARQUET_COMPRESSION_CODEC.to_owned(),
+                "GZIP".to_owned(),
+            ),
+            (WRITE_PARQUET_COMPRESSION_LEVEL.to_owned(), "6".to_owned()),
+            (
+                WRITE_PARQUET_BLOOM_FILTER_ENABLED_COLUMN.to_owned() + "id",
+                "true".to_owned(),
+            ),
+            (
+                WRITE_PARQUET_BLOOM_FILTER_ENABLED_COLUMN.to_owned() + "name",
+                "false".to_owned(),
+            ),
+        ]))
+        .unwrap()
+        .with_target_file_size(2048);
+        assert_eq!(config.target_file_size, 2048);
+        assert_eq!(
+            config.compression,
+            Compression::GZIP(GzipLevel::try_new(6).unwrap())
+        );
+        assert_eq!(config.bloom_filter_columns, vec!["id".to_owned()]);
+
+        assert!(WriterConfig::new(&HashMap::from_iter(vec![(
+            WRITE_PARQUET_COMPRESSION_CODEC.to_owned(),
+            "lzo".to_owned()
+        )]))
+        .is_err());
+    }
+
     #[test]
     fn test_generate_partiton_location_success() {
         let location = "s3://bucket/table";
@@ -255,7 +897,7 @@ mod tests {
             .with_partition_field(PartitionField::new(1, 1001, "month", Transform::Month))
             .build()
             .unwrap();
-        let partiton_values = vec![Value::Int(10)];
+        let partiton_values = vec![Some(Value::Int(10))];
 
         let result =
             super::generate_partition_location(location, &partition_spec, &partiton_values);
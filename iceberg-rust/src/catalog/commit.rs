@@ -1,7 +1,10 @@
 /*!
 Struct to perform a [CommitTable] or [CommitView] operation
 */
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use iceberg_rust_spec::{
     spec::{
@@ -9,8 +12,8 @@ use iceberg_rust_spec::{
         schema::Schema,
         snapshot::{Snapshot, SnapshotReference},
         sort::SortOrder,
-        table_metadata::TableMetadata,
-        view_metadata::{GeneralViewMetadata, Version},
+        table_metadata::{MetadataLog, SnapshotLog, TableMetadata},
+        view_metadata::{GeneralViewMetadata, Version, VersionLogStruct},
     },
     view_metadata::Materialization,
 };
@@ -44,7 +47,7 @@ pub struct CommitView<T: Materialization> {
 }
 
 /// Update the metadata of a table in the catalog
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(
     tag = "action",
     rename_all = "kebab-case",
@@ -133,7 +136,7 @@ pub enum TableUpdate {
 }
 
 /// Requirements on the table metadata to perform the updates
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[serde(
     tag = "type",
     rename_all = "kebab-case",
@@ -289,11 +292,25 @@ pub fn check_view_requirements<T: Materialization + Eq + 'static>(
         ViewRequirement::AssertViewUuid { uuid } => metadata.view_uuid == *uuid,
     })
 }
-/// Apply updates to metadata
+/// Table property for the number of previous metadata files that are kept in the metadata log
+pub static METADATA_PREVIOUS_VERSIONS_MAX: &str = "write.metadata.previous-versions-max";
+
+static METADATA_PREVIOUS_VERSIONS_MAX_DEFAULT: usize = 100;
+
+/// Apply updates to metadata. The previous metadata file that is replaced by the updated metadata is added to the metadata log.
 pub fn apply_table_updates(
     metadata: &mut TableMetadata,
     updates: Vec<TableUpdate>,
+    previous_metadata_location: Option<&str>,
 ) -> Result<(), Error> {
+    let now_ms = now_ms();
+    if let Some(previous_metadata_location) = previous_metadata_location {
+        metadata.metadata_log.push(MetadataLog {
+            metadata_file: previous_metadata_location.to_owned(),
+            timestamp_ms: metadata.last_updated_ms,
+        });
+    }
+    metadata.last_updated_ms = now_ms;
     for update in updates {
         match update {
             TableUpdate::UpgradeFormatVersion { format_version: _ } => {
@@ -327,6 +344,9 @@ pub fn apply_table_updates(
                 metadata.default_sort_order_id = sort_order_id;
             }
             TableUpdate::AddSnapshot { snapshot } => {
+                if *snapshot.sequence_number() > metadata.last_sequence_number {
+                    metadata.last_sequence_number = *snapshot.sequence_number();
+                }
                 metadata.snapshots.insert(*snapshot.snapshot_id(), snapshot);
             }
             TableUpdate::SetSnapshotRef {
@@ -334,6 +354,13 @@ pub fn apply_table_updates(
                 snapshot_reference,
             } => {
                 if ref_name == "main" {
+                    // The snapshot log records when the current snapshot of the main branch changed
+                    if metadata.current_snapshot_id != Some(snapshot_reference.snapshot_id) {
+                        metadata.snapshot_log.push(SnapshotLog {
+                            snapshot_id: snapshot_reference.snapshot_id,
+                            timestamp_ms: now_ms,
+                        });
+                    }
                     metadata.current_snapshot_id = Some(snapshot_reference.snapshot_id);
                 }
                 metadata.refs.insert(ref_name, snapshot_reference);
@@ -342,6 +369,16 @@ pub fn apply_table_updates(
                 for id in snapshot_ids {
                     metadata.snapshots.remove(&id);
                 }
+                // All entries before a snapshot that has expired are removed from the snapshot log
+                let mut snapshot_log = Vec::new();
+                for entry in metadata.snapshot_log.drain(..) {
+                    if metadata.snapshots.contains_key(&entry.snapshot_id) {
+                        snapshot_log.push(entry);
+                    } else {
+                        snapshot_log.clear();
+                    }
+                }
+                metadata.snapshot_log = snapshot_log;
             }
             TableUpdate::RemoveSnapshotRef { ref_name } => {
                 metadata.refs.remove(&ref_name);
@@ -359,6 +396,16 @@ pub fn apply_table_updates(
             }
         };
     }
+    // The oldest metadata files are removed from the metadata log
+    let previous_versions_max = metadata
+        .properties
+        .get(METADATA_PREVIOUS_VERSIONS_MAX)
+        .and_then(|max| max.parse().ok())
+        .unwrap_or(METADATA_PREVIOUS_VERSIONS_MAX_DEFAULT);
+    if metadata.metadata_log.len() > previous_versions_max {
+        let removed = metadata.metadata_log.len() - previous_versions_max;
+        metadata.metadata_log.drain(..removed);
+    }
     Ok(())
 }
 
@@ -367,6 +414,7 @@ pub fn apply_view_updates<T: Materialization + 'static>(
     metadata: &mut GeneralViewMetadata<T>,
     updates: Vec<ViewUpdate<T>>,
 ) -> Result<(), Error> {
+    let now_ms = now_ms();
     for update in updates {
         match update {
             ViewUpdate::UpgradeFormatVersion { format_version: _ } => {
@@ -398,9 +446,23 @@ pub fn apply_view_updates<T: Materialization + 'static>(
                     .insert(view_version.version_id, view_version);
             }
             ViewUpdate::SetCurrentViewVersion { view_version_id } => {
+                // The version log records when the current version changed
+                if metadata.current_version_id != view_version_id {
+                    metadata.version_log.push(VersionLogStruct {
+                        timestamp_ms: now_ms,
+                        version_id: view_version_id,
+                    });
+                }
                 metadata.current_version_id = view_version_id;
             }
         };
     }
     Ok(())
 }
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
@@ -15,7 +15,7 @@ use iceberg_rust_spec::{
         schema::{Schema, DEFAULT_SCHEMA_ID},
         sort::{SortOrder, DEFAULT_SORT_ORDER_ID},
         table_metadata::TableMetadata,
-        view_metadata::{Version, ViewMetadata, DEFAULT_VERSION_ID},
+        view_metadata::{Version, VersionLogStruct, ViewMetadata, DEFAULT_VERSION_ID},
     },
     view_metadata::{FullIdentifier, Lineage, Materialization, ViewRepresentation},
 };
@@ -111,7 +111,7 @@ impl TryInto<TableMetadata> for CreateTable {
             last_updated_ms: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
-                .as_micros() as i64,
+                .as_millis() as i64,
             last_column_id,
             schemas: HashMap::from_iter(vec![(DEFAULT_SCHEMA_ID, self.schema)]),
             current_schema_id: DEFAULT_SCHEMA_ID,
@@ -126,6 +126,8 @@ impl TryInto<TableMetadata> for CreateTable {
             snapshots: HashMap::new(),
             snapshot_log: Vec::new(),
             metadata_log: Vec::new(),
+            statistics: Vec::new(),
+            partition_statistics: Vec::new(),
             sort_orders: HashMap::from_iter(vec![(
                 DEFAULT_SORT_ORDER_ID,
                 self.write_order.unwrap_or_default(),
@@ -187,8 +189,11 @@ impl TryInto<ViewMetadata> for CreateView<Option<()>> {
                 .location
                 .ok_or(Error::NotFound("Table".to_owned(), "location".to_owned()))?,
             current_version_id: DEFAULT_VERSION_ID,
+            version_log: vec![VersionLogStruct {
+                timestamp_ms: self.view_version.timestamp_ms,
+                version_id: DEFAULT_VERSION_ID,
+            }],
             versions: HashMap::from_iter(vec![(DEFAULT_VERSION_ID, self.view_version)]),
-            version_log: Vec::new(),
             schemas: HashMap::from_iter(vec![(DEFAULT_SCHEMA_ID, self.schema)]),
             properties: self.properties,
         })
@@ -205,8 +210,11 @@ impl TryInto<MaterializedViewMetadata> for CreateView<FullIdentifier> {
                 .location
                 .ok_or(Error::NotFound("Table".to_owned(), "location".to_owned()))?,
             current_version_id: DEFAULT_VERSION_ID,
+            version_log: vec![VersionLogStruct {
+                timestamp_ms: self.view_version.timestamp_ms,
+                version_id: DEFAULT_VERSION_ID,
+            }],
             versions: HashMap::from_iter(vec![(DEFAULT_VERSION_ID, self.view_version)]),
-            version_log: Vec::new(),
             schemas: HashMap::from_iter(vec![(DEFAULT_SCHEMA_ID, self.schema)]),
             properties: self.properties,
         })
@@ -26,6 +26,9 @@ pub enum Error {
     /// Not supported
     #[error("Feature {0} is not supported.")]
     NotSupported(String),
+    /// Commit conflict
+    #[error("Commit conflicts with a concurrent change: {0}")]
+    CommitConflict(String),
     /// Iceberg spec error
     #[error(transparent)]
     Iceberg(#[from] iceberg_rust_spec::error::Error),
@@ -38,6 +41,9 @@ pub enum Error {
     /// Avro error
     #[error(transparent)]
     Avro(#[from] apache_avro::Error),
+    /// Orc error
+    #[error(transparent)]
+    Orc(#[from] orc_rust::error::OrcError),
     /// Thrift error
     #[error(transparent)]
     Thrift(#[from] thrift::Error),
@@ -0,0 +1,971 @@
+/*!
+ * Helpers for avro data files
+*/
+
+use std::{collections::HashMap, io::Cursor, sync::Arc};
+
+use apache_avro::{
+    from_avro_datum,
+    schema::{RecordField, Schema as AvroSchema},
+    to_avro_datum,
+    types::Value as AvroValue,
+    Decimal, Reader,
+};
+use arrow::{
+    array::{
+        ArrayRef, AsArray, BinaryArray, BooleanArray, Date32Array, Decimal128Array,
+        FixedSizeBinaryArray, Float32Array, Float64Array, Int32Array, Int64Array, StringArray,
+        Time64MicrosecondArray, TimestampMicrosecondArray,
+    },
+    datatypes::{
+        Date32Type, Decimal128Type, Float32Type, Float64Type, Int32Type, Int64Type,
+        Schema as ArrowSchema, Time64MicrosecondType, TimestampMicrosecondType,
+    },
+    error::ArrowError,
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use iceberg_rust_spec::{
+    spec::{
+        manifest::{Content, DataFile, FileFormat},
+        schema::Schema,
+        types::{PrimitiveType, StructField, StructType, Type},
+        values::Struct,
+    },
+    util,
+};
+use object_store::{MultipartId, ObjectStore};
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::error::Error;
+
+use super::{
+    abort_upload,
+    metrics::{ColumnMetrics, MetricsConfig},
+};
+
+/// Name of the avro field attribute that stores the iceberg field id
+static FIELD_ID: &str = "field-id";
+
+/// Number of rows of the record batches that are read from avro files
+static BATCH_SIZE: usize = 8192;
+
+/// Magic bytes at the start of an avro object container file
+static MAGIC: [u8; 4] = [b'O', b'b', b'j', 1];
+
+/// Convert an iceberg schema to an avro record schema. The field ids are stored in the `field-id` attribute of the avro fields,
+/// optional fields are unions with null. Only primitive fields are supported.
+pub fn avro_schema(schema: &Schema) -> Result<AvroSchema, Error> {
+    Ok(AvroSchema::parse(&avro_schema_json(schema)?)?)
+}
+
+/// Json of the avro record schema of an iceberg schema. Unlike the parsed avro schema it keeps the attributes of the logical types,
+/// like `adjust-to-utc` of timestamps.
+fn avro_schema_json(schema: &Schema) -> Result<JsonValue, Error> {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let Type::Primitive(primitive) = &field.field_type else {
+                return Err(Error::NotSupported(format!(
+                    "Avro data files with the nested field {}",
+                    field.name
+                )));
+            };
+            let data_type = match primitive {
+                PrimitiveType::Boolean => json!("boolean"),
+                PrimitiveType::Int => json!("int"),
+                PrimitiveType::Long => json!("long"),
+                PrimitiveType::Float => json!("float"),
+                PrimitiveType::Double => json!("double"),
+                PrimitiveType::Decimal { precision, scale } => json!({
+                    "type": "fixed",
+                    "name": format!("decimal_{}", field.id),
+                    "size": decimal_size(*precision),
+                    "logicalType": "decimal",
+                    "precision": precision,
+                    "scale": scale,
+                }),
+                PrimitiveType::Date => json!({"type": "int", "logicalType": "date"}),
+                PrimitiveType::Time => json!({"type": "long", "logicalType": "time-micros"}),
+                PrimitiveType::Timestamp => json!({
+                    "type": "long",
+                    "logicalType": "timestamp-micros",
+                    "adjust-to-utc": false,
+                }),
+                PrimitiveType::Timestamptz => json!({
+                    "type": "long",
+                    "logicalType": "timestamp-micros",
+                    "adjust-to-utc": true,
+                }),
+                PrimitiveType::String => json!("string"),
+                PrimitiveType::Uuid => json!({
+                    "type": "fixed",
+                    "name": format!("uuid_fixed_{}", field.id),
+                    "size": 16,
+                    "logicalType": "uuid",
+                }),
+                PrimitiveType::Fixed(size) => json!({
+                    "type": "fixed",
+                    "name": format!("fixed_{}", field.id),
+                    "size": size,
+                }),
+                PrimitiveType::Binary => json!("bytes"),
+            };
+            Ok(if field.required {
+                json!({"name": field.name, "type": data_type, FIELD_ID: field.id})
+            } else {
+                json!({
+                    "name": field.name,
+                    "type": ["null", data_type],
+                    "default": null,
+                    FIELD_ID: field.id,
+                })
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(json!({
+        "type": "record",
+        "name": format!("r{}", schema.schema_id()),
+        "fields": fields,
+    }))
+}
+
+/// Read an avro data file into arrow record batches. Without a schema all fields of the file are read, their iceberg types are
+/// derived from the avro schema. With a schema only the fields with the given field ids are read, all fields of the schema if no ids are given.
+/// Avro fields are matched to the iceberg fields by their `field-id` attribute and by name if the attribute is missing.
+pub async fn read_avro(
+    file_path: &str,
+    object_store: Arc<dyn ObjectStore>,
+    schema: Option<&Schema>,
+    field_ids: Option<&[i32]>,
+) -> Result<impl Iterator<Item = Result<RecordBatch, ArrowError>> + Send, Error> {
+    let bytes = object_store
+        .get(&util::strip_prefix(file_path).into())
+        .await?
+        .bytes()
+        .await?;
+    let json_schema = json_schema(&bytes)?;
+    let mut reader = Reader::new(Cursor::new(bytes))?;
+    let AvroSchema::Record(record) = reader.writer_schema().clone() else {
+        return Err(Error::InvalidFormat("Avro data file schema".to_owned()));
+    };
+
+    let fields = match schema {
+        Some(schema) => match field_ids {
+            Some(ids) => ids
+                .iter()
+                .map(|id| {
+                    schema
+                        .get(*id as usize)
+                        .cloned()
+                        .ok_or(Error::NotFound("Field".to_owned(), id.to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => schema.fields().iter().cloned().collect(),
+        },
+        None if field_ids.is_some() => {
+            return Err(Error::InvalidFormat(
+                "Avro projection by field ids without a schema".to_owned(),
+            ))
+        }
+        None => record
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(position, field)| {
+                iceberg_field(field, json_type(&json_schema, &field.name), position)
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+
+    // Position of the avro field of every iceberg field
+    let positions = fields
+        .iter()
+        .map(|field| {
+            record
+                .fields
+                .iter()
+                .position(|avro_field| field_id(avro_field) == Some(field.id))
+                .or_else(|| {
+                    record.fields.iter().position(|avro_field| {
+                        field_id(avro_field).is_none() && avro_field.name == field.name
+                    })
+                })
+        })
+        .collect::<Vec<_>>();
+    let arrow_schema: Arc<ArrowSchema> = Arc::new((&StructType::new(fields.clone())).try_into()?);
+
+    // The records are decoded lazily, one record batch at a time
+    Ok(std::iter::from_fn(move || {
+        let rows = reader
+            .by_ref()
+            .take(BATCH_SIZE)
+            .map(|value| match value {
+                Ok(AvroValue::Record(values)) => Ok(values
+                    .into_iter()
+                    .map(|(_, value)| value)
+                    .collect::<Vec<_>>()),
+                Ok(value) => Err(ArrowError::ParseError(format!(
+                    "Avro value {:?} is not a record",
+                    value
+                ))),
+                Err(err) => Err(ArrowError::ExternalError(Box::new(err))),
+            })
+            .collect::<Result<Vec<_>, _>>();
+        match rows {
+            Ok(rows) if rows.is_empty() => None,
+            Ok(rows) => Some(record_batch(&rows, &fields, &positions, &arrow_schema)),
+            Err(err) => Some(Err(err)),
+        }
+    }))
+}
+
+/// Convert decoded avro records to a record batch. The values of the iceberg fields are taken from the given positions of the records.
+fn record_batch(
+    rows: &[Vec<AvroValue>],
+    fields: &[StructField],
+    positions: &[Option<usize>],
+    arrow_schema: &Arc<ArrowSchema>,
+) -> Result<RecordBatch, ArrowError> {
+    let columns = fields
+        .iter()
+        .zip(positions.iter())
+        .map(|(field, position)| {
+            let values = rows
+                .iter()
+                .map(|row| match position {
+                    Some(position) => unwrap_union(&row[*position]),
+                    None => &AvroValue::Null,
+                })
+                .collect::<Vec<_>>();
+            arrow_array(&values, field)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    RecordBatch::try_new_with_options(
+        arrow_schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(rows.len())),
+    )
+}
+
+/// Writes arrow record batches to an avro object container file. Every record batch is encoded as a block and uploaded to the object store.
+/// The container is encoded by the writer because the avro writer serializes the parsed schema, which drops the attributes of the logical types.
+/// Collects the column metrics of the data file according to the metrics modes while writing. The upload is aborted if the file can't be written.
+pub struct AvroWriter {
+    path: String,
+    schema: Schema,
+    avro_schema: AvroSchema,
+    marker: [u8; 16],
+    object_store: Arc<dyn ObjectStore>,
+    multipart_id: MultipartId,
+    upload: Box<dyn AsyncWrite + Send + Unpin>,
+    bytes_written: usize,
+    metrics: ColumnMetrics,
+}
+
+impl AvroWriter {
+    /// Create a writer for an avro file at the path
+    pub async fn try_new(
+        path: &str,
+        schema: &Schema,
+        metrics: &MetricsConfig,
+        object_store: Arc<dyn ObjectStore>,
+    ) -> Result<Self, Error> {
+        let json_schema = avro_schema_json(schema)?;
+        let mut marker = [0u8; 16];
+        getrandom::getrandom(&mut marker)
+            .map_err(|err| ArrowError::ExternalError(Box::new(err)))?;
+        let header = header(&json_schema, &marker)?;
+
+        let avro_schema = AvroSchema::parse(&json_schema)?;
+
+        let (multipart_id, mut upload) = object_store.put_multipart(&path.into()).await?;
+        if let Err(err) = upload.write_all(&header).await {
+            return Err(abort_upload(object_store.as_ref(), path, &multipart_id, err.into()).await);
+        }
+        Ok(AvroWriter {
+            path: path.to_owned(),
+            schema: schema.clone(),
+            avro_schema,
+            marker,
+            object_store,
+            multipart_id,
+            upload,
+            bytes_written: header.len(),
+            metrics: ColumnMetrics::new(schema, metrics),
+        })
+    }
+
+    /// Path of the avro file
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Number of bytes that were uploaded so far
+    pub fn bytes_written(&self) -> usize {
+        self.bytes_written
+    }
+
+    /// Write a record batch to the file
+    pub async fn write(&mut self, batch: &RecordBatch) -> Result<(), Error> {
+        match self.write_block(batch).await {
+            Ok(()) => Ok(()),
+            Err(err) => Err(abort_upload(
+                self.object_store.as_ref(),
+                &self.path,
+                &self.multipart_id,
+                err,
+            )
+            .await),
+        }
+    }
+
+    /// Encode the record batch as a block and upload it
+    async fn write_block(&mut self, batch: &RecordBatch) -> Result<(), Error> {
+        if batch.num_rows() == 0 {
+            return Ok(());
+        }
+        let columns = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let column = batch.column_by_name(&field.name).ok_or_else(|| {
+                    Error::Schema(field.name.clone(), format!("{:?}", batch.schema()))
+                })?;
+                avro_values(column, field)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut records = Vec::new();
+        for row in 0..batch.num_rows() {
+            records.extend(to_avro_datum(
+                &self.avro_schema,
+                AvroValue::Record(
+                    self.schema
+                        .fields()
+                        .iter()
+                        .zip(columns.iter())
+                        .map(|(field, column)| (field.name.clone(), column[row].clone()))
+                        .collect(),
+                ),
+            )?);
+        }
+        // A block consists of the number of records, their size, the encoded records and the sync marker
+        let mut block = to_avro_datum(&AvroSchema::Long, batch.num_rows() as i64)?;
+        block.extend(to_avro_datum(&AvroSchema::Long, records.len() as i64)?);
+        block.extend(records);
+        block.extend(self.marker);
+        self.upload.write_all(&block).await?;
+        self.bytes_written += block.len();
+        self.metrics.update(batch, &self.schema);
+        Ok(())
+    }
+
+    /// Finish the upload of the file to the object store and create the data file for the given location and partition
+    pub async fn close(mut self, location: &str, partition: Struct) -> Result<DataFile, Error> {
+        if let Err(err) = self.upload.shutdown().await {
+            return Err(abort_upload(
+                self.object_store.as_ref(),
+                &self.path,
+                &self.multipart_id,
+                err.into(),
+            )
+            .await);
+        }
+
+        DataFile::builder()
+            .with_content(Content::Data)
+            .with_file_path(location.to_owned())
+            .with_file_format(FileFormat::Avro)
+            .with_partition(partition)
+            .with_record_count(self.metrics.record_count)
+            .with_file_size_in_bytes(self.bytes_written as i64)
+            .with_column_sizes(None)
+            .with_value_counts(Some(self.metrics.value_counts()))
+            .with_null_value_counts(Some(self.metrics.null_value_counts()))
+            .with_nan_value_counts(None)
+            .with_distinct_counts(None)
+            .with_lower_bounds(Some(self.metrics.lower_bounds()))
+            .with_upper_bounds(Some(self.metrics.upper_bounds()))
+            .build()
+            .map_err(iceberg_rust_spec::error::Error::from)
+            .map_err(Error::from)
+    }
+}
+
+/// Schema of the metadata in the header of avro object container files
+fn metadata_schema() -> AvroSchema {
+    AvroSchema::Map(Box::new(AvroSchema::Bytes))
+}
+
+/// Encode the header of an avro object container file with the json schema and uncompressed blocks
+fn header(json_schema: &JsonValue, marker: &[u8; 16]) -> Result<Vec<u8>, Error> {
+    let metadata = AvroValue::Map(HashMap::from_iter(vec![
+        (
+            "avro.schema".to_owned(),
+            AvroValue::Bytes(serde_json::to_vec(json_schema)?),
+        ),
+        ("avro.codec".to_owned(), AvroValue::Bytes(b"null".to_vec())),
+    ]));
+    let mut header = MAGIC.to_vec();
+    header.extend(to_avro_datum(&metadata_schema(), metadata)?);
+    header.extend(marker);
+    Ok(header)
+}
+
+/// Read the json schema from the header of an avro object container file. Unlike the parsed avro schema it contains the attributes of the logical types.
+fn json_schema(bytes: &[u8]) -> Result<JsonValue, Error> {
+    let mut metadata = bytes
+        .strip_prefix(MAGIC.as_slice())
+        .ok_or(Error::InvalidFormat("Avro file header".to_owned()))?;
+    let AvroValue::Map(metadata) = from_avro_datum(&metadata_schema(), &mut metadata, None)? else {
+        return Err(Error::InvalidFormat("Avro file metadata".to_owned()));
+    };
+    match metadata.get("avro.schema") {
+        Some(AvroValue::Bytes(schema)) => Ok(serde_json::from_slice(schema)?),
+        _ => Err(Error::NotFound(
+            "Avro file metadata".to_owned(),
+            "avro.schema".to_owned(),
+        )),
+    }
+}
+
+/// Json type of the field of an avro record schema, unions with null are unwrapped
+fn json_type<'a>(json_schema: &'a JsonValue, name: &str) -> Option<&'a JsonValue> {
+    let field = json_schema
+        .get("fields")?
+        .as_array()?
+        .iter()
+        .find(|field| field.get("name").and_then(JsonValue::as_str) == Some(name))?;
+    match field.get("type")? {
+        JsonValue::Array(variants) => variants
+            .iter()
+            .find(|variant| variant.as_str() != Some("null")),
+        data_type => Some(data_type),
+    }
+}
+
+/// Convert an arrow column to avro values of the iceberg field. Values of optional fields are wrapped in the union with null.
+fn avro_values(array: &ArrayRef, field: &StructField) -> Result<Vec<AvroValue>, Error> {
+    let Type::Primitive(primitive) = &field.field_type else {
+        return Err(Error::NotSupported(format!(
+            "Avro data files with the nested field {}",
+            field.name
+        )));
+    };
+    let values: Vec<Option<AvroValue>> = match primitive {
+        PrimitiveType::Boolean => array
+            .as_boolean()
+            .iter()
+            .map(|x| x.map(AvroValue::Boolean))
+            .collect(),
+        PrimitiveType::Int => array
+            .as_primitive::<Int32Type>()
+            .iter()
+            .map(|x| x.map(AvroValue::Int))
+            .collect(),
+        PrimitiveType::Long => array
+            .as_primitive::<Int64Type>()
+            .iter()
+            .map(|x| x.map(AvroValue::Long))
+            .collect(),
+        PrimitiveType::Float => array
+            .as_primitive::<Float32Type>()
+            .iter()
+            .map(|x| x.map(AvroValue::Float))
+            .collect(),
+        PrimitiveType::Double => array
+            .as_primitive::<Float64Type>()
+            .iter()
+            .map(|x| x.map(AvroValue::Double))
+            .collect(),
+        PrimitiveType::Decimal { precision, .. } => {
+            let size = decimal_size(*precision);
+            array
+                .as_primitive::<Decimal128Type>()
+                .iter()
+                .map(|x| {
+                    x.map(|x| AvroValue::Decimal(Decimal::from(&x.to_be_bytes()[16 - size..])))
+                })
+                .collect()
+        }
+        PrimitiveType::Date => array
+            .as_primitive::<Date32Type>()
+            .iter()
+            .map(|x| x.map(AvroValue::Date))
+            .collect(),
+        PrimitiveType::Time => array
+            .as_primitive::<Time64MicrosecondType>()
+            .iter()
+            .map(|x| x.map(AvroValue::TimeMicros))
+            .collect(),
+        PrimitiveType::Timestamp | PrimitiveType::Timestamptz => array
+            .as_primitive::<TimestampMicrosecondType>()
+            .iter()
+            .map(|x| x.map(AvroValue::TimestampMicros))
+            .collect(),
+        PrimitiveType::String => array
+            .as_string::<i32>()
+            .iter()
+            .map(|x| x.map(|x| AvroValue::String(x.to_owned())))
+            .collect(),
+        PrimitiveType::Uuid => array
+            .as_string::<i32>()
+            .iter()
+            .map(|x| {
+                x.map(|x| {
+                    Ok(AvroValue::Fixed(
+                        16,
+                        Uuid::parse_str(x)?.as_bytes().to_vec(),
+                    ))
+                })
+                .transpose()
+            })
+            .collect::<Result<_, Error>>()?,
+        PrimitiveType::Fixed(size) => array
+            .as_fixed_size_binary()
+            .iter()
+            .map(|x| x.map(|x| AvroValue::Fixed(*size as usize, x.to_vec())))
+            .collect(),
+        PrimitiveType::Binary => array
+            .as_binary::<i32>()
+            .iter()
+            .map(|x| x.map(|x| AvroValue::Bytes(x.to_vec())))
+            .collect(),
+    };
+    values
+        .into_iter()
+        .map(|value| match (value, field.required) {
+            (Some(value), true) => Ok(value),
+            (Some(value), false) => Ok(AvroValue::Union(1, Box::new(value))),
+            (None, false) => Ok(AvroValue::Union(0, Box::new(AvroValue::Null))),
+            (None, true) => Err(Error::InvalidFormat(format!(
+                "Null value in required field {}",
+                field.name
+            ))),
+        })
+        .collect()
+}
+
+/// Convert avro values to an arrow array of the iceberg field
+fn arrow_array(values: &[&AvroValue], field: &StructField) -> Result<ArrayRef, ArrowError> {
+    let Type::Primitive(primitive) = &field.field_type else {
+        return Err(ArrowError::NotYetImplemented(format!(
+            "Avro data files with the nested field {}",
+            field.name
+        )));
+    };
+    let invalid = |value: &AvroValue| {
+        ArrowError::ParseError(format!(
+            "Avro value {:?} of field {} is not a {}",
+            value, field.name, primitive
+        ))
+    };
+    macro_rules! collect {
+        ($array:ty, $($pattern:pat => $value:expr),+) => {
+            Arc::new(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        AvroValue::Null => Ok(None),
+                        $($pattern => Ok(Some($value)),)+
+                        value => Err(invalid(value)),
+                    })
+                    .collect::<Result<$array, ArrowError>>()?,
+            ) as ArrayRef
+        };
+    }
+    Ok(match primitive {
+        PrimitiveType::Boolean => collect!(BooleanArray, AvroValue::Boolean(x) => *x),
+        PrimitiveType::Int => collect!(Int32Array, AvroValue::Int(x) => *x),
+        PrimitiveType::Long => {
+            collect!(Int64Array, AvroValue::Long(x) => *x, AvroValue::Int(x) => *x as i64)
+        }
+        PrimitiveType::Float => collect!(Float32Array, AvroValue::Float(x) => *x),
+        PrimitiveType::Double => {
+            collect!(Float64Array, AvroValue::Double(x) => *x, AvroValue::Float(x) => *x as f64)
+        }
+        PrimitiveType::Decimal { precision, scale } => {
+            let array = values
+                .iter()
+                .map(|value| match value {
+                    AvroValue::Null => Ok(None),
+                    AvroValue::Decimal(x) => Vec::<u8>::try_from(x)
+                        .map(|bytes| Some(i128_from_be_bytes(&bytes)))
+                        .map_err(|err| ArrowError::ExternalError(Box::new(err))),
+                    AvroValue::Fixed(_, bytes) | AvroValue::Bytes(bytes) => {
+                        Ok(Some(i128_from_be_bytes(bytes)))
+                    }
+                    value => Err(invalid(value)),
+                })
+                .collect::<Result<Decimal128Array, ArrowError>>()?
+                .with_precision_and_scale(*precision as u8, *scale as i8)?;
+            Arc::new(array) as ArrayRef
+        }
+        PrimitiveType::Date => {
+            collect!(Date32Array, AvroValue::Date(x) => *x, AvroValue::Int(x) => *x)
+        }
+        PrimitiveType::Time => {
+            collect!(Time64MicrosecondArray, AvroValue::TimeMicros(x) => *x, AvroValue::Long(x) => *x)
+        }
+        PrimitiveType::Timestamp => collect!(
+            TimestampMicrosecondArray,
+            AvroValue::TimestampMicros(x) => *x,
+            AvroValue::Long(x) => *x
+        ),
+        PrimitiveType::Timestamptz => Arc::new(
+            values
+                .iter()
+                .map(|value| match value {
+                    AvroValue::Null => Ok(None),
+                    AvroValue::TimestampMicros(x) | AvroValue::Long(x) => Ok(Some(*x)),
+                    value => Err(invalid(value)),
+                })
+                .collect::<Result<TimestampMicrosecondArray, ArrowError>>()?
+                .with_timezone("UTC"),
+        ) as ArrayRef,
+        PrimitiveType::String => collect!(StringArray, AvroValue::String(x) => x.as_str()),
+        PrimitiveType::Uuid => collect!(
+            StringArray,
+            AvroValue::Uuid(x) => x.to_string(),
+            AvroValue::String(x) => x.clone(),
+            AvroValue::Fixed(16, x) => Uuid::from_slice(x)
+                .map_err(|err| ArrowError::ExternalError(Box::new(err)))?
+                .to_string()
+        ),
+        PrimitiveType::Fixed(size) => {
+            Arc::new(FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        AvroValue::Null => Ok(None),
+                        AvroValue::Fixed(_, x) | AvroValue::Bytes(x) => Ok(Some(x.as_slice())),
+                        value => Err(invalid(value)),
+                    })
+                    .collect::<Result<Vec<_>, ArrowError>>()?
+                    .into_iter(),
+                *size as i32,
+            )?) as ArrayRef
+        }
+        PrimitiveType::Binary => collect!(
+            BinaryArray,
+            AvroValue::Bytes(x) => x.as_slice(),
+            AvroValue::Fixed(_, x) => x.as_slice()
+        ),
+    })
+}
+
+/// Derive the iceberg field from an avro field and its json type. Fields without a `field-id` attribute get an id from their position.
+fn iceberg_field(
+    field: &RecordField,
+    json_type: Option<&JsonValue>,
+    position: usize,
+) -> Result<StructField, Error> {
+    let attribute = |name: &str| json_type.and_then(|json_type| json_type.get(name));
+    let (schema, required) = match &field.schema {
+        AvroSchema::Union(union) => match union.variants() {
+            [AvroSchema::Null, schema] | [schema, AvroSchema::Null] => (schema, false),
+            _ => {
+                return Err(Error::NotSupported(format!(
+                    "Avro union field {}",
+                    field.name
+                )))
+            }
+        },
+        schema => (schema, true),
+    };
+    let primitive = match schema {
+        AvroSchema::Boolean => PrimitiveType::Boolean,
+        AvroSchema::Int => PrimitiveType::Int,
+        AvroSchema::Long => PrimitiveType::Long,
+        AvroSchema::Float => PrimitiveType::Float,
+        AvroSchema::Double => PrimitiveType::Double,
+        AvroSchema::Decimal(decimal) => PrimitiveType::Decimal {
+            precision: decimal.precision as u32,
+            scale: decimal.scale as u32,
+        },
+        AvroSchema::Date => PrimitiveType::Date,
+        AvroSchema::TimeMicros => PrimitiveType::Time,
+        AvroSchema::TimestampMicros => match attribute("adjust-to-utc") {
+            Some(JsonValue::Bool(true)) => PrimitiveType::Timestamptz,
+            _ => PrimitiveType::Timestamp,
+        },
+        AvroSchema::String => PrimitiveType::String,
+        AvroSchema::Uuid => PrimitiveType::Uuid,
+        AvroSchema::Fixed(fixed)
+            if fixed.size == 16
+                && attribute("logicalType").and_then(JsonValue::as_str) == Some("uuid") =>
+        {
+            PrimitiveType::Uuid
+        }
+        AvroSchema::Fixed(fixed) => PrimitiveType::Fixed(fixed.size as u64),
+        AvroSchema::Bytes => PrimitiveType::Binary,
+        _ => {
+            return Err(Error::NotSupported(format!(
+                "Avro field {} of type {:?}",
+                field.name, schema
+            )))
+        }
+    };
+    Ok(StructField {
+        id: field_id(field).unwrap_or(position as i32 + 1),
+        name: field.name.clone(),
+        required,
+        field_type: Type::Primitive(primitive),
+        doc: field.doc.clone(),
+    })
+}
+
+fn field_id(field: &RecordField) -> Option<i32> {
+    field
+        .custom_attributes
+        .get(FIELD_ID)
+        .and_then(|id| id.as_i64())
+        .map(|id| id as i32)
+}
+
+fn unwrap_union(value: &AvroValue) -> &AvroValue {
+    match value {
+        AvroValue::Union(_, value) => value,
+        value => value,
+    }
+}
+
+/// Minimum number of bytes of a fixed that holds an unscaled decimal value of the precision
+fn decimal_size(precision: u32) -> usize {
+    (1..=16)
+        .find(|bytes| 10f64.powi(precision as i32) <= 2f64.powi(8 * *bytes as i32 - 1))
+        .unwrap_or(16)
+}
+
+/// Sign-extend a big-endian two's complement value
+fn i128_from_be_bytes(bytes: &[u8]) -> i128 {
+    let fill = if bytes.first().is_some_and(|x| x & 0x80 != 0) {
+        0xff
+    } else {
+        0
+    };
+    let mut buffer = [fill; 16];
+    buffer[16 - bytes.len().min(16)..].copy_from_slice(&bytes[bytes.len().saturating_sub(16)..]);
+    i128::from_be_bytes(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, AsArray, Int64Array, StringArray, TimestampMicrosecondArray},
+        datatypes::{Int64Type, Schema as ArrowSchema, TimestampMicrosecondType},
+        record_batch::RecordBatch,
+    };
+    use iceberg_rust_spec::spec::{
+        schema::Schema,
+        types::{PrimitiveType, StructField, StructType, Type},
+        values::{Struct, Value},
+    };
+    use object_store::{memory::InMemory, ObjectStore};
+
+    use crate::file_format::metrics::MetricsConfig;
+
+    use super::{
+        avro_schema, avro_schema_json, decimal_size, i128_from_be_bytes, iceberg_field, json_type,
+        read_avro, AvroWriter,
+    };
+
+    #[test]
+    fn test_avro_schema_field_ids() {
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 4,
+                        name: "amount".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Decimal {
+                            precision: 9,
+                            scale: 2,
+                        }),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 7,
+                        name: "created".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Date),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 8,
+                        name: "updated".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Timestamptz),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 9,
+                        name: "key".to_string(),
+                        required: true,
+                        field_type: Type::Primitive(PrimitiveType::Uuid),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let json_schema = avro_schema_json(&schema).unwrap();
+        let apache_avro::Schema::Record(record) = avro_schema(&schema).unwrap() else {
+            panic!("Expected a record schema")
+        };
+        let fields = record
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(position, field)| {
+                iceberg_field(field, json_type(&json_schema, &field.name), position).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(fields, schema.fields().iter().cloned().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_decimal_bytes() {
+        assert_eq!(decimal_size(9), 4);
+        assert_eq!(decimal_size(38), 16);
+        assert_eq!(i128_from_be_bytes(&[0xff, 0x85]), -123);
+        assert_eq!(i128_from_be_bytes(&[0x00, 0x7b]), 123);
+    }
+
+    #[tokio::test]
+    async fn test_timestamptz_round_trip() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let field = |id: i32, name: &str, primitive: PrimitiveType| StructField {
+            id,
+            name: name.to_string(),
+            required: false,
+            field_type: Type::Primitive(primitive),
+            doc: None,
+        };
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(field(1, "id", PrimitiveType::Long))
+                    .with_struct_field(field(2, "created", PrimitiveType::Timestamp))
+                    .with_struct_field(field(3, "updated", PrimitiveType::Timestamptz))
+                    .with_struct_field(field(4, "key", PrimitiveType::Uuid))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        let arrow_schema: ArrowSchema = (schema.fields()).try_into().unwrap();
+
+        let mut writer = AvroWriter::try_new(
+            "test/data/file.avro",
+            &schema,
+            &MetricsConfig::default(),
+            object_store.clone(),
+        )
+        .await
+        .unwrap();
+        writer
+            .write(
+                &RecordBatch::try_new(
+                    Arc::new(arrow_schema.clone()),
+                    vec![
+                        Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef,
+                        Arc::new(TimestampMicrosecondArray::from(vec![Some(1), None])) as ArrayRef,
+                        Arc::new(
+                            TimestampMicrosecondArray::from(vec![
+                                None,
+                                Some(1_700_000_000_000_000),
+                            ])
+                            .with_timezone("UTC"),
+                        ) as ArrayRef,
+                        Arc::new(StringArray::from(vec![
+                            Some("6ba7b810-9dad-11d1-80b4-00c04fd430c8"),
+                            None,
+                        ])) as ArrayRef,
+                    ],
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        let data_file = writer
+            .close(
+                "test/data/file.avro",
+                Struct::from_iter(Vec::<(String, Option<Value>)>::new()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(*data_file.record_count(), 2);
+        assert_eq!(
+            *data_file.file_size_in_bytes() as usize,
+            object_store
+                .head(&"test/data/file.avro".into())
+                .await
+                .unwrap()
+                .size
+        );
+
+        // Without a schema the iceberg types are derived from the avro schema of the file
+        let batches = read_avro("test/data/file.avro", object_store.clone(), None, None)
+            .await
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(*batches[0].schema(), arrow_schema);
+        assert_eq!(
+            batches[0]
+                .column(2)
+                .as_primitive::<TimestampMicrosecondType>()
+                .value(1),
+            1_700_000_000_000_000
+        );
+        assert_eq!(
+            batches[0].column(3).as_string::<i32>().value(0),
+            "6ba7b810-9dad-11d1-80b4-00c04fd430c8"
+        );
+
+        // Fields are read by their field id after the column "id" was renamed
+        let renamed = Schema::builder()
+            .with_schema_id(1)
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(field(1, "order_id", PrimitiveType::Long))
+                    .with_struct_field(field(5, "id", PrimitiveType::Long))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        let batches = read_avro("test/data/file.avro", object_store, Some(&renamed), None)
+            .await
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(batches[0].num_columns(), 2);
+        assert_eq!(
+            batches[0]
+                .column(0)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![1, 2]
+        );
+        assert_eq!(batches[0].column(1).null_count(), 2);
+    }
+}
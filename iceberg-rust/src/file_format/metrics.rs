@@ -0,0 +1,492 @@
+/*!
+ * Column metrics of data files and the metrics modes that determine which metrics are stored in the manifests
+*/
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use arrow::{
+    array::{Array, ArrayRef, AsArray},
+    compute::{max, max_boolean, max_string, min, min_boolean, min_string},
+    datatypes::{
+        DataType, Date32Type, Int32Type, Int64Type, Time64MicrosecondType, TimeUnit,
+        TimestampMicrosecondType,
+    },
+    record_batch::RecordBatch,
+};
+use iceberg_rust_spec::spec::{manifest::AvroMap, schema::Schema, values::Value};
+
+use crate::error::Error;
+
+/// Table property for the metrics mode of all columns without a column specific mode
+pub static WRITE_METADATA_METRICS_DEFAULT: &str = "write.metadata.metrics.default";
+/// Prefix of the table properties for the metrics mode of a column, followed by the column name. Nested columns use dotted names.
+pub static WRITE_METADATA_METRICS_COLUMN: &str = "write.metadata.metrics.column.";
+
+static WRITE_METADATA_METRICS_DEFAULT_DEFAULT: MetricsMode = MetricsMode::Truncate(16);
+
+/// Determines which metrics of a column are stored in the data file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsMode {
+    /// No metrics
+    None,
+    /// Value counts and null value counts
+    Counts,
+    /// Counts and lower and upper bounds that are truncated to the given length
+    Truncate(usize),
+    /// Counts and full lower and upper bounds
+    Full,
+}
+
+impl FromStr for MetricsMode {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mode = s.trim().to_lowercase();
+        match mode.as_str() {
+            "none" => Ok(MetricsMode::None),
+            "counts" => Ok(MetricsMode::Counts),
+            "full" => Ok(MetricsMode::Full),
+            _ => mode
+                .strip_prefix("truncate(")
+                .and_then(|x| x.strip_suffix(')'))
+                .and_then(|x| x.parse().ok())
+                .filter(|length| *length > 0)
+                .map(MetricsMode::Truncate)
+                .ok_or(Error::InvalidFormat(format!("Metrics mode {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for MetricsMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricsMode::None => write!(f, "none"),
+            MetricsMode::Counts => write!(f, "counts"),
+            MetricsMode::Truncate(length) => write!(f, "truncate({})", length),
+            MetricsMode::Full => write!(f, "full"),
+        }
+    }
+}
+
+/// Metrics modes of the columns of a table, read from the table properties
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsConfig {
+    /// Mode of the columns without a column specific mode
+    pub default: MetricsMode,
+    /// Modes of single columns by their full name
+    pub columns: HashMap<String, MetricsMode>,
+}
+
+impl MetricsConfig {
+    /// Read the metrics modes from the table properties. Invalid modes are ignored.
+    pub fn new(properties: &HashMap<String, String>) -> Self {
+        MetricsConfig {
+            default: properties
+                .get(WRITE_METADATA_METRICS_DEFAULT)
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(WRITE_METADATA_METRICS_DEFAULT_DEFAULT),
+            columns: properties
+                .iter()
+                .filter_map(|(key, value)| {
+                    let column = key.strip_prefix(WRITE_METADATA_METRICS_COLUMN)?;
+                    Some((column.to_owned(), value.parse().ok()?))
+                })
+                .collect(),
+        }
+    }
+
+    /// Config that stores the full metrics of all columns
+    pub fn full() -> Self {
+        MetricsConfig {
+            default: MetricsMode::Full,
+            columns: HashMap::new(),
+        }
+    }
+
+    /// Metrics mode of the column with the full name
+    pub fn mode(&self, column: &str) -> MetricsMode {
+        self.columns.get(column).copied().unwrap_or(self.default)
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig::new(&HashMap::new())
+    }
+}
+
+/// Truncate a lower bound to the length of the metrics mode. Strings are truncated to the number of unicode characters, binary values to the number of bytes.
+pub(crate) fn truncate_lower_bound(value: Value, length: usize) -> Value {
+    match value {
+        Value::String(x) if x.chars().count() > length => {
+            Value::String(x.chars().take(length).collect())
+        }
+        Value::Binary(x) if x.len() > length => Value::Binary(x[..length].to_vec()),
+        x => x,
+    }
+}
+
+/// Truncate an upper bound to the length of the metrics mode. The last character or byte of a truncated bound is incremented, so that the bound is still
+/// greater than all values. Returns None if no truncated upper bound exists, because all characters or bytes have their maximum value.
+pub(crate) fn truncate_upper_bound(value: Value, length: usize) -> Option<Value> {
+    match value {
+        Value::String(x) if x.chars().count() > length => {
+            let mut chars: Vec<char> = x.chars().take(length).collect();
+            while let Some(last) = chars.pop() {
+                if let Some(next) = next_char(last) {
+                    chars.push(next);
+                    return Some(Value::String(chars.into_iter().collect()));
+                }
+            }
+            None
+        }
+        Value::Binary(x) if x.len() > length => {
+            let mut bytes = x[..length].to_vec();
+            while let Some(last) = bytes.pop() {
+                if last < u8::MAX {
+                    bytes.push(last + 1);
+                    return Some(Value::Binary(bytes));
+                }
+            }
+            None
+        }
+        x => Some(x),
+    }
+}
+
+/// Keep the lower bounds of the columns whose metrics mode stores bounds. Bounds of the mode truncate are truncated to its length.
+pub(crate) fn lower_bounds_by_mode(
+    bounds: HashMap<i32, Value>,
+    modes: &HashMap<i32, MetricsMode>,
+) -> HashMap<i32, Value> {
+    bounds
+        .into_iter()
+        .filter_map(|(id, value)| match modes.get(&id) {
+            Some(MetricsMode::Full) => Some((id, value)),
+            Some(MetricsMode::Truncate(length)) => Some((id, truncate_lower_bound(value, *length))),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Keep the upper bounds of the columns whose metrics mode stores bounds. Bounds of the mode truncate are truncated to its length
+/// and dropped if no truncated upper bound exists.
+pub(crate) fn upper_bounds_by_mode(
+    bounds: HashMap<i32, Value>,
+    modes: &HashMap<i32, MetricsMode>,
+) -> HashMap<i32, Value> {
+    bounds
+        .into_iter()
+        .filter_map(|(id, value)| match modes.get(&id) {
+            Some(MetricsMode::Full) => Some((id, value)),
+            Some(MetricsMode::Truncate(length)) => {
+                Some((id, truncate_upper_bound(value, *length)?))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Next unicode scalar value, skipping the surrogate range
+fn next_char(c: char) -> Option<char> {
+    match c {
+        '\u{D7FF}' => Some('\u{E000}'),
+        char::MAX => None,
+        c => char::from_u32(c as u32 + 1),
+    }
+}
+
+/// Value counts, null value counts and bounds of the columns of a data file
+#[derive(Debug)]
+pub(crate) struct ColumnMetrics {
+    pub(crate) record_count: i64,
+    pub(crate) value_counts: HashMap<i32, i64>,
+    pub(crate) null_value_counts: HashMap<i32, i64>,
+    pub(crate) lower_bounds: HashMap<i32, Value>,
+    pub(crate) upper_bounds: HashMap<i32, Value>,
+    modes: HashMap<i32, MetricsMode>,
+}
+
+impl ColumnMetrics {
+    /// Create empty metrics for the top-level fields of the schema. Columns with the metrics mode none are not collected.
+    pub(crate) fn new(schema: &Schema, config: &MetricsConfig) -> Self {
+        ColumnMetrics {
+            record_count: 0,
+            value_counts: HashMap::new(),
+            null_value_counts: HashMap::new(),
+            lower_bounds: HashMap::new(),
+            upper_bounds: HashMap::new(),
+            modes: schema
+                .fields()
+                .iter()
+                .map(|field| (field.id, config.mode(&field.name)))
+                .filter(|(_, mode)| *mode != MetricsMode::None)
+                .collect(),
+        }
+    }
+
+    /// Add the columns of the record batch that correspond to top-level fields of the schema to the metrics
+    pub(crate) fn update(&mut self, batch: &RecordBatch, schema: &Schema) {
+        self.record_count += batch.num_rows() as i64;
+        for field in schema.fields().iter() {
+            if !self.modes.contains_key(&field.id) {
+                continue;
+            }
+            let Some(column) = batch.column_by_name(&field.name) else {
+                continue;
+            };
+            *self.value_counts.entry(field.id).or_default() += column.len() as i64;
+            *self.null_value_counts.entry(field.id).or_default() += column.null_count() as i64;
+            if let Some((lower, upper)) = bounds(column) {
+                match self.lower_bounds.get(&field.id) {
+                    Some(current) if *current <= lower => (),
+                    _ => {
+                        self.lower_bounds.insert(field.id, lower);
+                    }
+                }
+                match self.upper_bounds.get(&field.id) {
+                    Some(current) if *current >= upper => (),
+                    _ => {
+                        self.upper_bounds.insert(field.id, upper);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Value counts in the format of the data file
+    pub(crate) fn value_counts(&self) -> AvroMap<i64> {
+        AvroMap(self.value_counts.clone())
+    }
+
+    /// Null value counts in the format of the data file
+    pub(crate) fn null_value_counts(&self) -> AvroMap<i64> {
+        AvroMap(self.null_value_counts.clone())
+    }
+
+    /// Lower bounds of the columns whose metrics mode stores bounds, truncated to the length of the mode
+    pub(crate) fn lower_bounds(&self) -> HashMap<i32, Value> {
+        lower_bounds_by_mode(self.lower_bounds.clone(), &self.modes)
+    }
+
+    /// Upper bounds of the columns whose metrics mode stores bounds, truncated to the length of the mode
+    pub(crate) fn upper_bounds(&self) -> HashMap<i32, Value> {
+        upper_bounds_by_mode(self.upper_bounds.clone(), &self.modes)
+    }
+}
+
+/// Minimum and maximum of the non-null values of a column. Floating point columns have no bounds, because NaN values are not ordered.
+fn bounds(array: &ArrayRef) -> Option<(Value, Value)> {
+    match array.data_type() {
+        DataType::Boolean => Some((
+            Value::Boolean(min_boolean(array.as_boolean())?),
+            Value::Boolean(max_boolean(array.as_boolean())?),
+        )),
+        DataType::Int32 => Some((
+            Value::Int(min(array.as_primitive::<Int32Type>())?),
+            Value::Int(max(array.as_primitive::<Int32Type>())?),
+        )),
+        DataType::Int64 => Some((
+            Value::LongInt(min(array.as_primitive::<Int64Type>())?),
+            Value::LongInt(max(array.as_primitive::<Int64Type>())?),
+        )),
+        DataType::Date32 => Some((
+            Value::Date(min(array.as_primitive::<Date32Type>())?),
+            Value::Date(max(array.as_primitive::<Date32Type>())?),
+        )),
+        DataType::Time64(TimeUnit::Microsecond) => Some((
+            Value::Time(min(array.as_primitive::<Time64MicrosecondType>())?),
+            Value::Time(max(array.as_primitive::<Time64MicrosecondType>())?),
+        )),
+        DataType::Timestamp(TimeUnit::Microsecond, None) => Some((
+            Value::Timestamp(min(array.as_primitive::<TimestampMicrosecondType>())?),
+            Value::Timestamp(max(array.as_primitive::<TimestampMicrosecondType>())?),
+        )),
+        DataType::Timestamp(TimeUnit::Microsecond, Some(_)) => Some((
+            Value::TimestampTZ(min(array.as_primitive::<TimestampMicrosecondType>())?),
+            Value::TimestampTZ(max(array.as_primitive::<TimestampMicrosecondType>())?),
+        )),
+        DataType::Utf8 => Some((
+            Value::String(min_string(array.as_string::<i32>())?.to_owned()),
+            Value::String(max_string(array.as_string::<i32>())?.to_owned()),
+        )),
+        DataType::LargeUtf8 => Some((
+            Value::String(min_string(array.as_string::<i64>())?.to_owned()),
+            Value::String(max_string(array.as_string::<i64>())?.to_owned()),
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use arrow::{
+        array::{ArrayRef, Int64Array, StringArray},
+        record_batch::RecordBatch,
+    };
+    use iceberg_rust_spec::spec::{
+        schema::Schema,
+        types::{PrimitiveType, StructField, StructType, Type},
+        values::Value,
+    };
+
+    use super::{
+        truncate_lower_bound, truncate_upper_bound, ColumnMetrics, MetricsConfig, MetricsMode,
+        WRITE_METADATA_METRICS_COLUMN, WRITE_METADATA_METRICS_DEFAULT,
+    };
+
+    #[test]
+    fn test_column_metrics() {
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .with_struct_field(StructField {
+                        id: 2,
+                        name: "name".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::String),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let batches = [
+            (vec![Some(3), None], vec![Some("bb"), Some("cc")]),
+            (vec![Some(1), Some(5)], vec![None, Some("aa")]),
+        ]
+        .into_iter()
+        .map(|(ids, names)| {
+            RecordBatch::try_from_iter(vec![
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("name", Arc::new(StringArray::from(names)) as ArrayRef),
+            ])
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+        let mut metrics = ColumnMetrics::new(&schema, &MetricsConfig::full());
+        for batch in &batches {
+            metrics.update(batch, &schema);
+        }
+
+        assert_eq!(metrics.record_count, 4);
+        assert_eq!(metrics.value_counts[&1], 4);
+        assert_eq!(metrics.null_value_counts[&1], 1);
+        assert_eq!(metrics.null_value_counts[&2], 1);
+        assert_eq!(metrics.lower_bounds[&1], Value::LongInt(1));
+        assert_eq!(metrics.upper_bounds[&1], Value::LongInt(5));
+        assert_eq!(metrics.lower_bounds[&2], Value::String("aa".to_owned()));
+        assert_eq!(metrics.upper_bounds[&2], Value::String("cc".to_owned()));
+
+        // The column "id" only stores counts and the bounds of the column "name" are truncated
+        let config = MetricsConfig::new(&HashMap::from_iter(vec![
+            (
+                WRITE_METADATA_METRICS_DEFAULT.to_owned(),
+                "truncate(1)".to_owned(),
+            ),
+            (
+                WRITE_METADATA_METRICS_COLUMN.to_owned() + "id",
+                "counts".to_owned(),
+            ),
+        ]));
+        let mut metrics = ColumnMetrics::new(&schema, &config);
+        for batch in &batches {
+            metrics.update(batch, &schema);
+        }
+        assert_eq!(metrics.value_counts[&1], 4);
+        assert!(!metrics.lower_bounds().contains_key(&1));
+        assert!(!metrics.upper_bounds().contains_key(&1));
+        assert_eq!(metrics.lower_bounds()[&2], Value::String("a".to_owned()));
+        assert_eq!(metrics.upper_bounds()[&2], Value::String("d".to_owned()));
+
+        // Columns with the mode none have no metrics
+        let config = MetricsConfig::new(&HashMap::from_iter(vec![(
+            WRITE_METADATA_METRICS_DEFAULT.to_owned(),
+            "none".to_owned(),
+        )]));
+        let mut metrics = ColumnMetrics::new(&schema, &config);
+        for batch in &batches {
+            metrics.update(batch, &schema);
+        }
+        assert_eq!(metrics.record_count, 4);
+        assert!(metrics.value_counts.is_empty());
+        assert!(metrics.null_value_counts.is_empty());
+        assert!(metrics.lower_bounds().is_empty());
+    }
+
+    #[test]
+    fn test_metrics_config() {
+        let config = MetricsConfig::new(&HashMap::from_iter(vec![
+            (
+                WRITE_METADATA_METRICS_DEFAULT.to_owned(),
+                "counts".to_owned(),
+            ),
+            (
+                WRITE_METADATA_METRICS_COLUMN.to_owned() + "name",
+                "truncate(8)".to_owned(),
+            ),
+            (
+                WRITE_METADATA_METRICS_COLUMN.to_owned() + "address.city",
+                "Full".to_owned(),
+            ),
+            (
+                WRITE_METADATA_METRICS_COLUMN.to_owned() + "id",
+                "truncate(x)".to_owned(),
+            ),
+        ]));
+        assert_eq!(config.mode("name"), MetricsMode::Truncate(8));
+        assert_eq!(config.mode("address.city"), MetricsMode::Full);
+        assert_eq!(config.mode("id"), MetricsMode::Counts);
+        assert_eq!(
+            MetricsConfig::default().mode("id"),
+            MetricsMode::Truncate(16)
+        );
+    }
+
+    #[test]
+    fn test_truncate_bounds() {
+        let value = Value::String("iceberg".to_owned());
+        assert_eq!(
+            truncate_lower_bound(value.clone(), 3),
+            Value::String("ice".to_owned())
+        );
+        assert_eq!(
+            truncate_upper_bound(value.clone(), 3),
+            Some(Value::String("icf".to_owned()))
+        );
+        assert_eq!(truncate_upper_bound(value.clone(), 10), Some(value));
+
+        // Characters are counted as unicode scalar values and the maximum character is carried over
+        let value = Value::String("a\u{10FFFF}\u{10FFFF}b".to_owned());
+        assert_eq!(
+            truncate_upper_bound(value, 3),
+            Some(Value::String("b".to_owned()))
+        );
+        assert_eq!(
+            truncate_upper_bound(Value::String("\u{D7FF}xy".to_owned()), 1),
+            Some(Value::String("\u{E000}".to_owned()))
+        );
+
+        let value = Value::Binary(vec![1, 255, 255, 3]);
+        assert_eq!(
+            truncate_lower_bound(value.clone(), 3),
+            Value::Binary(vec![1, 255, 255])
+        );
+        assert_eq!(truncate_upper_bound(value, 3), Some(Value::Binary(vec![2])));
+        assert_eq!(
+            truncate_upper_bound(Value::Binary(vec![255, 255, 0]), 2),
+            None
+        );
+    }
+}
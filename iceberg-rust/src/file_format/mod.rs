@@ -2,4 +2,25 @@
  * Helper functions for different file formats.
 */
 
+use object_store::{MultipartId, ObjectStore};
+
+use crate::error::Error;
+
+pub mod avro;
+pub mod metrics;
+pub mod orc;
 pub mod parquet;
+
+/// Abort the multipart upload of a file that couldn't be written, so that no uploaded parts are left behind in the object store.
+/// Returns the error of the write, a failure to abort the upload is ignored.
+pub(crate) async fn abort_upload(
+    object_store: &dyn ObjectStore,
+    path: &str,
+    multipart_id: &MultipartId,
+    err: Error,
+) -> Error {
+    let _ = object_store
+        .abort_multipart(&path.into(), multipart_id)
+        .await;
+    err
+}
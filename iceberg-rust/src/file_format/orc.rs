@@ -0,0 +1,429 @@
+/*!
+ * Helpers for orc files
+*/
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+};
+
+use arrow::{
+    array::new_null_array,
+    compute::cast,
+    datatypes::{Field, Schema as ArrowSchema},
+    error::ArrowError,
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use iceberg_rust_spec::{
+    spec::{
+        manifest::{Content, DataFile, FileFormat},
+        schema::Schema,
+        types::StructType,
+        values::Struct,
+    },
+    util,
+};
+use object_store::{MultipartId, ObjectStore};
+use orc_rust::{projection::ProjectionMask, ArrowReaderBuilder, ArrowWriter, ArrowWriterBuilder};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::error::Error;
+
+use super::{
+    abort_upload,
+    metrics::{ColumnMetrics, MetricsConfig},
+};
+
+/// Name of the orc type attribute that stores the iceberg field id
+pub static ICEBERG_ID: &str = "iceberg.id";
+
+/// Read an orc data file into arrow record batches. Without a schema all columns are read with the types stored in the file.
+/// With a schema only the columns of the fields with the given field ids are read, all fields of the schema if no ids are given.
+/// Orc columns are matched to the iceberg fields by their `iceberg.id` attribute and by name if the attribute is missing.
+/// The columns are cast to the arrow types of the iceberg fields, fields that are missing in the file are filled with nulls.
+pub async fn read_orc(
+    file_path: &str,
+    object_store: Arc<dyn ObjectStore>,
+    schema: Option<&Schema>,
+    field_ids: Option<&[i32]>,
+) -> Result<impl Iterator<Item = Result<RecordBatch, ArrowError>> + Send, Error> {
+    let bytes = object_store
+        .get(&util::strip_prefix(file_path).into())
+        .await?
+        .bytes()
+        .await?;
+    let builder = ArrowReaderBuilder::try_new(bytes)?;
+
+    let Some(schema) = schema else {
+        if field_ids.is_some() {
+            return Err(Error::InvalidFormat(
+                "Orc projection by field ids without a schema".to_owned(),
+            ));
+        }
+        return Ok(Box::new(builder.build()) as Box<dyn Iterator<Item = _> + Send>);
+    };
+
+    let fields = match field_ids {
+        Some(ids) => ids
+            .iter()
+            .map(|id| {
+                schema
+                    .get(*id as usize)
+                    .cloned()
+                    .ok_or(Error::NotFound("Field".to_owned(), id.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => schema.fields().iter().cloned().collect(),
+    };
+    let arrow_schema: Arc<ArrowSchema> = Arc::new((&StructType::new(fields.clone())).try_into()?);
+
+    // Position of the orc root column of every iceberg field
+    let file_schema = builder.schema();
+    let positions = fields
+        .iter()
+        .map(|field| {
+            file_schema
+                .fields()
+                .iter()
+                .position(|column| field_id(column) == Some(field.id))
+                .or_else(|| {
+                    file_schema.fields().iter().position(|column| {
+                        field_id(column).is_none() && *column.name() == field.name
+                    })
+                })
+        })
+        .collect::<Vec<_>>();
+    let names = positions
+        .iter()
+        .map(|position| position.map(|position| file_schema.field(position).name().clone()))
+        .collect::<Vec<_>>();
+    let root = builder.file_metadata().root_data_type();
+    let projection = ProjectionMask::roots(
+        root,
+        positions
+            .iter()
+            .flatten()
+            .map(|position| root.children()[*position].data_type().column_index()),
+    );
+
+    Ok(Box::new(
+        builder
+            .with_projection(projection)
+            .build()
+            .map(move |batch| {
+                let batch = batch?;
+                let columns = arrow_schema
+                    .fields()
+                    .iter()
+                    .zip(names.iter())
+                    .map(|(field, name)| match name {
+                        Some(name) => {
+                            let column = batch.column_by_name(name).ok_or_else(|| {
+                                ArrowError::SchemaError(format!(
+                                    "Column {} not found in orc file",
+                                    name
+                                ))
+                            })?;
+                            cast(column, field.data_type())
+                        }
+                        None => Ok(new_null_array(field.data_type(), batch.num_rows())),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                RecordBatch::try_new_with_options(
+                    arrow_schema.clone(),
+                    columns,
+                    &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+                )
+            }),
+    ) as Box<dyn Iterator<Item = _> + Send>)
+}
+
+/// Arrow schema of the orc file. The field ids are stored in the `iceberg.id` attribute of the orc types.
+fn orc_schema(schema: &Schema) -> Result<ArrowSchema, Error> {
+    let arrow_schema: ArrowSchema = (schema.fields()).try_into()?;
+    Ok(ArrowSchema::new(
+        arrow_schema
+            .fields()
+            .iter()
+            .zip(schema.fields().iter())
+            .map(|(field, iceberg_field)| {
+                field
+                    .as_ref()
+                    .clone()
+                    .with_metadata(HashMap::from_iter(vec![(
+                        ICEBERG_ID.to_owned(),
+                        iceberg_field.id.to_string(),
+                    )]))
+            })
+            .collect::<Vec<_>>(),
+    ))
+}
+
+fn field_id(field: &Field) -> Option<i32> {
+    field
+        .metadata()
+        .get(ICEBERG_ID)
+        .and_then(|id| id.parse().ok())
+}
+
+/// Writes arrow record batches to an orc file. The stripes are uploaded to the object store as soon as they are encoded.
+/// Collects the column metrics of the data file according to the metrics modes while writing. The upload is aborted if the file can't be written.
+pub struct OrcWriter {
+    path: String,
+    writer: ArrowWriter<StripeSender>,
+    stripes: Receiver<Vec<u8>>,
+    object_store: Arc<dyn ObjectStore>,
+    multipart_id: MultipartId,
+    upload: Box<dyn AsyncWrite + Send + Unpin>,
+    bytes_written: usize,
+    schema: Schema,
+    metrics: ColumnMetrics,
+}
+
+impl OrcWriter {
+    /// Create a writer for an orc file at the path
+    pub async fn try_new(
+        path: &str,
+        schema: &Schema,
+        metrics: &MetricsConfig,
+        object_store: Arc<dyn ObjectStore>,
+    ) -> Result<Self, Error> {
+        let (sender, stripes) = channel();
+        let writer = ArrowWriterBuilder::new(StripeSender(sender), Arc::new(orc_schema(schema)?))
+            .try_build()?;
+        let (multipart_id, upload) = object_store.put_multipart(&path.into()).await?;
+        Ok(OrcWriter {
+            path: path.to_owned(),
+            writer,
+            stripes,
+            object_store,
+            multipart_id,
+            upload,
+            bytes_written: 0,
+            schema: schema.clone(),
+            metrics: ColumnMetrics::new(schema, metrics),
+        })
+    }
+
+    /// Path of the orc file
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Number of bytes that were uploaded so far
+    pub fn bytes_written(&self) -> usize {
+        self.bytes_written
+    }
+
+    /// Write a record batch to the file
+    pub async fn write(&mut self, batch: &RecordBatch) -> Result<(), Error> {
+        self.metrics.update(batch, &self.schema);
+        let size = match self.writer.write(batch) {
+            Ok(()) => upload_stripes(&self.stripes, &mut self.upload).await,
+            Err(err) => Err(err.into()),
+        };
+        match size {
+            Ok(size) => {
+                self.bytes_written += size;
+                Ok(())
+            }
+            Err(err) => Err(abort_upload(
+                self.object_store.as_ref(),
+                &self.path,
+                &self.multipart_id,
+                err,
+            )
+            .await),
+        }
+    }
+
+    /// Finish the file and the upload to the object store and create the data file for the given location and partition
+    pub async fn close(self, location: &str, partition: Struct) -> Result<DataFile, Error> {
+        let OrcWriter {
+            path,
+            writer,
+            stripes,
+            object_store,
+            multipart_id,
+            mut upload,
+            bytes_written,
+            metrics,
+            ..
+        } = self;
+        let file_size = async {
+            writer.close()?;
+            let file_size = bytes_written + upload_stripes(&stripes, &mut upload).await?;
+            upload.shutdown().await?;
+            Ok::<_, Error>(file_size)
+        }
+        .await;
+        let file_size = match file_size {
+            Ok(file_size) => file_size,
+            Err(err) => {
+                return Err(abort_upload(object_store.as_ref(), &path, &multipart_id, err).await)
+            }
+        };
+
+        DataFile::builder()
+            .with_content(Content::Data)
+            .with_file_path(location.to_owned())
+            .with_file_format(FileFormat::Orc)
+            .with_partition(partition)
+            .with_record_count(metrics.record_count)
+            .with_file_size_in_bytes(file_size as i64)
+            .with_column_sizes(None)
+            .with_value_counts(Some(metrics.value_counts()))
+            .with_null_value_counts(Some(metrics.null_value_counts()))
+            .with_nan_value_counts(None)
+            .with_distinct_counts(None)
+            .with_lower_bounds(Some(metrics.lower_bounds()))
+            .with_upper_bounds(Some(metrics.upper_bounds()))
+            .build()
+            .map_err(iceberg_rust_spec::error::Error::from)
+            .map_err(Error::from)
+    }
+}
+
+/// Upload the encoded bytes that the orc writer sent so far and return their size
+async fn upload_stripes(
+    stripes: &Receiver<Vec<u8>>,
+    upload: &mut Box<dyn AsyncWrite + Send + Unpin>,
+) -> Result<usize, Error> {
+    let mut size = 0;
+    while let Ok(bytes) = stripes.try_recv() {
+        upload.write_all(&bytes).await?;
+        size += bytes.len();
+    }
+    Ok(size)
+}
+
+/// Sends the bytes encoded by the orc writer to the upload of the file
+#[derive(Debug)]
+struct StripeSender(Sender<Vec<u8>>);
+
+impl Write for StripeSender {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .send(buf.to_vec())
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::BrokenPipe, err))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, AsArray, Int64Array, StringArray},
+        datatypes::Int64Type,
+        record_batch::RecordBatch,
+    };
+    use iceberg_rust_spec::spec::{
+        schema::Schema,
+        types::{PrimitiveType, StructField, StructType, Type},
+        values::{Struct, Value},
+    };
+    use object_store::{memory::InMemory, ObjectStore};
+
+    use crate::file_format::metrics::MetricsConfig;
+
+    use super::{read_orc, OrcWriter};
+
+    #[tokio::test]
+    async fn test_read_orc_by_field_id() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let field = |id: i32, name: &str, primitive: PrimitiveType| StructField {
+            id,
+            name: name.to_string(),
+            required: false,
+            field_type: Type::Primitive(primitive),
+            doc: None,
+        };
+        let schema = Schema::builder()
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(field(1, "id", PrimitiveType::Long))
+                    .with_struct_field(field(2, "name", PrimitiveType::String))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut writer = OrcWriter::try_new(
+            "test/data/file.orc",
+            &schema,
+            &MetricsConfig::default(),
+            object_store.clone(),
+        )
+        .await
+        .unwrap();
+        writer
+            .write(
+                &RecordBatch::try_from_iter(vec![
+                    ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+                    (
+                        "name",
+                        Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef,
+                    ),
+                ])
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        let data_file = writer
+            .close(
+                "test/data/file.orc",
+                Struct::from_iter(Vec::<(String, Option<Value>)>::new()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(*data_file.record_count(), 3);
+        assert_eq!(
+            *data_file.file_size_in_bytes() as usize,
+            object_store
+                .head(&"test/data/file.orc".into())
+                .await
+                .unwrap()
+                .size
+        );
+
+        // The column "id" was renamed to "key" and the column "name" was dropped
+        let renamed = Schema::builder()
+            .with_schema_id(1)
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(field(1, "key", PrimitiveType::Long))
+                    .with_struct_field(field(3, "name", PrimitiveType::String))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        let batches = read_orc("test/data/file.orc", object_store, Some(&renamed), None)
+            .await
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].schema().field(0).name(), "key");
+        assert_eq!(
+            batches[0]
+                .column(0)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(batches[0].column(1).null_count(), 3);
+    }
+}
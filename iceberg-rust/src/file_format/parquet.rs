@@ -15,21 +15,27 @@ use iceberg_rust_spec::spec::{
     values::{Struct, Value},
 };
 use parquet::{
+    arrow::{async_reader::AsyncFileReader, ParquetRecordBatchStreamBuilder},
+    basic::Type as PhysicalType,
+    bloom_filter::Sbbf,
     file::{metadata::RowGroupMetaData, writer::TrackedWrite},
     format::FileMetaData,
     schema::types::{from_thrift, SchemaDescriptor},
 };
 use thrift::protocol::{TCompactOutputProtocol, TSerializable};
 
-use crate::error::Error;
+use crate::{error::Error, table::scan::Predicate};
 
-/// Read datafile statistics from parquetfile
+use super::metrics::{lower_bounds_by_mode, upper_bounds_by_mode, MetricsConfig, MetricsMode};
+
+/// Read datafile statistics from parquetfile. The metrics modes determine which statistics of a column are kept.
 pub fn parquet_to_datafile(
     location: &str,
     file_size: usize,
     file_metadata: &FileMetaData,
     schema: &Schema,
     partition_spec: &[PartitionField],
+    metrics: &MetricsConfig,
 ) -> Result<DataFile, Error> {
     let mut partition = partition_spec
         .iter()
@@ -59,24 +65,34 @@ pub fn parquet_to_datafile(
     let mut distinct_counts = AvroMap(HashMap::new());
     let mut lower_bounds: HashMap<i32, Value> = HashMap::new();
     let mut upper_bounds: HashMap<i32, Value> = HashMap::new();
+    let mut modes: HashMap<i32, MetricsMode> = HashMap::new();
+    let mut split_offsets = Vec::new();
 
     for row_group in &file_metadata.row_groups {
         let row_group = RowGroupMetaData::from_thrift(parquet_schema.clone(), row_group.clone())?;
 
+        // Row groups start with the first page of their first column chunk
+        if let Some(column) = row_group.columns().first() {
+            split_offsets.push(column.byte_range().0 as i64);
+        }
+
         for column in row_group.columns() {
-            let column_name = column.column_descr().name();
-            let id = schema
-                .get_name(column_name)
-                .ok_or_else(|| Error::Schema(column_name.to_string(), "".to_string()))?
-                .id;
+            let path = column.column_descr().path().parts();
+            let (id, column_name, data_type) = leaf_field(schema, path)
+                .ok_or_else(|| Error::Schema(path.join("."), "".to_string()))?;
+            let mode = metrics.mode(&column_name);
+            if mode == MetricsMode::None {
+                continue;
+            }
+            modes.insert(id, mode);
             column_sizes
                 .entry(id)
                 .and_modify(|x| *x += column.compressed_size())
                 .or_insert(column.compressed_size());
             value_counts
                 .entry(id)
-                .and_modify(|x| *x += row_group.num_rows())
-                .or_insert(row_group.num_rows());
+                .and_modify(|x| *x += column.num_values())
+                .or_insert(column.num_values());
 
             if let Some(statistics) = column.statistics() {
                 null_value_counts
@@ -89,59 +105,13 @@ pub fn parquet_to_datafile(
                         .and_modify(|x| *x += distinct_count as i64)
                         .or_insert(distinct_count as i64);
                 }
-                let data_type = &schema
-                    .fields()
-                    .get(id as usize)
-                    .ok_or_else(|| Error::Schema(column_name.to_string(), "".to_string()))?
-                    .field_type;
 
-                if let Type::Primitive(_) = &data_type {
+                if let (Type::Primitive(_), true) = (data_type, statistics.has_min_max_set()) {
                     let new = Value::try_from_bytes(statistics.min_bytes(), data_type)?;
                     match lower_bounds.entry(id) {
                         Entry::Occupied(mut entry) => {
-                            let entry = entry.get_mut();
-                            match (&entry, &new) {
-                                (Value::Int(current), Value::Int(new_val)) => {
-                                    if *current > *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::LongInt(current), Value::LongInt(new_val)) => {
-                                    if *current > *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::Float(current), Value::Float(new_val)) => {
-                                    if *current > *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::Double(current), Value::Double(new_val)) => {
-                                    if *current > *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::Date(current), Value::Date(new_val)) => {
-                                    if *current > *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::Time(current), Value::Time(new_val)) => {
-                                    if *current > *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::Timestamp(current), Value::Timestamp(new_val)) => {
-                                    if *current > *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::TimestampTZ(current), Value::TimestampTZ(new_val)) => {
-                                    if *current > *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                _ => (),
+                            if new < *entry.get() {
+                                entry.insert(new);
                             }
                         }
                         Entry::Vacant(entry) => {
@@ -151,49 +121,8 @@ pub fn parquet_to_datafile(
                     let new = Value::try_from_bytes(statistics.max_bytes(), data_type)?;
                     match upper_bounds.entry(id) {
                         Entry::Occupied(mut entry) => {
-                            let entry = entry.get_mut();
-                            match (&entry, &new) {
-                                (Value::Int(current), Value::Int(new_val)) => {
-                                    if *current < *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::LongInt(current), Value::LongInt(new_val)) => {
-                                    if *current < *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::Float(current), Value::Float(new_val)) => {
-                                    if *current < *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::Double(current), Value::Double(new_val)) => {
-                                    if *current < *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::Date(current), Value::Date(new_val)) => {
-                                    if *current < *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::Time(current), Value::Time(new_val)) => {
-                                    if *current < *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::Timestamp(current), Value::Timestamp(new_val)) => {
-                                    if *current < *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                (Value::TimestampTZ(current), Value::TimestampTZ(new_val)) => {
-                                    if *current < *new_val {
-                                        *entry = new
-                                    }
-                                }
-                                _ => (),
+                            if new > *entry.get() {
+                                entry.insert(new);
                             }
                         }
                         Entry::Vacant(entry) => {
@@ -201,17 +130,17 @@ pub fn parquet_to_datafile(
                         }
                     }
 
-                    if let Some(partition_value) = partition.get_mut(column_name) {
+                    if let Some(partition_value) = partition.get_mut(&column_name) {
                         if partition_value.is_none() {
                             let transform = transforms
-                                .get(column_name)
+                                .get(&column_name)
                                 .ok_or_else(|| Error::InvalidFormat("transform".to_string()))?;
                             let min = Value::try_from_bytes(statistics.min_bytes(), data_type)?
                                 .tranform(transform)?;
                             let max = Value::try_from_bytes(statistics.max_bytes(), data_type)?
                                 .tranform(transform)?;
                             if min == max {
-                                *partition_value = Some(min)
+                                *partition_value = min
                             }
                         }
                     }
@@ -219,6 +148,11 @@ pub fn parquet_to_datafile(
             }
         }
     }
+
+    // Bounds are truncated after they were merged over all row groups
+    let lower_bounds = lower_bounds_by_mode(lower_bounds, &modes);
+    let upper_bounds = upper_bounds_by_mode(upper_bounds, &modes);
+
     let content = DataFile::builder()
         .with_content(Content::Data)
         .with_file_path(location.to_string())
@@ -233,11 +167,132 @@ pub fn parquet_to_datafile(
         .with_distinct_counts(Some(distinct_counts))
         .with_lower_bounds(Some(lower_bounds))
         .with_upper_bounds(Some(upper_bounds))
+        .with_split_offsets(Some(split_offsets))
         .build()
         .map_err(iceberg_rust_spec::error::Error::from)?;
     Ok(content)
 }
 
+/// Find the iceberg field of a parquet leaf column by its path. Returns the field id, the full dotted name and the type of the field.
+/// Lists and maps are written with the intermediate `list` and `key_value` groups, which are skipped.
+fn leaf_field<'schema>(
+    schema: &'schema Schema,
+    path: &[String],
+) -> Option<(i32, String, &'schema Type)> {
+    let (first, rest) = path.split_first()?;
+    let field = schema.get_name(first)?;
+    let mut id = field.id;
+    let mut names = vec![field.name.clone()];
+    let mut data_type = &field.field_type;
+    for part in rest {
+        match (data_type, part.as_str()) {
+            (Type::Struct(fields), name) => {
+                let field = fields.get_name(name)?;
+                id = field.id;
+                names.push(field.name.clone());
+                data_type = &field.field_type;
+            }
+            (Type::List(_), "list") | (Type::Map(_), "key_value") => (),
+            (Type::List(list), "element" | "item") => {
+                id = list.element_id;
+                names.push("element".to_owned());
+                data_type = &list.element;
+            }
+            (Type::Map(map), "key") => {
+                id = map.key_id;
+                names.push("key".to_owned());
+                data_type = &map.key;
+            }
+            (Type::Map(map), "value") => {
+                id = map.value_id;
+                names.push("value".to_owned());
+                data_type = &map.value;
+            }
+            _ => return None,
+        }
+    }
+    Some((id, names.join("."), data_type))
+}
+
+/// Select the row groups of a parquet file that might contain rows matching the equality and in predicates of the filter, based on the bloom filters
+/// of the columns. The columns of the predicates are resolved to field ids with the schema and matched to the parquet leaf columns with the same
+/// `PARQUET:field_id`. Predicates without such a column and row groups without a bloom filter for a column are not used for pruning.
+pub(crate) async fn bloom_filter_row_groups<T: AsyncFileReader + Send + 'static>(
+    builder: &mut ParquetRecordBatchStreamBuilder<T>,
+    filter: &[Predicate],
+    schema: &Schema,
+) -> Result<Vec<usize>, Error> {
+    let num_row_groups = builder.metadata().num_row_groups();
+    // Parquet column index, physical type and values of every predicate that can be checked with a bloom filter
+    let checks = filter
+        .iter()
+        .filter_map(|predicate| {
+            let values = match predicate {
+                Predicate::Eq(_, value) => vec![value.clone()],
+                Predicate::In(_, values) => values.clone(),
+                _ => return None,
+            };
+            let id = schema.fields().get_name(predicate.column())?.id;
+            let columns = builder.metadata().file_metadata().schema_descr().columns();
+            let index = columns.iter().position(|column| {
+                let info = column.self_type().get_basic_info();
+                info.has_id() && info.id() == id
+            })?;
+            Some((index, columns[index].physical_type(), values))
+        })
+        .collect::<Vec<_>>();
+    if checks.is_empty() {
+        return Ok((0..num_row_groups).collect());
+    }
+
+    let mut row_groups = Vec::with_capacity(num_row_groups);
+    'row_groups: for row_group in 0..num_row_groups {
+        for (column, physical_type, values) in &checks {
+            let Some(bloom_filter) = builder
+                .get_row_group_column_bloom_filter(row_group, *column)
+                .await?
+            else {
+                continue;
+            };
+            if !values
+                .iter()
+                .any(|value| bloom_filter_may_contain(&bloom_filter, value, *physical_type))
+            {
+                continue 'row_groups;
+            }
+        }
+        row_groups.push(row_group);
+    }
+    Ok(row_groups)
+}
+
+/// Check whether the value might be contained in the bloom filter. The value is hashed in the physical representation of the column,
+/// values that can't be converted to it are assumed to be contained.
+fn bloom_filter_may_contain(
+    bloom_filter: &Sbbf,
+    value: &Value,
+    physical_type: PhysicalType,
+) -> bool {
+    match (value, physical_type) {
+        (Value::Boolean(x), PhysicalType::BOOLEAN) => bloom_filter.check(x),
+        (Value::Int(x) | Value::Date(x), PhysicalType::INT32) => bloom_filter.check(x),
+        (
+            Value::LongInt(x) | Value::Time(x) | Value::Timestamp(x) | Value::TimestampTZ(x),
+            PhysicalType::INT64,
+        ) => bloom_filter.check(x),
+        (Value::Float(x), PhysicalType::FLOAT) => bloom_filter.check(&x.0),
+        (Value::Double(x), PhysicalType::DOUBLE) => bloom_filter.check(&x.0),
+        (Value::String(x), PhysicalType::BYTE_ARRAY) => bloom_filter.check(x.as_str()),
+        (Value::UUID(x), PhysicalType::BYTE_ARRAY) => bloom_filter.check(x.to_string().as_str()),
+        (Value::UUID(x), PhysicalType::FIXED_LEN_BYTE_ARRAY) => {
+            bloom_filter.check(x.as_bytes().as_slice())
+        }
+        (Value::Fixed(_, x), PhysicalType::FIXED_LEN_BYTE_ARRAY)
+        | (Value::Binary(x), PhysicalType::BYTE_ARRAY) => bloom_filter.check(x.as_slice()),
+        _ => true,
+    }
+}
+
 /// Get parquet metadata size
 pub fn thrift_size<T: TSerializable>(metadata: &T) -> Result<usize, Error> {
     let mut buffer = TrackedWrite::new(Vec::<u8>::new());
@@ -245,3 +300,167 @@ pub fn thrift_size<T: TSerializable>(metadata: &T) -> Result<usize, Error> {
     metadata.write_to_out_protocol(&mut protocol)?;
     Ok(buffer.bytes_written())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use arrow::{
+        array::{ArrayRef, Int64Array},
+        datatypes::{DataType, Field, Schema as ArrowSchema},
+        record_batch::RecordBatch,
+    };
+    use iceberg_rust_spec::{
+        arrow::schema::PARQUET_FIELD_ID_META_KEY,
+        spec::{
+            schema::Schema,
+            types::{PrimitiveType, StructField, StructType, Type},
+            values::Value,
+        },
+    };
+    use object_store::{memory::InMemory, path::Path, ObjectStore};
+    use parquet::arrow::{
+        async_reader::ParquetObjectReader, ArrowWriter, ParquetRecordBatchStreamBuilder,
+    };
+
+    use crate::{
+        arrow::write::{
+            WriterConfig, WRITE_PARQUET_BLOOM_FILTER_ENABLED_COLUMN,
+            WRITE_PARQUET_BLOOM_FILTER_FPP_COLUMN,
+        },
+        table::scan::Predicate,
+    };
+
+    use super::bloom_filter_row_groups;
+
+    #[tokio::test]
+    async fn test_bloom_filter_row_groups() {
+        let config = WriterConfig::new(&HashMap::from_iter(vec![
+            (
+                WRITE_PARQUET_BLOOM_FILTER_ENABLED_COLUMN.to_owned() + "id",
+                "true".to_owned(),
+            ),
+            (
+                WRITE_PARQUET_BLOOM_FILTER_FPP_COLUMN.to_owned() + "id",
+                "0.001".to_owned(),
+            ),
+        ]))
+        .unwrap();
+
+        let arrow_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "id",
+            DataType::Int64,
+            true,
+        )
+        .with_metadata(HashMap::from_iter(vec![(
+            PARQUET_FIELD_ID_META_KEY.to_owned(),
+            "1".to_owned(),
+        )]))]));
+        let mut buffer = Vec::new();
+        let mut writer = None;
+        for ids in [vec![1, 2, 3], vec![10, 11, 12]] {
+            let batch = RecordBatch::try_new(
+                arrow_schema.clone(),
+                vec![Arc::new(Int64Array::from(ids)) as ArrayRef],
+            )
+            .unwrap();
+            let writer = writer.get_or_insert_with(|| {
+                ArrowWriter::try_new(
+                    &mut buffer,
+                    batch.schema(),
+                    Some(config.writer_properties()),
+                )
+                .unwrap()
+            });
+            writer.write(&batch).unwrap();
+            // Every batch is written to its own row group
+            writer.flush().unwrap();
+        }
+        writer.unwrap().close().unwrap();
+
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let path = Path::from("data.parquet");
+        object_store.put(&path, buffer.into()).await.unwrap();
+        let object_meta = object_store.head(&path).await.unwrap();
+
+        let schema = |id: i32, name: &str| {
+            Schema::builder()
+                .with_fields(
+                    StructType::builder()
+                        .with_struct_field(StructField {
+                            id,
+                            name: name.to_owned(),
+                            required: false,
+                            field_type: Type::Primitive(PrimitiveType::Long),
+                            doc: None,
+                        })
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+        };
+        let check = |filter: Vec<Predicate>, schema: Schema| {
+            let object_store = object_store.clone();
+            let object_meta = object_meta.clone();
+            async move {
+                let mut builder = ParquetRecordBatchStreamBuilder::new(ParquetObjectReader::new(
+                    object_store,
+                    object_meta,
+                ))
+                .await
+                .unwrap();
+                bloom_filter_row_groups(&mut builder, &filter, &schema)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        assert_eq!(
+            check(
+                vec![Predicate::Eq("id".to_owned(), Value::LongInt(11))],
+                schema(1, "id")
+            )
+            .await,
+            vec![1]
+        );
+        assert_eq!(
+            check(
+                vec![Predicate::In(
+                    "id".to_owned(),
+                    vec![Value::LongInt(2), Value::LongInt(12)]
+                )],
+                schema(1, "id")
+            )
+            .await,
+            vec![0, 1]
+        );
+        assert_eq!(
+            check(
+                vec![Predicate::Lt("id".to_owned(), Value::LongInt(2))],
+                schema(1, "id")
+            )
+            .await,
+            vec![0, 1]
+        );
+
+        // The column was renamed, it is still matched by its field id
+        assert_eq!(
+            check(
+                vec![Predicate::Eq("key".to_owned(), Value::LongInt(11))],
+                schema(1, "key")
+            )
+            .await,
+            vec![1]
+        );
+        // The column was dropped and a new column with the same name was added, the file has no column with its field id
+        assert_eq!(
+            check(
+                vec![Predicate::Eq("id".to_owned(), Value::LongInt(11))],
+                schema(2, "id")
+            )
+            .await,
+            vec![0, 1]
+        );
+    }
+}
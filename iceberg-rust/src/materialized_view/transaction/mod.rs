@@ -134,9 +134,7 @@ impl<'view> Transaction<'view> {
                     )
                     .await?;
 
-                if let Some(requirement) = requirement {
-                    table_requirements.push(requirement);
-                }
+                table_requirements.extend(requirement);
                 table_updates.extend(update);
             }
 
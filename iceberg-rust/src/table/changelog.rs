@@ -0,0 +1,210 @@
+/*!
+ * Changelog scans that return the rows that were inserted and deleted by the snapshots of a table.
+*/
+
+use std::collections::HashSet;
+
+use iceberg_rust_spec::spec::{
+    manifest::{Content, ManifestEntry},
+    name_mapping::NameMapping,
+    schema::Schema,
+    snapshot::Operation,
+};
+
+use crate::{
+    arrow::{delete::applies_to, projection::name_mapping},
+    error::Error,
+};
+
+use super::{
+    datafiles,
+    incremental::{snapshot_range, StartSnapshot},
+    snapshot_changes, Table,
+};
+
+/// Name of the changelog column that contains the type of the change
+pub static CHANGE_TYPE: &str = "_change_type";
+/// Name of the changelog column that contains the position of the snapshot in the changelog
+pub static CHANGE_ORDINAL: &str = "_change_ordinal";
+/// Name of the changelog column that contains the id of the snapshot that committed the change
+pub static COMMIT_SNAPSHOT_ID: &str = "_commit_snapshot_id";
+
+/// Type of a row change in the changelog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeType {
+    /// The row was inserted
+    Insert,
+    /// The row was deleted
+    Delete,
+}
+
+impl ChangeType {
+    /// Value of the change type column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeType::Insert => "INSERT",
+            ChangeType::Delete => "DELETE",
+        }
+    }
+}
+
+/// Determines which rows of a data file changed in a snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileChanges {
+    /// The data file was added by the snapshot. All rows are inserted, except the rows that are deleted by delete files of the same snapshot.
+    Added {
+        /// Delete files added by the snapshot that apply to the data file
+        deletes: Vec<ManifestEntry>,
+    },
+    /// The data file was removed by the snapshot. All rows that were not deleted before are deleted.
+    Removed {
+        /// Delete files that applied to the data file before the snapshot
+        deletes: Vec<ManifestEntry>,
+    },
+    /// Rows of an existing data file are deleted by delete files that were added by the snapshot
+    RowsDeleted {
+        /// Delete files that applied to the data file before the snapshot
+        existing_deletes: Vec<ManifestEntry>,
+        /// Delete files added by the snapshot that apply to the data file
+        added_deletes: Vec<ManifestEntry>,
+    },
+}
+
+/// Rows of a data file that changed in a snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangelogTask {
+    /// Position of the snapshot in the changelog, starting with 0 for the oldest snapshot
+    pub change_ordinal: i64,
+    /// Id of the snapshot that committed the change
+    pub commit_snapshot_id: i64,
+    /// Data file whose rows changed
+    pub data_file: ManifestEntry,
+    /// Rows of the data file that changed
+    pub changes: FileChanges,
+    /// Schema of the changelog that the columns of the data file are projected onto
+    pub schema: Schema,
+    /// Name mapping for data files that were written without field ids
+    pub name_mapping: Option<NameMapping>,
+}
+
+impl ChangelogTask {
+    /// Type of the row changes of the task
+    pub fn change_type(&self) -> ChangeType {
+        match self.changes {
+            FileChanges::Added { .. } => ChangeType::Insert,
+            FileChanges::Removed { .. } | FileChanges::RowsDeleted { .. } => ChangeType::Delete,
+        }
+    }
+}
+
+impl Table {
+    /// Plan the changelog between the start and the end snapshot. The end snapshot defaults to the current snapshot,
+    /// without a start snapshot the changelog begins with the first snapshot of the table. The rows of all tasks are read with the current table schema.
+    /// Replace snapshots don't change the table data and are not part of the changelog.
+    pub async fn changelog_tasks(
+        &self,
+        start: Option<StartSnapshot>,
+        end: Option<i64>,
+    ) -> Result<Vec<ChangelogTask>, Error> {
+        let metadata = self.metadata();
+        let object_store = self.object_store();
+        let schema = metadata.current_schema(None)?;
+        let name_mapping = name_mapping(&metadata.properties)?;
+        let is_data = |entry: &ManifestEntry| matches!(entry.data_file().content(), Content::Data);
+        let applying = |deletes: &[ManifestEntry], data_file: &ManifestEntry| {
+            deletes
+                .iter()
+                .filter(|delete| applies_to(delete, data_file))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let mut tasks = Vec::new();
+        let mut change_ordinal = 0;
+        for snapshot in snapshot_range(metadata, start, end)? {
+            if matches!(snapshot.summary().operation, Operation::Replace) {
+                continue;
+            }
+            let commit_snapshot_id = *snapshot.snapshot_id();
+            let (added, removed) =
+                snapshot_changes(snapshot, metadata, object_store.clone()).await?;
+            let (added_data, added_deletes): (Vec<_>, Vec<_>) =
+                added.into_iter().partition(is_data);
+            let removed_data: Vec<_> = removed.into_iter().filter(is_data).collect();
+
+            // Files of the parent snapshot
+            let parent = snapshot
+                .parent_snapshot_id()
+                .as_ref()
+                .and_then(|id| metadata.snapshots.get(id));
+            let (existing_data, existing_deletes): (Vec<_>, Vec<_>) = match parent {
+                Some(parent) => {
+                    let manifests = parent
+                        .manifests(metadata, object_store.clone())
+                        .await?
+                        .collect::<Result<Vec<_>, _>>()?;
+                    datafiles(object_store.clone(), &manifests, None).await?
+                }
+                None => Vec::new(),
+            }
+            .into_iter()
+            .partition(is_data);
+
+            for data_file in added_data {
+                tasks.push(ChangelogTask {
+                    change_ordinal,
+                    commit_snapshot_id,
+                    changes: FileChanges::Added {
+                        deletes: applying(&added_deletes, &data_file),
+                    },
+                    data_file,
+                    schema: schema.clone(),
+                    name_mapping: name_mapping.clone(),
+                });
+            }
+
+            let removed_paths: HashSet<String> = removed_data
+                .iter()
+                .map(|entry| entry.data_file().file_path().clone())
+                .collect();
+            for data_file in removed_data {
+                tasks.push(ChangelogTask {
+                    change_ordinal,
+                    commit_snapshot_id,
+                    changes: FileChanges::Removed {
+                        deletes: applying(&existing_deletes, &data_file),
+                    },
+                    data_file,
+                    schema: schema.clone(),
+                    name_mapping: name_mapping.clone(),
+                });
+            }
+
+            if !added_deletes.is_empty() {
+                for data_file in existing_data {
+                    if removed_paths.contains(data_file.data_file().file_path()) {
+                        continue;
+                    }
+                    let deletes = applying(&added_deletes, &data_file);
+                    if deletes.is_empty() {
+                        continue;
+                    }
+                    tasks.push(ChangelogTask {
+                        change_ordinal,
+                        commit_snapshot_id,
+                        changes: FileChanges::RowsDeleted {
+                            existing_deletes: applying(&existing_deletes, &data_file),
+                            added_deletes: deletes,
+                        },
+                        data_file,
+                        schema: schema.clone(),
+                        name_mapping: name_mapping.clone(),
+                    });
+                }
+            }
+
+            change_ordinal += 1;
+        }
+        Ok(tasks)
+    }
+}
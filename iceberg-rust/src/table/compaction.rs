@@ -0,0 +1,455 @@
+/*!
+ * Compaction of small and oversized data files into files of the target size
+*/
+
+use std::{cmp::Reverse, collections::HashMap, sync::Arc};
+
+use arrow::{
+    compute::{concat_batches, lexsort_to_indices, take, SortColumn, SortOptions},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use derive_builder::Builder;
+use futures::{stream, TryStreamExt};
+use iceberg_rust_spec::spec::{
+    manifest::{Content, DataFile, ManifestEntry},
+    sort::{NullOrder, SortDirection, SortField},
+    table_metadata::TableMetadata,
+    types::StructField,
+    values::Struct,
+};
+use object_store::ObjectStore;
+
+use crate::{
+    arrow::{
+        delete::applies_to, read::read, transform::transform_arrow,
+        write::write_parquet_partitioned_with_target_size,
+    },
+    error::Error,
+};
+
+use super::Table;
+
+/// Determines how the rows of a rewrite group are written to the new files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompactionStrategy {
+    /// Combine the rows of the files without changing their order
+    #[default]
+    BinPack,
+    /// Sort the rows of every rewrite group by the default sort order of the table
+    Sort,
+}
+
+/// Options to select and rewrite the data files of a compaction
+#[derive(Debug, Clone, PartialEq, Builder)]
+#[builder(setter(prefix = "with"))]
+pub struct CompactionOptions {
+    /// Target size of the new data files in bytes
+    #[builder(default = "512 * 1024 * 1024")]
+    pub target_file_size: u64,
+    /// Files smaller than this size in bytes are compacted. Defaults to 75% of the target size.
+    #[builder(setter(strip_option), default)]
+    pub min_file_size: Option<u64>,
+    /// Files larger than this size in bytes are split. Defaults to 180% of the target size.
+    #[builder(setter(strip_option), default)]
+    pub max_file_size: Option<u64>,
+    /// Minimum number of files in a rewrite group. Groups with fewer files are only rewritten if they exceed the target size or contain an oversized file.
+    #[builder(default = "5")]
+    pub min_input_files: usize,
+    /// Maximum size of all files in a rewrite group in bytes
+    #[builder(default = "100 * 1024 * 1024 * 1024")]
+    pub max_group_size: u64,
+    /// Maximum size of all files in a rewrite group in bytes with the sort strategy. The rows of a group are sorted in memory.
+    /// Defaults to twice the target size.
+    #[builder(setter(strip_option), default)]
+    pub max_sort_group_size: Option<u64>,
+    /// Strategy to write the rows of a rewrite group
+    #[builder(default)]
+    pub strategy: CompactionStrategy,
+    /// Commit every rewrite group in its own snapshot, so that the progress is kept if a later group fails
+    #[builder(default)]
+    pub partial_progress: bool,
+}
+
+impl CompactionOptions {
+    /// Create a builder for the compaction options
+    pub fn builder() -> CompactionOptionsBuilder {
+        CompactionOptionsBuilder::default()
+    }
+    fn min_file_size(&self) -> u64 {
+        self.min_file_size.unwrap_or(self.target_file_size / 4 * 3)
+    }
+    fn max_file_size(&self) -> u64 {
+        self.max_file_size.unwrap_or(self.target_file_size / 5 * 9)
+    }
+    fn max_group_size(&self) -> u64 {
+        match self.strategy {
+            CompactionStrategy::BinPack => self.max_group_size,
+            CompactionStrategy::Sort => self
+                .max_sort_group_size
+                .unwrap_or(self.target_file_size * 2)
+                .min(self.max_group_size),
+        }
+    }
+}
+
+/// Data files of a partition that are rewritten together
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewriteGroup {
+    /// Partition of the data files
+    pub partition: Struct,
+    /// Data files to rewrite
+    pub files: Vec<ManifestEntry>,
+}
+
+impl RewriteGroup {
+    /// Size of all files in the group in bytes
+    pub fn size(&self) -> u64 {
+        self.files.iter().map(file_size).sum()
+    }
+}
+
+/// Group the data files that are smaller or larger than the configured sizes per partition into rewrite groups.
+/// The files are bin-packed into groups of at most the maximum group size of the strategy, groups that wouldn't improve the layout are dropped.
+pub fn plan_rewrite_groups(
+    entries: impl IntoIterator<Item = ManifestEntry>,
+    options: &CompactionOptions,
+) -> Vec<RewriteGroup> {
+    let min_file_size = options.min_file_size();
+    let max_file_size = options.max_file_size();
+    let max_group_size = options.max_group_size();
+
+    let mut partitions: HashMap<Struct, Vec<ManifestEntry>> = HashMap::new();
+    for entry in entries {
+        let size = file_size(&entry);
+        if matches!(entry.data_file().content(), Content::Data)
+            && (size < min_file_size || size > max_file_size)
+        {
+            partitions
+                .entry(entry.data_file().partition().clone())
+                .or_default()
+                .push(entry);
+        }
+    }
+
+    let mut groups = Vec::new();
+    for (partition, mut files) in partitions {
+        // First fit decreasing
+        files.sort_by_key(|entry| Reverse(file_size(entry)));
+        let mut bins: Vec<(u64, Vec<ManifestEntry>)> = Vec::new();
+        for entry in files {
+            let size = file_size(&entry);
+            match bins
+                .iter_mut()
+                .find(|(bin_size, _)| bin_size + size <= max_group_size)
+            {
+                Some((bin_size, bin)) => {
+                    *bin_size += size;
+                    bin.push(entry);
+                }
+                None => bins.push((size, vec![entry])),
+            }
+        }
+
+        groups.extend(
+            bins.into_iter()
+                .filter(|(size, files)| {
+                    files.len() >= options.min_input_files
+                        || (files.len() > 1 && *size > options.target_file_size)
+                        || files.iter().any(|entry| file_size(entry) > max_file_size)
+                })
+                .map(|(_, files)| RewriteGroup {
+                    partition: partition.clone(),
+                    files,
+                }),
+        );
+    }
+    groups
+}
+
+impl Table {
+    /// Rewrite small and oversized data files of a branch into files of the target size. The rewritten groups are committed as file rewrites.
+    /// Delete files are applied to the rewritten rows. Returns the rewrite groups that were committed.
+    pub async fn compact(
+        &mut self,
+        branch: Option<&str>,
+        options: &CompactionOptions,
+    ) -> Result<Vec<RewriteGroup>, Error> {
+        let Some(snapshot_id) = self
+            .metadata()
+            .current_snapshot(branch)?
+            .map(|snapshot| *snapshot.snapshot_id())
+        else {
+            return Ok(Vec::new());
+        };
+        // Fail before any group is rewritten, groups might already be committed with partial progress
+        if options.strategy == CompactionStrategy::Sort {
+            sort_fields(self.metadata(), branch)?;
+        }
+
+        let manifests = self.manifests(None, Some(snapshot_id)).await?;
+        let (data_files, delete_files): (Vec<_>, Vec<_>) = self
+            .datafiles(&manifests, None)
+            .await?
+            .into_iter()
+            .partition(|entry| matches!(entry.data_file().content(), Content::Data));
+
+        let groups = plan_rewrite_groups(data_files, options);
+
+        let mut files_to_delete = Vec::new();
+        let mut files = Vec::new();
+        for group in &groups {
+            let new_files = rewrite_group(
+                self.metadata(),
+                group,
+                &delete_files,
+                self.object_store(),
+                branch,
+                options,
+            )
+            .await?;
+            let old_files = group
+                .files
+                .iter()
+                .map(|entry| entry.data_file().clone())
+                .collect::<Vec<_>>();
+            if options.partial_progress {
+                self.new_transaction(branch)
+                    .validate_from_snapshot(snapshot_id)
+                    .rewrite_files(old_files, new_files)
+                    .commit()
+                    .await?;
+            } else {
+                files_to_delete.extend(old_files);
+                files.extend(new_files);
+            }
+        }
+
+        if !files_to_delete.is_empty() {
+            self.new_transaction(branch)
+                .validate_from_snapshot(snapshot_id)
+                .rewrite_files(files_to_delete, files)
+                .commit()
+                .await?;
+        }
+
+        Ok(groups)
+    }
+}
+
+/// Read the rows of the rewrite group and write them to new data files
+async fn rewrite_group(
+    metadata: &TableMetadata,
+    group: &RewriteGroup,
+    delete_files: &[ManifestEntry],
+    object_store: Arc<dyn ObjectStore>,
+    branch: Option<&str>,
+    options: &CompactionOptions,
+) -> Result<Vec<DataFile>, Error> {
+    let delete_files = delete_files
+        .iter()
+        .filter(|delete| group.files.iter().any(|entry| applies_to(delete, entry)))
+        .cloned();
+
+    let batches = read(
+        group.files.iter().cloned().chain(delete_files),
+        object_store.clone(),
+    )
+    .await
+    .map_err(ArrowError::from);
+
+    let files = match options.strategy {
+        CompactionStrategy::BinPack => {
+            write_parquet_partitioned_with_target_size(
+                metadata,
+                batches,
+                object_store,
+                branch,
+                options.target_file_size as usize,
+            )
+            .await?
+        }
+        CompactionStrategy::Sort => {
+            let batches: Vec<RecordBatch> = batches.try_collect().await?;
+            let batches = match batches.first() {
+                Some(first) => vec![sort_batch(
+                    metadata,
+                    concat_batches(&first.schema(), &batches)?,
+                    branch,
+                )?],
+                None => Vec::new(),
+            };
+            write_parquet_partitioned_with_target_size(
+                metadata,
+                stream::iter(batches.into_iter().map(Ok)),
+                object_store,
+                branch,
+                options.target_file_size as usize,
+            )
+            .await?
+        }
+    };
+    Ok(files)
+}
+
+/// Resolve the fields of the default sort order of the table to their source columns
+fn sort_fields<'a>(
+    metadata: &'a TableMetadata,
+    branch: Option<&str>,
+) -> Result<Vec<(&'a StructField, &'a SortField)>, Error> {
+    let schema = metadata.current_schema(branch)?;
+    let sort_order = metadata
+        .sort_orders
+        .get(&metadata.default_sort_order_id)
+        .ok_or(Error::NotFound(
+            "Sort order".to_owned(),
+            metadata.default_sort_order_id.to_string(),
+        ))?;
+    if sort_order.fields.is_empty() {
+        return Err(Error::NotFound(
+            "Sort order".to_owned(),
+            "fields".to_owned(),
+        ));
+    }
+
+    sort_order
+        .fields
+        .iter()
+        .map(|field| {
+            let source = schema.get(field.source_id as usize).ok_or(Error::NotFound(
+                "Field".to_owned(),
+                field.source_id.to_string(),
+            ))?;
+            Ok((source, field))
+        })
+        .collect()
+}
+
+/// Sort the record batch by the default sort order of the table
+fn sort_batch(
+    metadata: &TableMetadata,
+    batch: RecordBatch,
+    branch: Option<&str>,
+) -> Result<RecordBatch, Error> {
+    let sort_columns = sort_fields(metadata, branch)?
+        .into_iter()
+        .map(|(source, field)| {
+            let column = batch
+                .column_by_name(&source.name)
+                .ok_or(Error::NotFound("Column".to_owned(), source.name.clone()))?;
+            Ok(SortColumn {
+                values: transform_arrow(column.clone(), &field.transform, &source.field_type)?,
+                options: Some(SortOptions {
+                    descending: matches!(field.direction, SortDirection::Descending),
+                    nulls_first: matches!(field.null_order, NullOrder::First),
+                }),
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let indices = lexsort_to_indices(&sort_columns, None)?;
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|column| take(column, &indices, None))
+        .collect::<Result<Vec<_>, ArrowError>>()?;
+    Ok(RecordBatch::try_new(batch.schema(), columns)?)
+}
+
+fn file_size(entry: &ManifestEntry) -> u64 {
+    *entry.data_file().file_size_in_bytes() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use iceberg_rust_spec::spec::{
+        manifest::{Content, DataFile, FileFormat, ManifestEntry, Status},
+        table_metadata::FormatVersion,
+        values::{Struct, Value},
+    };
+
+    use super::{plan_rewrite_groups, CompactionOptions, CompactionStrategy};
+
+    fn entry(size: i64, partition: i32) -> ManifestEntry {
+        ManifestEntry::builder()
+            .with_format_version(FormatVersion::V2)
+            .with_status(Status::Added)
+            .with_data_file(
+                DataFile::builder()
+                    .with_content(Content::Data)
+                    .with_file_path(format!("/test/{}-{}.parquet", partition, size))
+                    .with_file_format(FileFormat::Parquet)
+                    .with_partition(Struct::from_iter(vec![(
+                        "day".to_owned(),
+                        Some(Value::Int(partition)),
+                    )]))
+                    .with_record_count(1)
+                    .with_file_size_in_bytes(size)
+                    .with_column_sizes(None)
+                    .with_value_counts(None)
+                    .with_null_value_counts(None)
+                    .with_nan_value_counts(None)
+                    .with_distinct_counts(None)
+                    .with_lower_bounds(None)
+                    .with_upper_bounds(None)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_plan_rewrite_groups() {
+        let options = CompactionOptions::builder()
+            .with_target_file_size(100)
+            .with_min_input_files(2)
+            .with_max_group_size(150)
+            .build()
+            .unwrap();
+
+        let entries = vec![
+            entry(10, 1),
+            entry(20, 1),
+            entry(60, 1),
+            entry(70, 1),
+            entry(90, 1),
+            entry(30, 2),
+            entry(300, 3),
+        ];
+
+        let mut groups = plan_rewrite_groups(entries, &options);
+        groups.sort_by_key(|group| group.size());
+
+        let sizes = groups
+            .iter()
+            .map(|group| {
+                group
+                    .files
+                    .iter()
+                    .map(|entry| *entry.data_file().file_size_in_bytes())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // Files within the target range are kept, small files that don't fill a group are left for a later compaction
+        assert_eq!(sizes, vec![vec![70, 60, 20], vec![300]]);
+    }
+
+    #[test]
+    fn test_plan_sort_groups() {
+        let options = CompactionOptions::builder()
+            .with_target_file_size(100)
+            .with_min_input_files(2)
+            .with_strategy(CompactionStrategy::Sort)
+            .build()
+            .unwrap();
+
+        let entries = (0..10).map(|_| entry(40, 1));
+
+        // The rows of a group are sorted in memory, groups are limited to twice the target size
+        let groups = plan_rewrite_groups(entries, &options);
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|group| group.size() <= 200));
+    }
+}
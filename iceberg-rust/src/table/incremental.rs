@@ -0,0 +1,113 @@
+/*!
+ * Incremental scans that return the data files appended to a table between two snapshots.
+*/
+
+use iceberg_rust_spec::spec::{
+    manifest::ManifestEntry,
+    snapshot::{Operation, Snapshot},
+    table_metadata::TableMetadata,
+};
+
+use crate::error::Error;
+
+use super::{ancestors, snapshot_changes, Table};
+
+/// Start of the snapshot range of an incremental scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartSnapshot {
+    /// Files added by the start snapshot are not part of the scan
+    Exclusive(i64),
+    /// Files added by the start snapshot are part of the scan
+    Inclusive(i64),
+}
+
+/// Determines how an incremental append scan handles snapshots that are not appends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonAppendSnapshots {
+    /// Fail if the range contains overwrite or delete snapshots
+    #[default]
+    Fail,
+    /// Ignore the changes of overwrite and delete snapshots
+    Skip,
+}
+
+impl Table {
+    /// Get the data files added by the append snapshots between the start and the end snapshot. The end snapshot defaults to the current snapshot,
+    /// without a start snapshot the range begins with the first snapshot of the table. The start snapshot has to be an ancestor of the end snapshot.
+    /// Replace snapshots don't change the table data and are always ignored. The files are ordered from the oldest to the newest snapshot.
+    pub async fn incremental_append_files(
+        &self,
+        start: Option<StartSnapshot>,
+        end: Option<i64>,
+        non_append: NonAppendSnapshots,
+    ) -> Result<Vec<ManifestEntry>, Error> {
+        let metadata = self.metadata();
+        let mut files = Vec::new();
+        for snapshot in snapshot_range(metadata, start, end)? {
+            match snapshot.summary().operation {
+                Operation::Append => {
+                    let (added, _) =
+                        snapshot_changes(snapshot, metadata, self.object_store()).await?;
+                    files.extend(added);
+                }
+                Operation::Replace => (),
+                Operation::Overwrite | Operation::Delete => {
+                    if non_append == NonAppendSnapshots::Fail {
+                        return Err(Error::NotSupported(format!(
+                            "Incremental append scan over {:?} snapshot {}",
+                            snapshot.summary().operation,
+                            snapshot.snapshot_id()
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Get the snapshots between the start and the end snapshot, ordered from the oldest to the newest snapshot.
+/// The end snapshot defaults to the current snapshot of the main branch. The start snapshot has to be an ancestor of the end snapshot.
+pub(crate) fn snapshot_range(
+    metadata: &TableMetadata,
+    start: Option<StartSnapshot>,
+    end: Option<i64>,
+) -> Result<Vec<&Snapshot>, Error> {
+    let end_snapshot_id = match end {
+        Some(snapshot_id) => snapshot_id,
+        None => match metadata.current_snapshot(None)? {
+            Some(snapshot) => *snapshot.snapshot_id(),
+            None => return Ok(vec![]),
+        },
+    };
+    if !metadata.snapshots.contains_key(&end_snapshot_id) {
+        return Err(Error::NotFound(
+            "Snapshot".to_owned(),
+            end_snapshot_id.to_string(),
+        ));
+    }
+
+    let start_snapshot_id = start.map(|start| match start {
+        StartSnapshot::Exclusive(id) | StartSnapshot::Inclusive(id) => id,
+    });
+    let mut snapshots = Vec::new();
+    let mut found_start = start_snapshot_id.is_none();
+    for snapshot in ancestors(metadata, end_snapshot_id) {
+        if Some(*snapshot.snapshot_id()) == start_snapshot_id {
+            found_start = true;
+            if matches!(start, Some(StartSnapshot::Inclusive(_))) {
+                snapshots.push(snapshot);
+            }
+            break;
+        }
+        snapshots.push(snapshot);
+    }
+    if let (false, Some(start_snapshot_id)) = (found_start, start_snapshot_id) {
+        return Err(Error::InvalidFormat(format!(
+            "Snapshot {} is not an ancestor of {}",
+            start_snapshot_id, end_snapshot_id
+        )));
+    }
+    snapshots.reverse();
+    Ok(snapshots)
+}
@@ -12,7 +12,7 @@ use apache_avro::{
     types::Value as AvroValue, Reader as AvroReader, Schema as AvroSchema, Writer as AvroWriter,
 };
 use iceberg_rust_spec::{
-    manifest::{ManifestEntry, ManifestEntryV1, ManifestEntryV2},
+    manifest::{ManifestEntry, ManifestEntryV1, ManifestEntryV2, Status},
     manifest_list::{self, FieldSummary, ManifestListEntry},
     partition::{PartitionField, PartitionSpec},
     schema::{Schema, SchemaV1, SchemaV2},
@@ -116,6 +116,7 @@ impl<'schema, 'metadata> ManifestWriter<'schema, 'metadata> {
         snapshot_id: i64,
         schema: &'schema AvroSchema,
         table_metadata: &'metadata TableMetadata,
+        content: manifest_list::Content,
         branch: Option<&str>,
     ) -> Result<Self, Error> {
         let mut writer = AvroWriter::new(schema, Vec::new());
@@ -155,16 +156,16 @@ impl<'schema, 'metadata> ManifestWriter<'schema, 'metadata> {
             serde_json::to_string(&table_metadata.default_partition_spec()?.spec_id())?,
         )?;
 
-        writer.add_user_metadata("content".to_string(), "data")?;
+        writer.add_user_metadata("content".to_string(), content_metadata(&content))?;
 
         let manifest = ManifestListEntry {
             format_version: table_metadata.format_version.clone(),
             manifest_path: manifest_location.to_owned(),
             manifest_length: 0,
             partition_spec_id: table_metadata.default_spec_id,
-            content: manifest_list::Content::Data,
-            sequence_number: table_metadata.last_sequence_number,
-            min_sequence_number: 0,
+            content,
+            sequence_number: table_metadata.last_sequence_number + 1,
+            min_sequence_number: table_metadata.last_sequence_number + 1,
             added_snapshot_id: snapshot_id,
             added_files_count: Some(0),
             existing_files_count: Some(0),
@@ -183,10 +184,13 @@ impl<'schema, 'metadata> ManifestWriter<'schema, 'metadata> {
         })
     }
 
-    /// Create an manifest writer from an existing manifest
+    /// Create an manifest writer from an existing manifest. The entries of the existing manifest are copied to a new manifest file
+    /// at `manifest_location` that is added by the snapshot `snapshot_id`, the existing manifest file isn't changed.
     pub fn from_existing(
         bytes: &[u8],
-        manifest: ManifestListEntry,
+        mut manifest: ManifestListEntry,
+        manifest_location: &str,
+        snapshot_id: i64,
         schema: &'schema AvroSchema,
         table_metadata: &'metadata TableMetadata,
         branch: Option<&str>,
@@ -230,10 +234,15 @@ impl<'schema, 'metadata> ManifestWriter<'schema, 'metadata> {
             serde_json::to_string(&table_metadata.default_partition_spec()?.spec_id())?,
         )?;
 
-        writer.add_user_metadata("content".to_string(), "data")?;
+        writer.add_user_metadata("content".to_string(), content_metadata(&manifest.content))?;
 
         writer.extend(manifest_reader.filter_map(Result::ok))?;
 
+        manifest.manifest_path = manifest_location.to_owned();
+        manifest.manifest_length = 0;
+        manifest.added_snapshot_id = snapshot_id;
+        manifest.sequence_number = table_metadata.last_sequence_number + 1;
+
         Ok(ManifestWriter {
             manifest,
             writer,
@@ -243,8 +252,6 @@ impl<'schema, 'metadata> ManifestWriter<'schema, 'metadata> {
 
     /// Add an manifest entry to the manifest
     pub fn append(&mut self, manifest_entry: ManifestEntry) -> Result<(), Error> {
-        let mut added_rows_count = 0;
-
         if self.manifest.partitions.is_none() {
             self.manifest.partitions = Some(
                 self.table_metadata
@@ -261,22 +268,42 @@ impl<'schema, 'metadata> ManifestWriter<'schema, 'metadata> {
             );
         }
 
-        added_rows_count += manifest_entry.data_file().record_count();
+        let record_count = *manifest_entry.data_file().record_count();
+        if let Some(sequence_number) = manifest_entry.sequence_number() {
+            self.manifest.min_sequence_number =
+                self.manifest.min_sequence_number.min(*sequence_number);
+        }
         update_partitions(
             self.manifest.partitions.as_mut().unwrap(),
             manifest_entry.data_file().partition(),
             self.table_metadata.default_partition_spec()?.fields(),
         )?;
 
+        let status = manifest_entry.status().clone();
+
         self.writer.append_ser(manifest_entry)?;
 
-        self.manifest.added_files_count = match self.manifest.added_files_count {
-            Some(count) => Some(count + 1),
+        let (files_count, rows_count) = match status {
+            Status::Added => (
+                &mut self.manifest.added_files_count,
+                &mut self.manifest.added_rows_count,
+            ),
+            Status::Existing => (
+                &mut self.manifest.existing_files_count,
+                &mut self.manifest.existing_rows_count,
+            ),
+            Status::Deleted => (
+                &mut self.manifest.deleted_files_count,
+                &mut self.manifest.deleted_rows_count,
+            ),
+        };
+        *files_count = match files_count {
+            Some(count) => Some(*count + 1),
             None => Some(1),
         };
-        self.manifest.added_rows_count = match self.manifest.added_rows_count {
-            Some(count) => Some(count + added_rows_count),
-            None => Some(added_rows_count),
+        *rows_count = match rows_count {
+            Some(count) => Some(*count + record_count),
+            None => Some(record_count),
         };
 
         Ok(())
@@ -303,6 +330,13 @@ impl<'schema, 'metadata> ManifestWriter<'schema, 'metadata> {
     }
 }
 
+fn content_metadata(content: &manifest_list::Content) -> &'static str {
+    match content {
+        manifest_list::Content::Data => "data",
+        manifest_list::Content::Deletes => "deletes",
+    }
+}
+
 #[allow(clippy::type_complexity)]
 /// Convert avro value to ManifestEntry based on the format version of the table.
 pub fn avro_value_to_manifest_entry(
@@ -2,16 +2,23 @@
 Defining the [Table] struct that represents an iceberg table.
 */
 
-use std::{io::Cursor, iter::repeat, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Cursor,
+    iter::repeat,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use manifest::ManifestReader;
 use object_store::{path::Path, ObjectStore};
 
-use futures::{stream, StreamExt, TryFutureExt, TryStreamExt};
+use futures::{future, stream, StreamExt, TryFutureExt, TryStreamExt};
 use iceberg_rust_spec::spec::{
-    manifest::{Content, ManifestEntry},
+    manifest::{Content, ManifestEntry, Status},
     manifest_list::ManifestListEntry,
     schema::Schema,
+    snapshot::Snapshot,
     table_metadata::TableMetadata,
 };
 use iceberg_rust_spec::util::{self};
@@ -22,9 +29,17 @@ use crate::{
     table::transaction::TableTransaction,
 };
 
+pub mod changelog;
+pub mod compaction;
+pub mod incremental;
 pub mod manifest;
+pub mod scan;
+pub mod split;
 pub mod transaction;
 
+/// Files younger than three days are not considered orphans by default, because they might belong to a write that is still in progress
+static ORPHAN_FILE_AGE_MS_DEFAULT: i64 = 3 * 24 * 60 * 60 * 1000;
+
 #[derive(Debug)]
 /// Iceberg table
 pub struct Table {
@@ -83,6 +98,17 @@ impl Table {
     pub fn into_metadata(self) -> TableMetadata {
         self.metadata
     }
+    #[inline]
+    /// Get the snapshot that was the current snapshot of the branch at the timestamp in milliseconds. Defaults to main.
+    pub fn snapshot_as_of(
+        &self,
+        timestamp_ms: i64,
+        branch: Option<&str>,
+    ) -> Result<Option<&Snapshot>, Error> {
+        self.metadata
+            .snapshot_as_of(timestamp_ms, branch)
+            .map_err(Error::from)
+    }
     /// Get list of current manifest files within an optional snapshot range. The start snapshot is excluded from the range.
     pub async fn manifests(
         &self,
@@ -130,7 +156,9 @@ impl Table {
                 .map_err(Error::from),
         }
     }
-    /// Get list of datafiles corresponding to the given manifest files
+    /// Get list of datafiles corresponding to the given manifest files.
+    /// Entries with status deleted are skipped. They only record that a file was removed by the snapshot of the manifest,
+    /// for example by an overwrite, and aren't part of the table anymore. Use the changelog to inspect removed files.
     #[inline]
     pub async fn datafiles(
         &self,
@@ -151,12 +179,118 @@ impl Table {
             .iter()
             .any(|entry| !matches!(entry.data_file().content(), Content::Data)))
     }
+    /// Remove files under the table location that are not referenced by any snapshot of the table and that are older than `older_than`,
+    /// in milliseconds since the epoch. Defaults to three days ago. Statistics files and the metadata files of the metadata log are kept,
+    /// other metadata files are removed unless they could be the current metadata file of the table.
+    /// Returns the paths of the orphan files. In a dry run the files are only returned and not deleted.
+    pub async fn remove_orphan_files(
+        &self,
+        older_than: Option<i64>,
+        dry_run: bool,
+    ) -> Result<Vec<String>, Error> {
+        let older_than = older_than.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as i64
+                - ORPHAN_FILE_AGE_MS_DEFAULT
+        });
+        let object_store = self.object_store();
+        let metadata = self.metadata();
+
+        let snapshots = metadata.snapshots.values().collect::<Vec<_>>();
+        let manifests = snapshot_manifests(&snapshots, metadata, object_store.clone())
+            .await?
+            .into_values()
+            .collect::<Vec<_>>();
+        let datafiles = datafiles(object_store.clone(), &manifests, None).await?;
+
+        let reachable = datafiles
+            .iter()
+            .map(|entry| entry.data_file().file_path().as_str())
+            .chain(
+                manifests
+                    .iter()
+                    .map(|manifest| manifest.manifest_path.as_str()),
+            )
+            .chain(
+                snapshots
+                    .iter()
+                    .map(|snapshot| snapshot.manifest_list().as_str()),
+            )
+            .chain(
+                metadata
+                    .statistics
+                    .iter()
+                    .map(|statistics| statistics.statistics_path.as_str()),
+            )
+            .chain(
+                metadata
+                    .partition_statistics
+                    .iter()
+                    .map(|statistics| statistics.statistics_path.as_str()),
+            )
+            .chain(
+                metadata
+                    .metadata_log
+                    .iter()
+                    .map(|log| log.metadata_file.as_str()),
+            )
+            .map(|path| Path::from(util::strip_prefix(path)))
+            .collect::<HashSet<_>>();
+
+        let location = Path::from(util::strip_prefix(&metadata.location));
+        let version_hint = location.child("metadata").child("version-hint.text");
+        let orphans = object_store
+            .list(Some(&location))
+            .try_filter(|file| {
+                future::ready(
+                    file.last_modified.timestamp_millis() < older_than
+                        && !reachable.contains(&file.location)
+                        && file.location != version_hint
+                        && !is_current_metadata_file(&file.location, metadata),
+                )
+            })
+            .map_ok(|file| file.location)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        if !dry_run {
+            stream::iter(orphans.iter())
+                .map(Ok::<_, Error>)
+                .try_for_each_concurrent(None, |path| {
+                    let object_store = object_store.clone();
+                    async move {
+                        object_store.delete(path).await?;
+                        Ok(())
+                    }
+                })
+                .await?;
+        }
+
+        Ok(orphans.into_iter().map(|path| path.to_string()).collect())
+    }
     /// Create a new transaction for this table
     pub fn new_transaction(&mut self, branch: Option<&str>) -> TableTransaction {
         TableTransaction::new(self, branch)
     }
 }
 
+/// The location of the current metadata file is only known to the catalog. Metadata files are named after the last sequence number of
+/// the table, so files with at least the current sequence number are either the current metadata file or belong to concurrent commits.
+/// Metadata files that don't follow the naming scheme are always kept.
+fn is_current_metadata_file(path: &Path, metadata: &TableMetadata) -> bool {
+    let Some(name) = path
+        .filename()
+        .and_then(|name| name.strip_suffix(".metadata.json"))
+    else {
+        return false;
+    };
+    name.split_once('-')
+        .and_then(|(version, _)| version.parse::<i64>().ok())
+        .map_or(true, |version| version >= metadata.last_sequence_number)
+}
+
 async fn datafiles(
     object_store: Arc<dyn ObjectStore>,
     manifests: &[ManifestListEntry],
@@ -190,10 +324,18 @@ async fn datafiles(
                         .await?,
                 ));
                 let reader = ManifestReader::new(bytes)?;
-                Ok(stream::iter(reader))
+                // Entries inherit null snapshot ids and sequence numbers from their manifest
+                Ok(stream::iter(reader.map(move |entry| {
+                    entry.map(|mut entry| {
+                        entry.inherit(file);
+                        entry
+                    })
+                })))
             }
         })
         .flat_map(|reader| reader.try_flatten_stream())
+        // Deleted entries only track the removal of a file in the snapshot that removed it
+        .try_filter(|entry| future::ready(!matches!(entry.status(), Status::Deleted)))
         .try_collect()
         .await
         .map_err(Error::from)
@@ -254,6 +396,88 @@ pub(crate) async fn delete_files(
     Ok(())
 }
 
+/// Delete the manifest lists, manifests and data files of snapshots that were removed from the metadata
+/// and that are not referenced by any of the remaining snapshots
+pub(crate) async fn delete_unreachable_files(
+    old_metadata: &TableMetadata,
+    metadata: &TableMetadata,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<(), Error> {
+    let (removed, retained): (Vec<_>, Vec<_>) = old_metadata
+        .snapshots
+        .values()
+        .partition(|snapshot| !metadata.snapshots.contains_key(snapshot.snapshot_id()));
+    if removed.is_empty() {
+        return Ok(());
+    }
+
+    let retained_manifests = snapshot_manifests(&retained, metadata, object_store.clone()).await?;
+    let removed_manifests = snapshot_manifests(&removed, old_metadata, object_store.clone())
+        .await?
+        .into_iter()
+        .filter(|(path, _)| !retained_manifests.contains_key(path))
+        .map(|(_, manifest)| manifest)
+        .collect::<Vec<_>>();
+
+    let retained_datafiles = datafiles(
+        object_store.clone(),
+        &retained_manifests.into_values().collect::<Vec<_>>(),
+        None,
+    )
+    .await?
+    .into_iter()
+    .map(|entry| entry.data_file().file_path().to_owned())
+    .collect::<HashSet<_>>();
+    let removed_datafiles = datafiles(object_store.clone(), &removed_manifests, None)
+        .await?
+        .into_iter()
+        .map(|entry| entry.data_file().file_path().to_owned())
+        .filter(|path| !retained_datafiles.contains(path))
+        .collect::<HashSet<_>>();
+
+    let paths = removed_datafiles
+        .into_iter()
+        .chain(
+            removed_manifests
+                .into_iter()
+                .map(|manifest| manifest.manifest_path),
+        )
+        .chain(
+            removed
+                .iter()
+                .map(|snapshot| snapshot.manifest_list().to_owned()),
+        );
+
+    stream::iter(paths)
+        .map(Ok::<_, Error>)
+        .try_for_each_concurrent(None, |path| {
+            let object_store = object_store.clone();
+            async move {
+                object_store
+                    .delete(&util::strip_prefix(&path).into())
+                    .await?;
+                Ok(())
+            }
+        })
+        .await
+}
+
+/// Get the manifests of the snapshots by their path
+async fn snapshot_manifests(
+    snapshots: &[&Snapshot],
+    metadata: &TableMetadata,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<HashMap<String, ManifestListEntry>, Error> {
+    let mut manifests = HashMap::new();
+    for snapshot in snapshots {
+        for manifest in snapshot.manifests(metadata, object_store.clone()).await? {
+            let manifest = manifest?;
+            manifests.insert(manifest.manifest_path.clone(), manifest);
+        }
+    }
+    Ok(manifests)
+}
+
 #[inline]
 // Filter manifest files according to predicate. Returns Some(&ManifestFile) of the predicate is true and None if it is false.
 fn filter_manifest(
@@ -265,3 +489,52 @@ fn filter_manifest(
         None
     }
 }
+
+/// Iterate over a snapshot and its ancestors, starting with the snapshot itself
+pub(crate) fn ancestors(
+    metadata: &TableMetadata,
+    snapshot_id: i64,
+) -> impl Iterator<Item = &Snapshot> {
+    std::iter::successors(metadata.snapshots.get(&snapshot_id), |snapshot| {
+        snapshot
+            .parent_snapshot_id()
+            .as_ref()
+            .and_then(|id| metadata.snapshots.get(id))
+    })
+}
+
+/// Get the manifest entries of the files that were added and the files that were removed by a snapshot
+pub(crate) async fn snapshot_changes(
+    snapshot: &Snapshot,
+    metadata: &TableMetadata,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<(Vec<ManifestEntry>, Vec<ManifestEntry>), Error> {
+    let snapshot_id = *snapshot.snapshot_id();
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for manifest in snapshot.manifests(metadata, object_store.clone()).await? {
+        let manifest = manifest?;
+        // Appends can add entries to manifests of previous snapshots, so the entries are selected by their snapshot id
+        if manifest.added_files_count == Some(0) && manifest.deleted_files_count == Some(0) {
+            continue;
+        }
+        let bytes = object_store
+            .get(&util::strip_prefix(&manifest.manifest_path).into())
+            .await?
+            .bytes()
+            .await?;
+        for entry in ManifestReader::new(bytes.as_ref())? {
+            let mut entry = entry?;
+            entry.inherit(&manifest);
+            if *entry.snapshot_id() != Some(snapshot_id) {
+                continue;
+            }
+            match entry.status() {
+                Status::Added => added.push(entry),
+                Status::Deleted => removed.push(entry),
+                Status::Existing => (),
+            }
+        }
+    }
+    Ok((added, removed))
+}
@@ -0,0 +1,295 @@
+/*!
+ * Scan planning that selects the data files of a table snapshot that match a filter.
+*/
+
+use std::{cmp::Ordering, slice};
+
+use futures::future;
+
+use iceberg_rust_spec::spec::{
+    manifest::{Content, ManifestEntry},
+    manifest_list::{FieldSummary, ManifestListEntry},
+    name_mapping::NameMapping,
+    partition::{PartitionSpec, Transform},
+    schema::Schema,
+    snapshot::Snapshot,
+};
+
+use crate::{
+    arrow::{delete::applies_to, projection::name_mapping},
+    error::Error,
+    table::transaction::overwrite::{compare, filter_match, predicate_match, FilterMatch},
+};
+
+pub use crate::table::transaction::overwrite::Predicate;
+
+use super::{datafiles, Table};
+
+/// Data file of a scan together with the delete files that apply to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileScanTask {
+    /// Data file to read
+    pub data_file: ManifestEntry,
+    /// Position and equality delete files that apply to the data file
+    pub deletes: Vec<ManifestEntry>,
+    /// Predicates of the scan filter that are not guaranteed by the column metrics of the data file and have to be evaluated for every row
+    pub residual: Vec<Predicate>,
+    /// Partition spec the data file was written with
+    pub spec: PartitionSpec,
+    /// Selected columns, all columns if None
+    pub columns: Option<Vec<String>>,
+    /// Schema of the snapshot that the columns of the data file are projected onto
+    pub schema: Schema,
+    /// Name mapping for data files that were written without field ids
+    pub name_mapping: Option<NameMapping>,
+}
+
+/// Builder for a scan of a table snapshot
+#[derive(Debug)]
+pub struct TableScan<'table> {
+    table: &'table Table,
+    columns: Option<Vec<String>>,
+    filter: Vec<Predicate>,
+    snapshot_id: Option<i64>,
+    reference: Option<String>,
+    timestamp_ms: Option<i64>,
+    case_sensitive: bool,
+}
+
+impl<'table> TableScan<'table> {
+    pub(crate) fn new(table: &'table Table) -> Self {
+        TableScan {
+            table,
+            columns: None,
+            filter: Vec::new(),
+            snapshot_id: None,
+            reference: None,
+            timestamp_ms: None,
+            case_sensitive: true,
+        }
+    }
+    /// Only read the given columns. Defaults to all columns.
+    pub fn select(mut self, columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.columns = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+    /// Only read rows that satisfy the predicate. Multiple predicates are combined as a conjunction.
+    pub fn filter(mut self, predicate: Predicate) -> Self {
+        self.filter.push(predicate);
+        self
+    }
+    /// Read the snapshot with the given id
+    pub fn snapshot_id(mut self, snapshot_id: i64) -> Self {
+        self.snapshot_id = Some(snapshot_id);
+        self
+    }
+    /// Read the current snapshot of a branch or a tag. Defaults to main.
+    pub fn use_ref(mut self, reference: &str) -> Self {
+        self.reference = Some(reference.to_owned());
+        self
+    }
+    /// Read the snapshot that was current at the timestamp in milliseconds. Can be combined with a branch.
+    pub fn as_of_timestamp(mut self, timestamp_ms: i64) -> Self {
+        self.timestamp_ms = Some(timestamp_ms);
+        self
+    }
+    /// Whether column names of the selection and the filter are matched case sensitively. Defaults to true.
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+    /// Get the snapshot the scan reads. Returns None if the table has no snapshot.
+    pub fn snapshot(&self) -> Result<Option<&'table Snapshot>, Error> {
+        let metadata = self.table.metadata();
+        match (self.snapshot_id, self.timestamp_ms) {
+            (Some(_), Some(_)) => Err(Error::InvalidFormat(
+                "Scan can select a snapshot either by id or by timestamp".to_owned(),
+            )),
+            (Some(snapshot_id), None) => {
+                metadata
+                    .snapshots
+                    .get(&snapshot_id)
+                    .map(Some)
+                    .ok_or(Error::NotFound(
+                        "Snapshot".to_owned(),
+                        snapshot_id.to_string(),
+                    ))
+            }
+            (None, Some(timestamp_ms)) => self
+                .table
+                .snapshot_as_of(timestamp_ms, self.reference.as_deref()),
+            (None, None) => metadata
+                .current_snapshot(self.reference.as_deref())
+                .map_err(Error::from),
+        }
+    }
+    /// Plan the files of the snapshot that might contain rows matching the filter. Manifests are pruned with the partition summaries
+    /// of identity partition fields and data files are pruned with their column metrics.
+    pub async fn plan_files(&self) -> Result<Vec<FileScanTask>, Error> {
+        let Some(snapshot) = self.snapshot()? else {
+            return Ok(vec![]);
+        };
+        let metadata = self.table.metadata();
+        let object_store = self.table.object_store();
+        let schema = metadata.schema(*snapshot.snapshot_id())?;
+        let name_mapping = name_mapping(&metadata.properties)?;
+
+        let columns = self
+            .columns
+            .as_ref()
+            .map(|columns| {
+                columns
+                    .iter()
+                    .map(|column| self.resolve(column, schema))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+        let filter = self
+            .filter
+            .iter()
+            .map(|predicate| {
+                let column = self.resolve(predicate.column(), schema)?;
+                Ok(predicate.clone().with_column(column))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let manifests = snapshot
+            .manifests(metadata, object_store.clone())
+            .await?
+            .collect::<Result<Vec<_>, _>>()?;
+        // Entries don't store their partition spec, so the manifests are read one by one
+        let entries = future::try_join_all(manifests.iter().map(|manifest| {
+            let object_store = object_store.clone();
+            let filter = &filter;
+            async move {
+                let spec = metadata
+                    .partition_specs
+                    .get(&manifest.partition_spec_id)
+                    .ok_or(Error::NotFound(
+                        "Partition spec".to_owned(),
+                        manifest.partition_spec_id.to_string(),
+                    ))?;
+                let entries = if manifest_may_match(filter, manifest, spec, schema) {
+                    datafiles(object_store, slice::from_ref(manifest), None).await?
+                } else {
+                    Vec::new()
+                };
+                Ok::<_, Error>(entries.into_iter().map(move |entry| (entry, spec)))
+            }
+        }))
+        .await?;
+
+        let (data_files, delete_files): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .flatten()
+            .partition(|(entry, _)| matches!(entry.data_file().content(), Content::Data));
+        let delete_files = delete_files
+            .into_iter()
+            .map(|(entry, _)| entry)
+            .collect::<Vec<_>>();
+
+        let mut tasks = Vec::new();
+        for (data_file, spec) in data_files {
+            if filter_match(&filter, data_file.data_file(), schema)? == FilterMatch::None {
+                continue;
+            }
+            let mut residual = Vec::new();
+            for predicate in &filter {
+                if predicate_match(predicate, data_file.data_file(), schema)? != FilterMatch::All {
+                    residual.push(predicate.clone());
+                }
+            }
+            let deletes = delete_files
+                .iter()
+                .filter(|delete| applies_to(delete, &data_file))
+                .cloned()
+                .collect();
+            tasks.push(FileScanTask {
+                data_file,
+                deletes,
+                residual,
+                spec: spec.clone(),
+                columns: columns.clone(),
+                schema: schema.clone(),
+                name_mapping: name_mapping.clone(),
+            });
+        }
+        Ok(tasks)
+    }
+
+    /// Get the name of the schema field that matches the column name
+    fn resolve(&self, column: &str, schema: &Schema) -> Result<String, Error> {
+        schema
+            .fields()
+            .iter()
+            .find(|field| {
+                if self.case_sensitive {
+                    field.name == column
+                } else {
+                    field.name.eq_ignore_ascii_case(column)
+                }
+            })
+            .map(|field| field.name.clone())
+            .ok_or(Error::NotFound("Column".to_owned(), column.to_owned()))
+    }
+}
+
+impl Table {
+    /// Create a scan of the current snapshot of the table
+    pub fn scan(&self) -> TableScan<'_> {
+        TableScan::new(self)
+    }
+}
+
+/// Check whether the manifest might contain files matching the filter, based on the summaries of identity partition fields
+fn manifest_may_match(
+    filter: &[Predicate],
+    manifest: &ManifestListEntry,
+    spec: &PartitionSpec,
+    schema: &Schema,
+) -> bool {
+    let Some(summaries) = &manifest.partitions else {
+        return true;
+    };
+    filter.iter().all(|predicate| {
+        let Some(field) = schema.fields().get_name(predicate.column()) else {
+            return true;
+        };
+        spec.fields()
+            .iter()
+            .zip(summaries)
+            .filter(|(partition_field, _)| {
+                *partition_field.source_id() == field.id
+                    && matches!(partition_field.transform(), Transform::Identity)
+            })
+            .all(|(_, summary)| summary_may_match(predicate, summary))
+    })
+}
+
+/// Check whether a partition field with the summary might contain values matching the predicate
+fn summary_may_match(predicate: &Predicate, summary: &FieldSummary) -> bool {
+    let (value, may_match): (_, fn(Ordering, Ordering) -> bool) = match predicate {
+        Predicate::In(name, values) => {
+            return values.iter().any(|value| {
+                summary_may_match(&Predicate::Eq(name.clone(), value.clone()), summary)
+            })
+        }
+        Predicate::IsNull(_) => return summary.contains_null,
+        Predicate::NotNull(_) => return true,
+        // The orderings compare the value to the lower and the upper bound of the partition field
+        Predicate::Eq(_, value) => (value, |lower, upper| {
+            lower != Ordering::Less && upper != Ordering::Greater
+        }),
+        Predicate::Lt(_, value) => (value, |lower, _| lower == Ordering::Greater),
+        Predicate::LtEq(_, value) => (value, |lower, _| lower != Ordering::Less),
+        Predicate::Gt(_, value) => (value, |_, upper| upper == Ordering::Less),
+        Predicate::GtEq(_, value) => (value, |_, upper| upper != Ordering::Greater),
+    };
+    let (Some(lower), Some(upper)) = (&summary.lower_bound, &summary.upper_bound) else {
+        return true;
+    };
+    match (compare(value, lower), compare(value, upper)) {
+        (Some(lower), Some(upper)) => may_match(lower, upper),
+        _ => true,
+    }
+}
@@ -0,0 +1,200 @@
+/*!
+ * Splitting of data files into byte ranges and bin-packing of the splits into scan tasks.
+*/
+
+use std::{collections::HashMap, ops::Range, str::FromStr};
+
+use iceberg_rust_spec::spec::manifest::DataFile;
+
+/// Table property for the target size in bytes of the splits of a scan task
+pub static READ_SPLIT_TARGET_SIZE: &str = "read.split.target-size";
+/// Table property for the estimated cost in bytes of opening a file, used as the minimum weight of a split
+pub static READ_SPLIT_OPEN_FILE_COST: &str = "read.split.open-file-cost";
+/// Table property for the number of scan tasks that are considered when packing a split
+pub static READ_SPLIT_PLANNING_LOOKBACK: &str = "read.split.planning-lookback";
+
+static READ_SPLIT_TARGET_SIZE_DEFAULT: i64 = 128 * 1024 * 1024;
+static READ_SPLIT_OPEN_FILE_COST_DEFAULT: i64 = 4 * 1024 * 1024;
+static READ_SPLIT_PLANNING_LOOKBACK_DEFAULT: usize = 10;
+
+/// Split settings of a scan, read from the table properties
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitConfig {
+    /// Target size in bytes of the splits of a scan task
+    pub target_size: i64,
+    /// Minimum weight in bytes of a split
+    pub open_file_cost: i64,
+    /// Number of open scan tasks a split can be packed into
+    pub lookback: usize,
+}
+
+impl SplitConfig {
+    /// Read the split settings from the table properties
+    pub fn new(properties: &HashMap<String, String>) -> Self {
+        fn property<T: FromStr>(properties: &HashMap<String, String>, key: &str, default: T) -> T {
+            properties
+                .get(key)
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(default)
+        }
+        SplitConfig {
+            target_size: property(
+                properties,
+                READ_SPLIT_TARGET_SIZE,
+                READ_SPLIT_TARGET_SIZE_DEFAULT,
+            )
+            .max(1),
+            open_file_cost: property(
+                properties,
+                READ_SPLIT_OPEN_FILE_COST,
+                READ_SPLIT_OPEN_FILE_COST_DEFAULT,
+            ),
+            lookback: property(
+                properties,
+                READ_SPLIT_PLANNING_LOOKBACK,
+                READ_SPLIT_PLANNING_LOOKBACK_DEFAULT,
+            )
+            .max(1),
+        }
+    }
+
+    /// Cut the data file into byte ranges of about the target size. Ranges start at the split offsets of the file, which are the offsets
+    /// of the parquet row groups. Without valid split offsets the file is cut into ranges of exactly the target size.
+    pub fn split(&self, data_file: &DataFile) -> Vec<Range<i64>> {
+        let file_size = *data_file.file_size_in_bytes();
+        match data_file
+            .split_offsets()
+            .as_ref()
+            .filter(|offsets| valid_offsets(offsets, file_size))
+        {
+            Some(offsets) => {
+                let mut ranges: Vec<Range<i64>> = Vec::new();
+                for (i, start) in offsets.iter().enumerate() {
+                    let end = offsets.get(i + 1).copied().unwrap_or(file_size);
+                    match ranges.last_mut() {
+                        Some(range) if end - range.start <= self.target_size => range.end = end,
+                        _ => ranges.push(*start..end),
+                    }
+                }
+                ranges
+            }
+            None => (0..file_size.max(1))
+                .step_by(self.target_size as usize)
+                .map(|start| start..(start + self.target_size).min(file_size))
+                .collect(),
+        }
+    }
+
+    /// Pack items into bins whose weight is at most the target size. An item is added to the first of the open bins it fits into,
+    /// if none fits a new bin is opened. The oldest bin is closed when more bins than the lookback are open.
+    /// The weight of an item is at least the open file cost.
+    pub fn pack<T>(
+        &self,
+        items: impl IntoIterator<Item = T>,
+        weight: impl Fn(&T) -> i64,
+    ) -> Vec<Vec<T>> {
+        let mut packed = Vec::new();
+        let mut open: Vec<(i64, Vec<T>)> = Vec::new();
+        for item in items {
+            let weight = weight(&item).max(self.open_file_cost);
+            match open
+                .iter_mut()
+                .find(|(bin_weight, _)| bin_weight + weight <= self.target_size)
+            {
+                Some((bin_weight, bin)) => {
+                    *bin_weight += weight;
+                    bin.push(item);
+                }
+                None => {
+                    open.push((weight, vec![item]));
+                    if open.len() > self.lookback {
+                        packed.push(open.remove(0).1);
+                    }
+                }
+            }
+        }
+        packed.extend(open.into_iter().map(|(_, bin)| bin));
+        packed
+    }
+}
+
+/// Split offsets have to be ascending and inside the file
+fn valid_offsets(offsets: &[i64], file_size: i64) -> bool {
+    !offsets.is_empty()
+        && offsets.windows(2).all(|x| x[0] < x[1])
+        && offsets
+            .iter()
+            .all(|offset| *offset >= 0 && *offset < file_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use iceberg_rust_spec::spec::{
+        manifest::{Content, DataFile, FileFormat},
+        values::{Struct, Value},
+    };
+
+    use super::{SplitConfig, READ_SPLIT_OPEN_FILE_COST, READ_SPLIT_TARGET_SIZE};
+
+    fn data_file(file_size: i64, split_offsets: Option<Vec<i64>>) -> DataFile {
+        DataFile::builder()
+            .with_content(Content::Data)
+            .with_file_path("/test/data.parquet".to_owned())
+            .with_file_format(FileFormat::Parquet)
+            .with_partition(Struct::from_iter(Vec::<(String, Option<Value>)>::new()))
+            .with_record_count(1)
+            .with_file_size_in_bytes(file_size)
+            .with_column_sizes(None)
+            .with_value_counts(None)
+            .with_null_value_counts(None)
+            .with_nan_value_counts(None)
+            .with_distinct_counts(None)
+            .with_lower_bounds(None)
+            .with_upper_bounds(None)
+            .with_split_offsets(split_offsets)
+            .build()
+            .unwrap()
+    }
+
+    fn config(target_size: i64, open_file_cost: i64) -> SplitConfig {
+        SplitConfig::new(&HashMap::from_iter(vec![
+            (READ_SPLIT_TARGET_SIZE.to_owned(), target_size.to_string()),
+            (
+                READ_SPLIT_OPEN_FILE_COST.to_owned(),
+                open_file_cost.to_string(),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn test_split_offsets() {
+        let config = config(100, 0);
+        let file = data_file(250, Some(vec![4, 30, 60, 150, 200]));
+        assert_eq!(config.split(&file), vec![4..60, 60..150, 150..250]);
+    }
+
+    #[test]
+    fn test_split_without_offsets() {
+        let config = config(100, 0);
+        assert_eq!(
+            config.split(&data_file(250, None)),
+            vec![0..100, 100..200, 200..250]
+        );
+        // Offsets outside of the file are ignored
+        assert_eq!(
+            config.split(&data_file(250, Some(vec![4, 300]))),
+            vec![0..100, 100..200, 200..250]
+        );
+    }
+
+    #[test]
+    fn test_pack() {
+        let config = config(100, 10);
+        assert_eq!(
+            config.pack(vec![60, 50, 30, 5, 120], |x| *x),
+            vec![vec![60, 30, 5], vec![50], vec![120]]
+        );
+    }
+}
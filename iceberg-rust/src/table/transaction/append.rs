@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 
 use iceberg_rust_spec::{
     manifest::ManifestEntry,
-    manifest_list::{ManifestListEntry, ManifestListReader},
+    manifest_list::{Content, ManifestListEntry, ManifestListReader},
 };
 
 use crate::{
@@ -99,11 +99,16 @@ pub(crate) fn select_manifest_partitioned(
     manifest_list_reader: ManifestListReader<&[u8]>,
     manifest_list_writer: &mut apache_avro::Writer<Vec<u8>>,
     bounding_partition_values: &Rectangle,
-) -> Result<SelectedManifest, Error> {
+) -> Result<Option<SelectedManifest>, Error> {
     let mut selected_state = None;
     let mut file_count_all_entries = 0;
     for manifest_res in manifest_list_reader {
         let manifest = manifest_res?;
+        // New data files are only added to data manifests, delete manifests are kept as they are
+        if matches!(manifest.content, Content::Deletes) {
+            manifest_list_writer.append_ser(manifest)?;
+            continue;
+        }
 
         let mut bounds = summary_to_rectangle(
             manifest
@@ -133,23 +138,26 @@ pub(crate) fn select_manifest_partitioned(
             }
         }
     }
-    selected_state
-        .map(|(_, entry)| SelectedManifest {
-            manifest: entry,
-            file_count_all_entries,
-        })
-        .ok_or(Error::NotFound("Manifest".to_owned(), "file".to_owned()))
+    Ok(selected_state.map(|(_, entry)| SelectedManifest {
+        manifest: entry,
+        file_count_all_entries,
+    }))
 }
 
 /// Select the manifest with the smallest number of rows.
 pub(crate) fn select_manifest_unpartitioned(
     manifest_list_reader: ManifestListReader<&[u8]>,
     manifest_list_writer: &mut apache_avro::Writer<Vec<u8>>,
-) -> Result<SelectedManifest, Error> {
+) -> Result<Option<SelectedManifest>, Error> {
     let mut selected_state = None;
     let mut file_count_all_entries = 0;
     for manifest_res in manifest_list_reader {
         let manifest = manifest_res?;
+        // New data files are only added to data manifests, delete manifests are kept as they are
+        if matches!(manifest.content, Content::Deletes) {
+            manifest_list_writer.append_ser(manifest)?;
+            continue;
+        }
         // TODO: should this also account for existing_rows_count / existing_files_count?
         let row_count = manifest.added_rows_count;
         file_count_all_entries += manifest.added_files_count.unwrap_or(0) as usize;
@@ -174,10 +182,8 @@ pub(crate) fn select_manifest_unpartitioned(
             continue;
         }
     }
-    selected_state
-        .map(|(_, entry)| SelectedManifest {
-            manifest: entry,
-            file_count_all_entries,
-        })
-        .ok_or(Error::NotFound("Manifest".to_owned(), "file".to_owned()))
+    Ok(selected_state.map(|(_, entry)| SelectedManifest {
+        manifest: entry,
+        file_count_all_entries,
+    }))
 }
@@ -0,0 +1,292 @@
+/*!
+ * Detects conflicts between the operations of a transaction and the snapshots that were committed since the transaction started.
+*/
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use iceberg_rust_spec::spec::{
+    manifest::{Content, DataFile, ManifestEntry},
+    schema::Schema,
+    table_metadata::TableMetadata,
+    values::{Struct, Value},
+};
+use object_store::ObjectStore;
+
+use crate::{
+    arrow::delete::{applies_to_partition, DELETE_FILE_PATH_FIELD_ID},
+    error::Error,
+    table::{ancestors, snapshot_changes},
+};
+
+use super::{
+    operation::Operation,
+    overwrite::{filter_match, FilterMatch, OverwriteMode},
+};
+
+/// Determines which concurrent changes conflict with the operations of a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IsolationLevel {
+    /// The transaction fails if data files were added concurrently that could contain rows the transaction would have read,
+    /// or if files the transaction depends on were removed or received new deletes
+    #[default]
+    Serializable,
+    /// Concurrently added data files are allowed, the transaction only fails if files it depends on were removed or received new deletes
+    Snapshot,
+}
+
+/// Files that were added and removed by the snapshots committed to a branch after the starting snapshot of a transaction
+#[derive(Debug, Default)]
+pub(crate) struct ConcurrentChanges {
+    pub(crate) added: Vec<ManifestEntry>,
+    pub(crate) removed: Vec<ManifestEntry>,
+}
+
+/// Collect the changes of the snapshots that were committed to the branch after the starting snapshot.
+/// Fails if the starting snapshot is no longer an ancestor of the branch.
+pub(crate) async fn concurrent_changes(
+    start_snapshot_id: Option<i64>,
+    metadata: &TableMetadata,
+    branch: Option<&str>,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<ConcurrentChanges, Error> {
+    let Some(head) = metadata.current_snapshot(branch)? else {
+        return match start_snapshot_id {
+            None => Ok(ConcurrentChanges::default()),
+            Some(_) => Err(Error::CommitConflict(format!(
+                "Branch {} was removed",
+                branch.unwrap_or("main")
+            ))),
+        };
+    };
+
+    let mut snapshots = Vec::new();
+    let mut found_start = start_snapshot_id.is_none();
+    for snapshot in ancestors(metadata, *head.snapshot_id()) {
+        if Some(*snapshot.snapshot_id()) == start_snapshot_id {
+            found_start = true;
+            break;
+        }
+        snapshots.push(snapshot);
+    }
+    if !found_start {
+        return Err(Error::CommitConflict(format!(
+            "The starting snapshot of the transaction is no longer an ancestor of branch {}",
+            branch.unwrap_or("main")
+        )));
+    }
+
+    let mut changes = ConcurrentChanges::default();
+    for snapshot in snapshots {
+        let (added, removed) = snapshot_changes(snapshot, metadata, object_store.clone()).await?;
+        changes.added.extend(added);
+        changes.removed.extend(removed);
+    }
+    Ok(changes)
+}
+
+/// Check that the operation can be applied to the current metadata, given the changes since the starting snapshot of the transaction
+pub(crate) async fn validate(
+    operation: &Operation,
+    start_snapshot_id: Option<i64>,
+    metadata: &TableMetadata,
+    isolation_level: IsolationLevel,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<(), Error> {
+    if !matches!(
+        operation,
+        Operation::Rewrite { .. }
+            | Operation::RewriteFiles { .. }
+            | Operation::Overwrite { .. }
+            | Operation::RowDelta { .. }
+    ) {
+        return Ok(());
+    }
+    let changes = concurrent_changes(
+        start_snapshot_id,
+        metadata,
+        operation.branch(),
+        object_store,
+    )
+    .await?;
+    if changes.added.is_empty() && changes.removed.is_empty() {
+        return Ok(());
+    }
+    let (added_data, added_deletes): (Vec<_>, Vec<_>) = changes
+        .added
+        .iter()
+        .partition(|entry| matches!(entry.data_file().content(), Content::Data));
+
+    match operation {
+        Operation::Rewrite { .. } => {
+            if let Some(entry) = changes.added.iter().chain(changes.removed.iter()).next() {
+                return Err(conflict(entry, "was changed while the table was rewritten"));
+            }
+        }
+        Operation::RewriteFiles {
+            files_to_delete, ..
+        } => {
+            let paths: HashSet<&String> = files_to_delete
+                .iter()
+                .map(|file| file.file_path())
+                .collect();
+            if let Some(entry) = changes
+                .removed
+                .iter()
+                .find(|entry| paths.contains(entry.data_file().file_path()))
+            {
+                return Err(conflict(entry, "was removed while it was rewritten"));
+            }
+            // The new files would lose the deletes, because they have a higher sequence number
+            if let Some(entry) = added_deletes.iter().find(|entry| {
+                files_to_delete
+                    .iter()
+                    .any(|file| may_apply(entry.data_file(), file))
+            }) {
+                return Err(conflict(entry, "deletes rows of rewritten files"));
+            }
+        }
+        Operation::Overwrite {
+            branch,
+            files,
+            mode,
+            ..
+        } => {
+            let schema = metadata.current_schema(branch.as_deref())?;
+            let partitions: HashSet<Struct> =
+                files.iter().map(|file| file.partition().clone()).collect();
+            let selects = |entry: &&ManifestEntry| may_select(mode, entry, &partitions, schema);
+
+            if isolation_level == IsolationLevel::Serializable {
+                if let Some(entry) = added_data.iter().copied().find(selects) {
+                    return Err(conflict(entry, "was added in the range of the overwrite"));
+                }
+            }
+            if let Some(entry) = added_deletes.iter().copied().find(selects) {
+                return Err(conflict(
+                    entry,
+                    "deletes rows in the range of the overwrite",
+                ));
+            }
+            if let Some(entry) = changes.removed.iter().find(selects) {
+                return Err(conflict(entry, "was removed in the range of the overwrite"));
+            }
+        }
+        Operation::RowDelta { delete_files, .. } => {
+            // Deleted rows might have been moved to other files
+            if let Some(entry) = changes.removed.iter().find(|entry| {
+                delete_files
+                    .iter()
+                    .any(|delete| may_apply(delete, entry.data_file()))
+            }) {
+                return Err(conflict(entry, "was removed while rows of it were deleted"));
+            }
+            // Rows of the files referenced by the position deletes might have been deleted concurrently
+            if let Some(entry) = added_deletes.iter().find(|entry| {
+                delete_files
+                    .iter()
+                    .any(|delete| may_delete_same_rows(delete, entry.data_file()))
+            }) {
+                return Err(conflict(
+                    entry,
+                    "deletes rows of the files referenced by the row delta",
+                ));
+            }
+            // Position deletes only apply to existing files, new data files can only contain rows of equality deletes
+            if isolation_level == IsolationLevel::Serializable {
+                if let Some(entry) = added_data.iter().find(|entry| {
+                    delete_files.iter().any(|delete| {
+                        matches!(delete.content(), Content::EqualityDeletes)
+                            && may_apply(delete, entry.data_file())
+                    })
+                }) {
+                    return Err(conflict(
+                        entry,
+                        "was added in the partitions of the row delta",
+                    ));
+                }
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+fn conflict(entry: &ManifestEntry, reason: &str) -> Error {
+    Error::CommitConflict(format!(
+        "File {} of snapshot {} {}",
+        entry.data_file().file_path(),
+        entry.snapshot_id().unwrap_or_default(),
+        reason
+    ))
+}
+
+/// Check whether the overwrite could select rows of the file
+fn may_select(
+    mode: &OverwriteMode,
+    entry: &ManifestEntry,
+    partitions: &HashSet<Struct>,
+    schema: &Schema,
+) -> bool {
+    match mode {
+        OverwriteMode::Dynamic => partitions.contains(entry.data_file().partition()),
+        OverwriteMode::Filter(filter) => !matches!(
+            filter_match(filter, entry.data_file(), schema),
+            Ok(FilterMatch::None)
+        ),
+    }
+}
+
+/// Check whether the delete file might delete rows of the data file, ignoring sequence numbers
+fn may_apply(delete_file: &DataFile, data_file: &DataFile) -> bool {
+    match delete_file.content() {
+        Content::Data => false,
+        Content::PositionDeletes => {
+            delete_file.partition() == data_file.partition()
+                && referenced_paths(delete_file).map_or(true, |(lower, upper)| {
+                    (lower..=upper).contains(&data_file.file_path().as_str())
+                })
+        }
+        Content::EqualityDeletes => {
+            applies_to_partition(delete_file.partition(), data_file.partition())
+        }
+    }
+}
+
+/// Check whether the other delete file might delete rows of the data files that the position delete file references
+fn may_delete_same_rows(delete_file: &DataFile, other: &DataFile) -> bool {
+    if !matches!(delete_file.content(), Content::PositionDeletes) {
+        return false;
+    }
+    match other.content() {
+        Content::Data => false,
+        Content::PositionDeletes => {
+            delete_file.partition() == other.partition()
+                && match (referenced_paths(delete_file), referenced_paths(other)) {
+                    (Some((lower, upper)), Some((other_lower, other_upper))) => {
+                        lower <= other_upper && other_lower <= upper
+                    }
+                    _ => true,
+                }
+        }
+        Content::EqualityDeletes => {
+            applies_to_partition(other.partition(), delete_file.partition())
+        }
+    }
+}
+
+/// Range of the data file paths that a position delete file references, if the bounds of the file path column are known
+fn referenced_paths(delete_file: &DataFile) -> Option<(&str, &str)> {
+    fn bound(bounds: &Option<HashMap<i32, Value>>) -> Option<&str> {
+        match bounds.as_ref()?.get(&DELETE_FILE_PATH_FIELD_ID)? {
+            Value::String(path) => Some(path.as_str()),
+            _ => None,
+        }
+    }
+    Some((
+        bound(delete_file.lower_bounds())?,
+        bound(delete_file.upper_bounds())?,
+    ))
+}
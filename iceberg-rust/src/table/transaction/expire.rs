@@ -0,0 +1,217 @@
+/*!
+ * Determines which snapshots and snapshot references are removed when expiring snapshots.
+*/
+
+use std::collections::HashSet;
+
+use iceberg_rust_spec::spec::{
+    snapshot::{SnapshotReference, SnapshotRetention},
+    table_metadata::{TableMetadata, MAIN_BRANCH},
+};
+
+/// Table property for the default minimum number of snapshots to keep in a branch
+pub static MIN_SNAPSHOTS_TO_KEEP: &str = "history.expire.min-snapshots-to-keep";
+/// Table property for the default maximum age of the snapshots to keep in a branch
+pub static MAX_SNAPSHOT_AGE_MS: &str = "history.expire.max-snapshot-age-ms";
+/// Table property for the default maximum age of snapshot references
+pub static MAX_REF_AGE_MS: &str = "history.expire.max-ref-age-ms";
+
+static MIN_SNAPSHOTS_TO_KEEP_DEFAULT: i32 = 1;
+static MAX_SNAPSHOT_AGE_MS_DEFAULT: i64 = 5 * 24 * 60 * 60 * 1000;
+static MAX_REF_AGE_MS_DEFAULT: i64 = i64::MAX;
+
+/// Snapshots and snapshot references that are removed by expiring snapshots
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Expired {
+    pub(crate) snapshot_ids: Vec<i64>,
+    pub(crate) refs: Vec<String>,
+}
+
+/// Apply the retention policies of the snapshot references at the time `now_ms`.
+/// `older_than` and `retain_last` replace the defaults from the table properties for references without their own policy.
+/// Snapshots that are not reachable from any reference are kept if they are younger than the default maximum age.
+pub(crate) fn expire(
+    metadata: &TableMetadata,
+    now_ms: i64,
+    older_than: Option<i64>,
+    retain_last: Option<i32>,
+) -> Expired {
+    let property = |key: &str| metadata.properties.get(key).and_then(|x| x.parse().ok());
+
+    let default_min_snapshots = retain_last
+        .or_else(|| property(MIN_SNAPSHOTS_TO_KEEP).map(|x: i64| x as i32))
+        .unwrap_or(MIN_SNAPSHOTS_TO_KEEP_DEFAULT);
+    let default_older_than = older_than.unwrap_or_else(|| {
+        now_ms.saturating_sub(property(MAX_SNAPSHOT_AGE_MS).unwrap_or(MAX_SNAPSHOT_AGE_MS_DEFAULT))
+    });
+    let default_max_ref_age = property(MAX_REF_AGE_MS).unwrap_or(MAX_REF_AGE_MS_DEFAULT);
+
+    let mut refs = metadata.refs.clone();
+    // Legacy tables might only track the current snapshot
+    if let Some(current) = metadata.current_snapshot_id {
+        refs.entry(MAIN_BRANCH.to_owned())
+            .or_insert(SnapshotReference {
+                snapshot_id: current,
+                retention: SnapshotRetention::default(),
+            });
+    }
+
+    let mut expired = Expired::default();
+    let mut retained = HashSet::new();
+    let mut referenced = HashSet::new();
+
+    for (name, reference) in refs {
+        let Some(snapshot) = metadata.snapshots.get(&reference.snapshot_id) else {
+            expired.refs.push(name);
+            continue;
+        };
+        let max_ref_age = match &reference.retention {
+            SnapshotRetention::Branch { max_ref_age_ms, .. } => {
+                max_ref_age_ms.unwrap_or(default_max_ref_age)
+            }
+            SnapshotRetention::Tag { max_ref_age_ms } => *max_ref_age_ms,
+        };
+        if name != MAIN_BRANCH && now_ms.saturating_sub(*snapshot.timestamp_ms()) > max_ref_age {
+            expired.refs.push(name);
+            continue;
+        }
+
+        match &reference.retention {
+            SnapshotRetention::Tag { .. } => {
+                retained.insert(reference.snapshot_id);
+                referenced.insert(reference.snapshot_id);
+            }
+            SnapshotRetention::Branch {
+                min_snapshots_to_keep,
+                max_snapshot_age_ms,
+                ..
+            } => {
+                let min_snapshots = min_snapshots_to_keep.unwrap_or(default_min_snapshots);
+                let older_than = max_snapshot_age_ms
+                    .map(|age| now_ms.saturating_sub(age))
+                    .unwrap_or(default_older_than);
+
+                let mut kept = 0;
+                let mut ancestor = Some(snapshot);
+                while let Some(snapshot) = ancestor {
+                    referenced.insert(*snapshot.snapshot_id());
+                    if kept < min_snapshots || *snapshot.timestamp_ms() >= older_than {
+                        retained.insert(*snapshot.snapshot_id());
+                        kept += 1;
+                    }
+                    ancestor = snapshot
+                        .parent_snapshot_id()
+                        .as_ref()
+                        .and_then(|id| metadata.snapshots.get(id));
+                }
+            }
+        }
+    }
+
+    expired.snapshot_ids = metadata
+        .snapshots
+        .values()
+        .filter(|snapshot| {
+            let id = snapshot.snapshot_id();
+            !retained.contains(id)
+                && (referenced.contains(id) || *snapshot.timestamp_ms() < default_older_than)
+        })
+        .map(|snapshot| *snapshot.snapshot_id())
+        .collect();
+    expired.snapshot_ids.sort_unstable();
+    expired.refs.sort_unstable();
+
+    expired
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use iceberg_rust_spec::spec::{
+        snapshot::{Snapshot, SnapshotBuilder, SnapshotReference, SnapshotRetention},
+        table_metadata::TableMetadata,
+    };
+
+    use super::{expire, Expired};
+
+    fn snapshot(id: i64, parent: Option<i64>, timestamp_ms: i64) -> (i64, Snapshot) {
+        let mut builder = SnapshotBuilder::default();
+        builder
+            .with_snapshot_id(id)
+            .with_sequence_number(id)
+            .with_timestamp_ms(timestamp_ms)
+            .with_manifest_list(format!("/test/snap-{}.avro", id));
+        if let Some(parent) = parent {
+            builder.with_parent_snapshot_id(parent);
+        }
+        (id, builder.build().unwrap())
+    }
+
+    #[test]
+    fn test_expire() {
+        let metadata = TableMetadata {
+            snapshots: HashMap::from_iter(vec![
+                snapshot(1, None, 100),
+                snapshot(2, Some(1), 200),
+                snapshot(3, Some(2), 300),
+                snapshot(4, Some(3), 400),
+                snapshot(5, Some(2), 250),
+                snapshot(6, None, 150),
+            ]),
+            current_snapshot_id: Some(4),
+            refs: HashMap::from_iter(vec![
+                (
+                    "main".to_owned(),
+                    SnapshotReference {
+                        snapshot_id: 4,
+                        retention: SnapshotRetention::default(),
+                    },
+                ),
+                (
+                    "old".to_owned(),
+                    SnapshotReference {
+                        snapshot_id: 5,
+                        retention: SnapshotRetention::Branch {
+                            min_snapshots_to_keep: None,
+                            max_snapshot_age_ms: None,
+                            max_ref_age_ms: Some(100),
+                        },
+                    },
+                ),
+                (
+                    "tag".to_owned(),
+                    SnapshotReference {
+                        snapshot_id: 1,
+                        retention: SnapshotRetention::Tag {
+                            max_ref_age_ms: 1000,
+                        },
+                    },
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            expire(&metadata, 400, Some(300), None),
+            Expired {
+                snapshot_ids: vec![2, 5, 6],
+                refs: vec!["old".to_owned()],
+            }
+        );
+        assert_eq!(
+            expire(&metadata, 400, Some(300), Some(3)),
+            Expired {
+                snapshot_ids: vec![5, 6],
+                refs: vec!["old".to_owned()],
+            }
+        );
+        assert_eq!(
+            expire(&metadata, 400, Some(0), None),
+            Expired {
+                snapshot_ids: vec![],
+                refs: vec!["old".to_owned()],
+            }
+        );
+    }
+}
@@ -0,0 +1,297 @@
+/*!
+ * Changes to the snapshot references of a table, like rollbacks and fast-forwards of branches.
+*/
+
+use std::mem::discriminant;
+
+use iceberg_rust_spec::spec::{
+    snapshot::{SnapshotReference, SnapshotRetention},
+    table_metadata::{TableMetadata, MAIN_BRANCH},
+};
+
+use crate::{
+    catalog::commit::{TableRequirement, TableUpdate},
+    error::Error,
+    table::ancestors,
+};
+
+/// Change of a single snapshot reference
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum RefChange {
+    /// Set the branch to an ancestor of its current snapshot
+    RollbackTo(i64),
+    /// Set the branch to the last ancestor of its current snapshot that was created before the timestamp in milliseconds
+    RollbackToTime(i64),
+    /// Set the branch to any snapshot of the table
+    SetCurrent(i64),
+    /// Set the branch to the current snapshot of another branch. The current snapshot of the branch has to be an ancestor of it.
+    FastForward(String),
+    /// Create a new reference
+    Create(i64, SnapshotRetention),
+    /// Replace an existing reference of the same kind
+    Replace(i64, SnapshotRetention),
+    /// Remove an existing reference
+    Remove,
+}
+
+/// Current snapshot id of the reference. Legacy tables only track the current snapshot of the main branch.
+fn head(metadata: &TableMetadata, ref_name: &str) -> Option<i64> {
+    metadata
+        .refs
+        .get(ref_name)
+        .map(|reference| reference.snapshot_id)
+        .or_else(|| {
+            (ref_name == MAIN_BRANCH)
+                .then_some(metadata.current_snapshot_id)
+                .flatten()
+        })
+}
+
+/// Check that the snapshot is the snapshot itself or an ancestor of the other snapshot
+pub(crate) fn is_ancestor(metadata: &TableMetadata, snapshot_id: i64, of: i64) -> bool {
+    ancestors(metadata, of).any(|snapshot| *snapshot.snapshot_id() == snapshot_id)
+}
+
+/// Validate the change against the current metadata and compute the table updates
+pub(crate) fn apply(
+    metadata: &TableMetadata,
+    ref_name: String,
+    change: RefChange,
+) -> Result<(Vec<TableRequirement>, Vec<TableUpdate>), Error> {
+    let current = metadata.refs.get(&ref_name);
+    let requirement = current
+        .map(|reference| TableRequirement::AssertRefSnapshotId {
+            r#ref: ref_name.clone(),
+            snapshot_id: reference.snapshot_id,
+        })
+        .into_iter()
+        .collect::<Vec<_>>();
+    let branch_head = || {
+        if current
+            .is_some_and(|reference| matches!(reference.retention, SnapshotRetention::Tag { .. }))
+        {
+            return Err(Error::InvalidFormat(format!("{} is a tag", ref_name)));
+        }
+        head(metadata, &ref_name).ok_or(Error::NotFound("Branch".to_owned(), ref_name.clone()))
+    };
+    // Moving a branch keeps its retention policy
+    let retention = current
+        .map(|reference| reference.retention.clone())
+        .unwrap_or_default();
+
+    let snapshot_id = match change {
+        RefChange::RollbackTo(snapshot_id) => {
+            if !is_ancestor(metadata, snapshot_id, branch_head()?) {
+                return Err(Error::InvalidFormat(format!(
+                    "Snapshot {} is not an ancestor of {}",
+                    snapshot_id, ref_name
+                )));
+            }
+            snapshot_id
+        }
+        RefChange::RollbackToTime(timestamp_ms) => *ancestors(metadata, branch_head()?)
+            .find(|snapshot| *snapshot.timestamp_ms() < timestamp_ms)
+            .ok_or(Error::NotFound(
+                "Snapshot before".to_owned(),
+                timestamp_ms.to_string(),
+            ))?
+            .snapshot_id(),
+        RefChange::SetCurrent(snapshot_id) => {
+            branch_head()?;
+            snapshot_id
+        }
+        RefChange::FastForward(to) => {
+            let from = branch_head()?;
+            let snapshot_id =
+                head(metadata, &to).ok_or(Error::NotFound("Branch".to_owned(), to))?;
+            if !is_ancestor(metadata, from, snapshot_id) {
+                return Err(Error::InvalidFormat(format!(
+                    "Branch {} cannot be fast-forwarded, its snapshot {} is not an ancestor of {}",
+                    ref_name, from, snapshot_id
+                )));
+            }
+            snapshot_id
+        }
+        RefChange::Create(snapshot_id, retention) => {
+            if head(metadata, &ref_name).is_some() {
+                return Err(Error::InvalidFormat(format!(
+                    "Reference {} already exists",
+                    ref_name
+                )));
+            }
+            return set_ref(metadata, Vec::new(), ref_name, snapshot_id, retention);
+        }
+        RefChange::Replace(snapshot_id, retention) => match current {
+            Some(reference) if discriminant(&reference.retention) == discriminant(&retention) => {
+                return set_ref(metadata, requirement, ref_name, snapshot_id, retention)
+            }
+            Some(_) => {
+                return Err(Error::InvalidFormat(format!(
+                    "Reference {} is of a different kind",
+                    ref_name
+                )))
+            }
+            None => return Err(Error::NotFound("Reference".to_owned(), ref_name)),
+        },
+        RefChange::Remove => {
+            if ref_name == MAIN_BRANCH {
+                return Err(Error::InvalidFormat(
+                    "The main branch cannot be removed".to_owned(),
+                ));
+            }
+            if current.is_none() {
+                return Err(Error::NotFound("Reference".to_owned(), ref_name));
+            }
+            return Ok((
+                requirement,
+                vec![TableUpdate::RemoveSnapshotRef { ref_name }],
+            ));
+        }
+    };
+
+    set_ref(metadata, requirement, ref_name, snapshot_id, retention)
+}
+
+fn set_ref(
+    metadata: &TableMetadata,
+    requirement: Vec<TableRequirement>,
+    ref_name: String,
+    snapshot_id: i64,
+    retention: SnapshotRetention,
+) -> Result<(Vec<TableRequirement>, Vec<TableUpdate>), Error> {
+    if !metadata.snapshots.contains_key(&snapshot_id) {
+        return Err(Error::NotFound(
+            "Snapshot".to_owned(),
+            snapshot_id.to_string(),
+        ));
+    }
+    Ok((
+        requirement,
+        vec![TableUpdate::SetSnapshotRef {
+            ref_name,
+            snapshot_reference: SnapshotReference {
+                snapshot_id,
+                retention,
+            },
+        }],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use iceberg_rust_spec::spec::{
+        snapshot::{Snapshot, SnapshotBuilder, SnapshotReference, SnapshotRetention},
+        table_metadata::TableMetadata,
+    };
+
+    use crate::catalog::commit::TableUpdate;
+
+    use super::{apply, RefChange};
+
+    fn snapshot(id: i64, parent: Option<i64>, timestamp_ms: i64) -> (i64, Snapshot) {
+        let mut builder = SnapshotBuilder::default();
+        builder
+            .with_snapshot_id(id)
+            .with_sequence_number(id)
+            .with_timestamp_ms(timestamp_ms)
+            .with_manifest_list(format!("/test/snap-{}.avro", id));
+        if let Some(parent) = parent {
+            builder.with_parent_snapshot_id(parent);
+        }
+        (id, builder.build().unwrap())
+    }
+
+    fn snapshot_id(updates: Vec<TableUpdate>) -> i64 {
+        match &updates[..] {
+            [TableUpdate::SetSnapshotRef {
+                snapshot_reference, ..
+            }] => snapshot_reference.snapshot_id,
+            _ => panic!("Expected a single snapshot reference update"),
+        }
+    }
+
+    #[test]
+    fn test_ref_changes() {
+        let metadata = TableMetadata {
+            snapshots: HashMap::from_iter(vec![
+                snapshot(1, None, 100),
+                snapshot(2, Some(1), 200),
+                snapshot(3, Some(2), 300),
+                snapshot(4, Some(2), 400),
+            ]),
+            current_snapshot_id: Some(3),
+            refs: HashMap::from_iter(vec![
+                (
+                    "main".to_owned(),
+                    SnapshotReference {
+                        snapshot_id: 3,
+                        retention: SnapshotRetention::default(),
+                    },
+                ),
+                (
+                    "staging".to_owned(),
+                    SnapshotReference {
+                        snapshot_id: 4,
+                        retention: SnapshotRetention::default(),
+                    },
+                ),
+                (
+                    "tag".to_owned(),
+                    SnapshotReference {
+                        snapshot_id: 1,
+                        retention: SnapshotRetention::Tag {
+                            max_ref_age_ms: 1000,
+                        },
+                    },
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        let change = |ref_name: &str, change| apply(&metadata, ref_name.to_owned(), change);
+
+        assert_eq!(
+            snapshot_id(change("main", RefChange::RollbackTo(1)).unwrap().1),
+            1
+        );
+        // Snapshot 4 is not an ancestor of the main branch
+        assert!(change("main", RefChange::RollbackTo(4)).is_err());
+        assert_eq!(
+            snapshot_id(change("main", RefChange::RollbackToTime(250)).unwrap().1),
+            2
+        );
+        assert_eq!(
+            snapshot_id(change("main", RefChange::SetCurrent(4)).unwrap().1),
+            4
+        );
+        assert!(change("main", RefChange::FastForward("staging".to_owned())).is_err());
+        assert!(change("tag", RefChange::RollbackTo(1)).is_err());
+        assert!(change("tag", RefChange::Create(2, SnapshotRetention::default())).is_err());
+        assert!(change("tag", RefChange::Replace(2, SnapshotRetention::default())).is_err());
+        assert!(change("main", RefChange::Remove).is_err());
+
+        // The main branch can be fast-forwarded once it is an ancestor of the staging branch
+        let mut metadata = metadata;
+        metadata.refs.insert(
+            "main".to_owned(),
+            SnapshotReference {
+                snapshot_id: 2,
+                retention: SnapshotRetention::default(),
+            },
+        );
+        assert_eq!(
+            snapshot_id(
+                apply(
+                    &metadata,
+                    "main".to_owned(),
+                    RefChange::FastForward("staging".to_owned())
+                )
+                .unwrap()
+                .1
+            ),
+            4
+        );
+    }
+}
@@ -1,31 +1,69 @@
 /*!
  * Defines the [Transaction] type that performs multiple [Operation]s with ACID properties.
 */
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use iceberg_rust_spec::spec::{manifest::DataFile, schema::Schema, snapshot::SnapshotReference};
+use futures::{stream, StreamExt, TryStreamExt};
+use iceberg_rust_spec::spec::{
+    manifest::DataFile,
+    schema::Schema,
+    snapshot::{Snapshot, SnapshotReference, SnapshotRetention},
+    table_metadata::{TableMetadata, MAIN_BRANCH},
+};
+use iceberg_rust_spec::util::strip_prefix;
+use object_store::ObjectStore;
 
-use crate::{catalog::commit::CommitTable, error::Error, table::Table};
+use tokio::time::sleep;
 
-use self::operation::Operation;
+use crate::{
+    catalog::{
+        commit::{apply_table_updates, CommitTable, TableRequirement, TableUpdate},
+        tabular::Tabular,
+    },
+    error::Error,
+    table::Table,
+};
 
-use super::delete_files;
+use self::{
+    conflict::IsolationLevel, manage::RefChange, operation::Operation, overwrite::OverwriteMode,
+};
+
+use super::{delete_files, delete_unreachable_files};
 
 pub(crate) mod append;
+pub mod conflict;
+pub mod expire;
+pub(crate) mod manage;
 pub(crate) mod operation;
+pub mod overwrite;
 
 pub(crate) static APPEND_KEY: &str = "append";
 pub(crate) static REWRITE_KEY: &str = "rewrite";
+pub(crate) static REWRITE_FILES_KEY: &str = "rewrite-files";
+pub(crate) static REWRITE_MANIFESTS_KEY: &str = "rewrite-manifests";
+pub(crate) static ROW_DELTA_KEY: &str = "row-delta";
+pub(crate) static OVERWRITE_KEY: &str = "overwrite";
+pub(crate) static EXPIRE_SNAPSHOTS_KEY: &str = "expire-snapshots";
 pub(crate) static ADD_SCHEMA_KEY: &str = "add-schema";
 pub(crate) static SET_DEFAULT_SPEC_KEY: &str = "set-default-spec";
 pub(crate) static UPDATE_PROPERTIES_KEY: &str = "update-properties";
 pub(crate) static SET_SNAPSHOT_REF_KEY: &str = "set-ref";
+pub(crate) static MANAGE_SNAPSHOTS_KEY: &str = "manage-snapshots";
+pub(crate) static CHERRY_PICK_KEY: &str = "cherry-pick";
 
 /// Transactions let you perform a sequence of [Operation]s that can be committed to be performed with ACID guarantees.
+/// The operations are executed in the order they were added, every operation builds on the changes of the previous ones.
 pub struct TableTransaction<'table> {
     table: &'table mut Table,
-    operations: HashMap<String, Operation>,
+    operations: Vec<(String, Operation)>,
     branch: Option<String>,
+    isolation_level: IsolationLevel,
+    validate_from_snapshot: Option<i64>,
 }
 
 impl<'table> TableTransaction<'table> {
@@ -33,64 +71,71 @@ impl<'table> TableTransaction<'table> {
     pub fn new(table: &'table mut Table, branch: Option<&str>) -> Self {
         TableTransaction {
             table,
-            operations: HashMap::new(),
+            operations: Vec::new(),
             branch: branch.map(ToString::to_string),
+            isolation_level: IsolationLevel::default(),
+            validate_from_snapshot: None,
         }
     }
+    /// Set the isolation level that determines which concurrent changes conflict with the transaction. Defaults to [IsolationLevel::Serializable].
+    pub fn isolation_level(mut self, isolation_level: IsolationLevel) -> Self {
+        self.isolation_level = isolation_level;
+        self
+    }
+    /// Validate the transaction against all changes committed after the snapshot, for example the snapshot the data of a rewrite was read from.
+    /// Defaults to the current snapshot of the table when the transaction is committed.
+    pub fn validate_from_snapshot(mut self, snapshot_id: i64) -> Self {
+        self.validate_from_snapshot = Some(snapshot_id);
+        self
+    }
     /// Update the schmema of the table
     pub fn add_schema(mut self, schema: Schema) -> Self {
-        self.operations
-            .insert(ADD_SCHEMA_KEY.to_owned(), Operation::AddSchema(schema));
+        self.insert_operation(ADD_SCHEMA_KEY, Operation::AddSchema(schema));
         self
     }
     /// Update the spec of the table
     pub fn set_default_spec(mut self, spec_id: i32) -> Self {
-        self.operations.insert(
-            SET_DEFAULT_SPEC_KEY.to_owned(),
-            Operation::SetDefaultSpec(spec_id),
-        );
+        self.insert_operation(SET_DEFAULT_SPEC_KEY, Operation::SetDefaultSpec(spec_id));
         self
     }
     /// Quickly append files to the table
     pub fn append(mut self, files: Vec<DataFile>) -> Self {
-        self.operations
-            .entry(APPEND_KEY.to_owned())
-            .and_modify(|mut x| {
-                if let Operation::Append {
-                    branch: _,
-                    files: old,
-                    additional_summary: None,
-                } = &mut x
-                {
-                    old.extend_from_slice(&files)
-                }
-            })
-            .or_insert(Operation::Append {
-                branch: self.branch.clone(),
-                files,
+        match self.operation_mut(APPEND_KEY) {
+            Some(Operation::Append {
+                branch: _,
+                files: old,
                 additional_summary: None,
-            });
+            }) => old.extend(files),
+            Some(_) => (),
+            None => self.operations.push((
+                APPEND_KEY.to_owned(),
+                Operation::Append {
+                    branch: self.branch.clone(),
+                    files,
+                    additional_summary: None,
+                },
+            )),
+        }
         self
     }
     /// Quickly append files to the table
     pub fn rewrite(mut self, files: Vec<DataFile>) -> Self {
-        self.operations
-            .entry(REWRITE_KEY.to_owned())
-            .and_modify(|mut x| {
-                if let Operation::Rewrite {
-                    branch: _,
-                    files: old,
-                    additional_summary: None,
-                } = &mut x
-                {
-                    old.extend_from_slice(&files)
-                }
-            })
-            .or_insert(Operation::Rewrite {
-                branch: self.branch.clone(),
-                files,
+        match self.operation_mut(REWRITE_KEY) {
+            Some(Operation::Rewrite {
+                branch: _,
+                files: old,
                 additional_summary: None,
-            });
+            }) => old.extend(files),
+            Some(_) => (),
+            None => self.operations.push((
+                REWRITE_KEY.to_owned(),
+                Operation::Rewrite {
+                    branch: self.branch.clone(),
+                    files,
+                    additional_summary: None,
+                },
+            )),
+        }
         self
     }
     /// Quickly append files to the table
@@ -99,95 +144,537 @@ impl<'table> TableTransaction<'table> {
         files: Vec<DataFile>,
         additional_summary: HashMap<String, String>,
     ) -> Self {
-        self.operations
-            .entry(REWRITE_KEY.to_owned())
-            .and_modify(|mut x| {
-                if let Operation::Rewrite {
-                    branch: _,
-                    files: old,
-                    additional_summary: old_lineage,
-                } = &mut x
-                {
-                    old.extend_from_slice(&files);
-                    *old_lineage = Some(additional_summary.clone());
-                }
-            })
-            .or_insert(Operation::Rewrite {
+        match self.operation_mut(REWRITE_KEY) {
+            Some(Operation::Rewrite {
+                branch: _,
+                files: old,
+                additional_summary: old_lineage,
+            }) => {
+                old.extend(files);
+                *old_lineage = Some(additional_summary);
+            }
+            Some(_) => (),
+            None => self.operations.push((
+                REWRITE_KEY.to_owned(),
+                Operation::Rewrite {
+                    branch: self.branch.clone(),
+                    files,
+                    additional_summary: Some(additional_summary),
+                },
+            )),
+        }
+        self
+    }
+    /// Replace data files of the table with new data files that contain the same rows, for example after compacting them
+    pub fn rewrite_files(mut self, files_to_delete: Vec<DataFile>, files: Vec<DataFile>) -> Self {
+        match self.operation_mut(REWRITE_FILES_KEY) {
+            Some(Operation::RewriteFiles {
+                branch: _,
+                files_to_delete: old_files_to_delete,
+                files: old,
+                additional_summary: None,
+            }) => {
+                old_files_to_delete.extend(files_to_delete);
+                old.extend(files);
+            }
+            Some(_) => (),
+            None => self.operations.push((
+                REWRITE_FILES_KEY.to_owned(),
+                Operation::RewriteFiles {
+                    branch: self.branch.clone(),
+                    files_to_delete,
+                    files,
+                    additional_summary: None,
+                },
+            )),
+        }
+        self
+    }
+    /// Regroup the data manifests of the table by partition into manifests of the target size set by the table property `commit.manifest.target-size-bytes`
+    pub fn rewrite_manifests(mut self) -> Self {
+        self.insert_operation(
+            REWRITE_MANIFESTS_KEY,
+            Operation::RewriteManifests {
                 branch: self.branch.clone(),
-                files,
-                additional_summary: Some(additional_summary),
-            });
+                additional_summary: None,
+            },
+        );
+        self
+    }
+    /// Add data files and delete files to the table in a single snapshot
+    pub fn row_delta(mut self, files: Vec<DataFile>, delete_files: Vec<DataFile>) -> Self {
+        match self.operation_mut(ROW_DELTA_KEY) {
+            Some(Operation::RowDelta {
+                branch: _,
+                files: old,
+                delete_files: old_delete_files,
+                additional_summary: None,
+            }) => {
+                old.extend(files);
+                old_delete_files.extend(delete_files);
+            }
+            Some(_) => (),
+            None => self.operations.push((
+                ROW_DELTA_KEY.to_owned(),
+                Operation::RowDelta {
+                    branch: self.branch.clone(),
+                    files,
+                    delete_files,
+                    additional_summary: None,
+                },
+            )),
+        }
+        self
+    }
+    /// Add the data files and the equality delete files of an upsert to the table in a single snapshot
+    pub fn upsert(self, files: Vec<DataFile>, equality_delete_files: Vec<DataFile>) -> Self {
+        self.row_delta(files, equality_delete_files)
+    }
+    /// Replace the files selected by the [OverwriteMode] with the new files in a single snapshot
+    pub fn overwrite(mut self, files: Vec<DataFile>, mode: OverwriteMode) -> Self {
+        match self.operation_mut(OVERWRITE_KEY) {
+            Some(Operation::Overwrite {
+                branch: _,
+                files: old,
+                mode: _,
+                additional_summary: None,
+            }) => old.extend(files),
+            Some(_) => (),
+            None => self.operations.push((
+                OVERWRITE_KEY.to_owned(),
+                Operation::Overwrite {
+                    branch: self.branch.clone(),
+                    files,
+                    mode,
+                    additional_summary: None,
+                },
+            )),
+        }
         self
     }
     /// Update the properties of the table
     pub fn update_properties(mut self, entries: Vec<(String, String)>) -> Self {
-        self.operations
-            .entry(UPDATE_PROPERTIES_KEY.to_owned())
-            .and_modify(|mut x| {
-                if let Operation::UpdateProperties(props) = &mut x {
-                    props.extend_from_slice(&entries)
-                }
-            })
-            .or_insert(Operation::UpdateProperties(entries));
+        match self.operation_mut(UPDATE_PROPERTIES_KEY) {
+            Some(Operation::UpdateProperties(props)) => props.extend(entries),
+            Some(_) => (),
+            None => self.operations.push((
+                UPDATE_PROPERTIES_KEY.to_owned(),
+                Operation::UpdateProperties(entries),
+            )),
+        }
         self
     }
     /// Set snapshot reference
     pub fn set_snapshot_ref(mut self, entry: (String, SnapshotReference)) -> Self {
-        self.operations.insert(
-            SET_SNAPSHOT_REF_KEY.to_owned(),
-            Operation::SetSnapshotRef(entry),
+        self.insert_operation(SET_SNAPSHOT_REF_KEY, Operation::SetSnapshotRef(entry));
+        self
+    }
+    /// Roll the branch of the transaction back to an ancestor of its current snapshot
+    pub fn rollback_to(self, snapshot_id: i64) -> Self {
+        let branch = self.branch_name();
+        self.manage_snapshots(branch, RefChange::RollbackTo(snapshot_id))
+    }
+    /// Roll the branch of the transaction back to the last ancestor of its current snapshot that was created before the timestamp in milliseconds
+    pub fn rollback_to_time(self, timestamp_ms: i64) -> Self {
+        let branch = self.branch_name();
+        self.manage_snapshots(branch, RefChange::RollbackToTime(timestamp_ms))
+    }
+    /// Set the current snapshot of the branch of the transaction to any snapshot of the table
+    pub fn set_current_snapshot(self, snapshot_id: i64) -> Self {
+        let branch = self.branch_name();
+        self.manage_snapshots(branch, RefChange::SetCurrent(snapshot_id))
+    }
+    /// Set the branch of the transaction to the current snapshot of the branch `to`, if the current snapshot of the branch is an ancestor of it
+    pub fn fast_forward(self, to: &str) -> Self {
+        let branch = self.branch_name();
+        self.manage_snapshots(branch, RefChange::FastForward(to.to_owned()))
+    }
+    /// Apply the changes of an append or overwrite snapshot of another branch to the branch of the transaction
+    pub fn cherry_pick(mut self, snapshot_id: i64) -> Self {
+        self.insert_operation(
+            CHERRY_PICK_KEY,
+            Operation::CherryPick {
+                branch: self.branch.clone(),
+                snapshot_id,
+            },
         );
         self
     }
+    /// Create a tag for the snapshot. The tag expires after `max_ref_age_ms`, it is kept forever by default.
+    pub fn create_tag(self, name: &str, snapshot_id: i64, max_ref_age_ms: Option<i64>) -> Self {
+        self.manage_snapshots(
+            name.to_owned(),
+            RefChange::Create(snapshot_id, tag_retention(max_ref_age_ms)),
+        )
+    }
+    /// Point an existing tag to another snapshot
+    pub fn replace_tag(self, name: &str, snapshot_id: i64, max_ref_age_ms: Option<i64>) -> Self {
+        self.manage_snapshots(
+            name.to_owned(),
+            RefChange::Replace(snapshot_id, tag_retention(max_ref_age_ms)),
+        )
+    }
+    /// Create a branch starting at the snapshot. The retention settings default to the table properties.
+    pub fn create_branch(
+        self,
+        name: &str,
+        snapshot_id: i64,
+        min_snapshots_to_keep: Option<i32>,
+        max_snapshot_age_ms: Option<i64>,
+        max_ref_age_ms: Option<i64>,
+    ) -> Self {
+        self.manage_snapshots(
+            name.to_owned(),
+            RefChange::Create(
+                snapshot_id,
+                SnapshotRetention::Branch {
+                    min_snapshots_to_keep,
+                    max_snapshot_age_ms,
+                    max_ref_age_ms,
+                },
+            ),
+        )
+    }
+    /// Point an existing branch to another snapshot and replace its retention settings
+    pub fn replace_branch(
+        self,
+        name: &str,
+        snapshot_id: i64,
+        min_snapshots_to_keep: Option<i32>,
+        max_snapshot_age_ms: Option<i64>,
+        max_ref_age_ms: Option<i64>,
+    ) -> Self {
+        self.manage_snapshots(
+            name.to_owned(),
+            RefChange::Replace(
+                snapshot_id,
+                SnapshotRetention::Branch {
+                    min_snapshots_to_keep,
+                    max_snapshot_age_ms,
+                    max_ref_age_ms,
+                },
+            ),
+        )
+    }
+    /// Remove a tag or a branch other than the main branch
+    pub fn remove_ref(self, name: &str) -> Self {
+        self.manage_snapshots(name.to_owned(), RefChange::Remove)
+    }
+    /// Expire snapshots according to the retention policies of the snapshot references and the table properties.
+    /// `older_than` (in milliseconds since the epoch) and `retain_last` override the defaults from the table properties.
+    /// If `delete_files` is true, the manifest lists, manifests and data files that are no longer reachable are deleted after the commit.
+    pub fn expire_snapshots(
+        mut self,
+        older_than: Option<i64>,
+        retain_last: Option<i32>,
+        delete_files: bool,
+    ) -> Self {
+        self.insert_operation(
+            EXPIRE_SNAPSHOTS_KEY,
+            Operation::ExpireSnapshots {
+                older_than,
+                retain_last,
+                delete_files,
+            },
+        );
+        self
+    }
+    fn branch_name(&self) -> String {
+        self.branch.clone().unwrap_or(MAIN_BRANCH.to_owned())
+    }
+    /// Every reference can only be changed once per transaction, a later change replaces an earlier one
+    fn manage_snapshots(mut self, ref_name: String, change: RefChange) -> Self {
+        self.insert_operation(
+            &(MANAGE_SNAPSHOTS_KEY.to_owned() + "-" + &ref_name),
+            Operation::ManageSnapshots { ref_name, change },
+        );
+        self
+    }
+    /// Operation of the transaction with the key
+    fn operation_mut(&mut self, key: &str) -> Option<&mut Operation> {
+        self.operations
+            .iter_mut()
+            .find(|(x, _)| x == key)
+            .map(|(_, operation)| operation)
+    }
+    /// Replace the operation with the same key or add the operation after the existing operations
+    fn insert_operation(&mut self, key: &str, operation: Operation) {
+        match self.operation_mut(key) {
+            Some(old) => *old = operation,
+            None => self.operations.push((key.to_owned(), operation)),
+        }
+    }
     /// Commit the transaction to perform the [Operation]s with ACID guarantees.
+    /// If the commit conflicts with a concurrent commit, the table is refreshed and the operations are applied to the new table metadata,
+    /// as long as they don't conflict with the concurrent changes. The retries are configured with the `commit.retry.*` table properties.
     pub async fn commit(self) -> Result<(), Error> {
         let catalog = self.table.catalog();
         let object_store = self.table.object_store();
         let identifier = self.table.identifier.clone();
+        let retry = RetryConfig::new(&self.table.metadata().properties);
 
-        // Save old metadata to be able to remove old data after a rewrite operation
-        let delete_data = if self.operations.values().any(|x| {
-            matches!(
-                x,
-                Operation::Rewrite {
-                    branch: _,
-                    files: _,
-                    additional_summary: _,
-                }
-            )
-        }) {
-            Some(self.table.metadata())
-        } else {
-            None
-        };
-
-        // Execute the table operations
-        let (mut requirements, mut updates) = (Vec::new(), Vec::new());
-        for operation in self.operations.into_values() {
-            let (requirement, update) = operation
-                .execute(self.table.metadata(), self.table.object_store())
+        // Metadata the operations were planned against
+        let base_metadata = self.table.metadata().clone();
+
+        let start = Instant::now();
+        let mut attempt = 0;
+        loop {
+            for (_, operation) in &self.operations {
+                let start_snapshot_id = match self.validate_from_snapshot {
+                    Some(snapshot_id) => Some(snapshot_id),
+                    None => base_metadata
+                        .current_snapshot(operation.branch())?
+                        .map(|snapshot| *snapshot.snapshot_id()),
+                };
+                conflict::validate(
+                    operation,
+                    start_snapshot_id,
+                    self.table.metadata(),
+                    self.isolation_level,
+                    object_store.clone(),
+                )
                 .await?;
+            }
+
+            let error = match try_commit(self.table, self.operations.clone()).await {
+                Err(Error::CommitConflict(error)) => error,
+                result => return result,
+            };
+            if attempt >= retry.num_retries || start.elapsed() >= retry.total_timeout {
+                return Err(Error::CommitConflict(error));
+            }
+            sleep(retry.backoff(attempt)).await;
+            attempt += 1;
+
+            let Tabular::Table(table) = catalog.clone().load_tabular(&identifier).await? else {
+                return Err(Error::InvalidFormat(format!(
+                    "{} is not a table",
+                    identifier
+                )));
+            };
+            *self.table = table;
+        }
+    }
+}
+
+/// Execute the operations against the current metadata of the table and commit the changes to the catalog
+async fn try_commit(table: &mut Table, operations: Vec<(String, Operation)>) -> Result<(), Error> {
+    let catalog = table.catalog();
+    let object_store = table.object_store();
+    let identifier = table.identifier.clone();
+
+    // Save old metadata to be able to remove old data after a rewrite operation
+    let delete_data = if operations.iter().any(|(_, x)| {
+        matches!(
+            x,
+            Operation::Rewrite {
+                branch: _,
+                files: _,
+                additional_summary: _,
+            }
+        )
+    }) {
+        Some(table.metadata())
+    } else {
+        None
+    };
+
+    // Save old metadata to be able to remove files of expired snapshots
+    let expired_data = if operations.iter().any(|(_, x)| {
+        matches!(
+            x,
+            Operation::ExpireSnapshots {
+                delete_files: true,
+                ..
+            }
+        )
+    }) {
+        Some(table.metadata())
+    } else {
+        None
+    };
 
-            if let Some(requirement) = requirement {
-                requirements.push(requirement);
+    // Execute the table operations
+    let (requirements, updates) = execute_operations(
+        table.metadata(),
+        operations.into_iter().map(|(_, operation)| operation),
+        object_store.clone(),
+    )
+    .await?;
+
+    let new_snapshots = updates
+        .iter()
+        .filter_map(|update| match update {
+            TableUpdate::AddSnapshot { snapshot } => Some(snapshot.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let new_table = match catalog
+        .clone()
+        .update_table(CommitTable {
+            identifier,
+            requirements,
+            updates,
+        })
+        .await
+    {
+        Ok(table) => table,
+        // The snapshots of a conflicting commit are never referenced, the next attempt writes its own files.
+        // Removing them is best effort, remaining files are cleaned up by removing orphan files.
+        Err(Error::CommitConflict(error)) => {
+            delete_snapshot_files(table.metadata(), &new_snapshots, object_store)
+                .await
+                .ok();
+            return Err(Error::CommitConflict(error));
+        }
+        Err(error) => return Err(error),
+    };
+
+    if let Some(old_metadata) = expired_data {
+        delete_unreachable_files(old_metadata, new_table.metadata(), object_store.clone()).await?;
+    }
+
+    if let Some(old_metadata) = delete_data {
+        delete_files(old_metadata, object_store).await?;
+    }
+
+    *table = new_table;
+    Ok(())
+}
+
+/// Delete the manifest lists of the snapshots and the manifests that were written for them.
+/// Manifests that the snapshots reuse from earlier snapshots are kept.
+async fn delete_snapshot_files(
+    metadata: &TableMetadata,
+    snapshots: &[Snapshot],
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<(), Error> {
+    let mut files = Vec::new();
+    for snapshot in snapshots {
+        files.extend(
+            snapshot
+                .manifests(metadata, object_store.clone())
+                .await?
+                .filter_map(|manifest| match manifest {
+                    Ok(manifest) if manifest.added_snapshot_id == *snapshot.snapshot_id() => {
+                        Some(Ok(manifest.manifest_path))
+                    }
+                    Ok(_) => None,
+                    Err(error) => Some(Err(error)),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        );
+        files.push(snapshot.manifest_list().clone());
+    }
+
+    stream::iter(files)
+        .map(Ok::<_, Error>)
+        .try_for_each_concurrent(None, |path| {
+            let object_store = object_store.clone();
+            async move {
+                object_store.delete(&strip_prefix(&path).into()).await?;
+                Ok(())
+            }
+        })
+        .await
+}
+
+/// Execute the operations in order. Every operation is executed against the metadata with the updates of the previous operations applied,
+/// so that several operations can add snapshots to the same branch. The requirements assert the state of the table before the transaction.
+pub(crate) async fn execute_operations(
+    metadata: &TableMetadata,
+    operations: impl IntoIterator<Item = Operation>,
+    object_store: Arc<dyn ObjectStore>,
+) -> Result<(Vec<TableRequirement>, Vec<TableUpdate>), Error> {
+    let mut metadata = metadata.clone();
+    let (mut requirements, mut updates) = (Vec::new(), Vec::new());
+    // References that were changed by the previous operations
+    let mut changed_refs = HashSet::new();
+    for operation in operations {
+        let (operation_requirements, update) =
+            operation.execute(&metadata, object_store.clone()).await?;
+
+        // A reference that points to the snapshot of a previous operation is asserted by the previous operation
+        requirements.extend(operation_requirements.into_iter().filter(|requirement| {
+            !matches!(
+                requirement,
+                TableRequirement::AssertRefSnapshotId { r#ref, .. } if changed_refs.contains(r#ref)
+            )
+        }));
+        for update in &update {
+            if let TableUpdate::SetSnapshotRef { ref_name, .. }
+            | TableUpdate::RemoveSnapshotRef { ref_name } = update
+            {
+                changed_refs.insert(ref_name.clone());
             }
-            updates.extend(update);
         }
+        apply_table_updates(&mut metadata, update.clone(), None)?;
+        updates.extend(update);
+    }
+    Ok((requirements, updates))
+}
+
+/// Table property for the number of times a conflicting commit is retried
+pub static COMMIT_NUM_RETRIES: &str = "commit.retry.num-retries";
+/// Table property for the minimum time in milliseconds to wait before retrying a commit
+pub static COMMIT_MIN_RETRY_WAIT_MS: &str = "commit.retry.min-wait-ms";
+/// Table property for the maximum time in milliseconds to wait before retrying a commit
+pub static COMMIT_MAX_RETRY_WAIT_MS: &str = "commit.retry.max-wait-ms";
+/// Table property for the total time in milliseconds after which a commit is no longer retried
+pub static COMMIT_TOTAL_RETRY_TIME_MS: &str = "commit.retry.total-timeout-ms";
+
+static COMMIT_NUM_RETRIES_DEFAULT: u32 = 4;
+static COMMIT_MIN_RETRY_WAIT_MS_DEFAULT: u64 = 100;
+static COMMIT_MAX_RETRY_WAIT_MS_DEFAULT: u64 = 60 * 1000;
+static COMMIT_TOTAL_RETRY_TIME_MS_DEFAULT: u64 = 30 * 60 * 1000;
 
-        let new_table = catalog
-            .clone()
-            .update_table(CommitTable {
-                identifier,
-                requirements,
-                updates,
-            })
-            .await?;
-
-        if let Some(old_metadata) = delete_data {
-            delete_files(old_metadata, object_store).await?;
+/// Retry settings of a commit, read from the table properties
+struct RetryConfig {
+    num_retries: u32,
+    min_wait: Duration,
+    max_wait: Duration,
+    total_timeout: Duration,
+}
+
+impl RetryConfig {
+    fn new(properties: &HashMap<String, String>) -> Self {
+        fn property<T: FromStr>(properties: &HashMap<String, String>, key: &str, default: T) -> T {
+            properties
+                .get(key)
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(default)
         }
+        RetryConfig {
+            num_retries: property(properties, COMMIT_NUM_RETRIES, COMMIT_NUM_RETRIES_DEFAULT),
+            min_wait: Duration::from_millis(property(
+                properties,
+                COMMIT_MIN_RETRY_WAIT_MS,
+                COMMIT_MIN_RETRY_WAIT_MS_DEFAULT,
+            )),
+            max_wait: Duration::from_millis(property(
+                properties,
+                COMMIT_MAX_RETRY_WAIT_MS,
+                COMMIT_MAX_RETRY_WAIT_MS_DEFAULT,
+            )),
+            total_timeout: Duration::from_millis(property(
+                properties,
+                COMMIT_TOTAL_RETRY_TIME_MS,
+                COMMIT_TOTAL_RETRY_TIME_MS_DEFAULT,
+            )),
+        }
+    }
+
+    /// Exponential backoff, starting with the minimum wait time
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.min_wait
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_wait)
+    }
+}
 
-        *self.table = new_table;
-        Ok(())
+fn tag_retention(max_ref_age_ms: Option<i64>) -> SnapshotRetention {
+    SnapshotRetention::Tag {
+        max_ref_age_ms: max_ref_age_ms.unwrap_or(i64::MAX),
     }
 }
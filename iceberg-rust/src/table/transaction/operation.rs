@@ -2,25 +2,34 @@
  * Defines the different [Operation]s on a [Table].
 */
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use iceberg_rust_spec::manifest_list::{
-    manifest_list_schema_v1, manifest_list_schema_v2, ManifestListReader,
+    self, manifest_list_schema_v1, manifest_list_schema_v2, ManifestListReader,
 };
-use iceberg_rust_spec::spec::table_metadata::TableMetadata;
+use iceberg_rust_spec::spec::table_metadata::{TableMetadata, MAIN_BRANCH};
 use iceberg_rust_spec::spec::{
-    manifest::{partition_value_schema, DataFile, ManifestEntry, Status},
+    manifest::{partition_value_schema, Content, DataFile, ManifestEntry, Status},
     schema::Schema,
     snapshot::{
         generate_snapshot_id, SnapshotBuilder, SnapshotReference, SnapshotRetention, Summary,
     },
+    values::Struct,
 };
 use iceberg_rust_spec::table_metadata::FormatVersion;
 use iceberg_rust_spec::util::strip_prefix;
 use object_store::ObjectStore;
 use smallvec::SmallVec;
 
-use crate::table::manifest::{ManifestReader, ManifestWriter};
+use crate::table::{
+    ancestors,
+    manifest::{ManifestReader, ManifestWriter},
+    snapshot_changes,
+};
 use crate::{
     catalog::commit::{TableRequirement, TableUpdate},
     error::Error,
@@ -30,10 +39,20 @@ use crate::{
 use super::append::{
     select_manifest_partitioned, select_manifest_unpartitioned, split_datafiles, SelectedManifest,
 };
+use super::expire::{expire, Expired};
+use super::manage::{self, is_ancestor, RefChange};
+use super::overwrite::OverwriteMode;
 
 /// The target number of datafiles per manifest is dynamic, but we don't want to go below this number.
 static MIN_DATAFILES_PER_MANIFEST: usize = 4;
 
+/// Summary property of a cherry-picked snapshot that references the original snapshot
+static SOURCE_SNAPSHOT_ID: &str = "source-snapshot-id";
+
+/// Table property for the target size of manifest files in bytes
+static MANIFEST_TARGET_SIZE_BYTES: &str = "commit.manifest.target-size-bytes";
+static MANIFEST_TARGET_SIZE_BYTES_DEFAULT: i64 = 8 * 1024 * 1024;
+
 /// To achieve fast lookups of the datafiles, the manifest tree should be somewhat balanced, meaning that manifest files should contain a similar number of datafiles.
 /// This means that manifest files might need to be split up when they get too large. Since the number of datafiles being added by a append operation might be really large,
 /// it might even be required to split the manifest file multiple times. *n_splits* stores how many times a manifest file needs to be split to give at most *limit* datafiles per manifest.
@@ -57,7 +76,7 @@ fn compute_n_splits(
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 ///Table operations
 pub enum Operation {
     /// Update schema
@@ -89,28 +108,71 @@ pub enum Operation {
         files: Vec<DataFile>,
         additional_summary: Option<HashMap<String, String>>,
     },
-    // /// Replace manifests files and commit
-    // RewriteManifests,
-    // /// Replace files in the table by a filter expression
-    // NewOverwrite,
-    // /// Remove or replace rows in existing data files
-    // NewRowDelta,
+    /// Replace data files in the table without changing the table data
+    RewriteFiles {
+        branch: Option<String>,
+        files_to_delete: Vec<DataFile>,
+        files: Vec<DataFile>,
+        additional_summary: Option<HashMap<String, String>>,
+    },
+    /// Regroup the live entries of the data manifests by partition into manifests of the target size
+    RewriteManifests {
+        branch: Option<String>,
+        additional_summary: Option<HashMap<String, String>>,
+    },
+    /// Replace the files selected by the overwrite mode with new files
+    Overwrite {
+        branch: Option<String>,
+        files: Vec<DataFile>,
+        mode: OverwriteMode,
+        additional_summary: Option<HashMap<String, String>>,
+    },
+    /// Remove or replace rows in existing data files by adding data files and delete files
+    RowDelta {
+        branch: Option<String>,
+        files: Vec<DataFile>,
+        delete_files: Vec<DataFile>,
+        additional_summary: Option<HashMap<String, String>>,
+    },
     // /// Delete files in the table and commit
     // NewDelete,
-    // /// Expire snapshots in the table
-    // ExpireSnapshots,
-    // /// Manage snapshots in the table
-    // ManageSnapshots,
+    /// Expire snapshots in the table
+    ExpireSnapshots {
+        older_than: Option<i64>,
+        retain_last: Option<i32>,
+        delete_files: bool,
+    },
+    /// Change a snapshot reference of the table
+    ManageSnapshots { ref_name: String, change: RefChange },
+    /// Apply the changes of a snapshot of another branch to the branch
+    CherryPick {
+        branch: Option<String>,
+        snapshot_id: i64,
+    },
     // /// Read and write table data and metadata files
     // IO,
 }
 
 impl Operation {
+    /// Branch the operation commits a snapshot to
+    pub(crate) fn branch(&self) -> Option<&str> {
+        match self {
+            Operation::Append { branch, .. }
+            | Operation::Rewrite { branch, .. }
+            | Operation::RewriteFiles { branch, .. }
+            | Operation::RewriteManifests { branch, .. }
+            | Operation::Overwrite { branch, .. }
+            | Operation::RowDelta { branch, .. }
+            | Operation::CherryPick { branch, .. } => branch.as_deref(),
+            _ => None,
+        }
+    }
+
     pub async fn execute(
         self,
         table_metadata: &TableMetadata,
         object_store: Arc<dyn ObjectStore>,
-    ) -> Result<(Option<TableRequirement>, Vec<TableUpdate>), Error> {
+    ) -> Result<(Vec<TableRequirement>, Vec<TableUpdate>), Error> {
         match self {
             Operation::Append {
                 branch,
@@ -167,10 +229,7 @@ impl Operation {
                     let manifest_list_reader =
                         ManifestListReader::new(old_manifest_list_bytes.as_ref(), table_metadata)?;
 
-                    let SelectedManifest {
-                        manifest,
-                        file_count_all_entries,
-                    } = if partition_column_names.is_empty() {
+                    let selected_manifest = if partition_column_names.is_empty() {
                         select_manifest_unpartitioned(
                             manifest_list_reader,
                             &mut manifest_list_writer,
@@ -182,8 +241,15 @@ impl Operation {
                             &bounding_partition_values,
                         )?
                     };
-                    existing_file_count = file_count_all_entries;
-                    Some(manifest)
+                    selected_manifest.map(
+                        |SelectedManifest {
+                             manifest,
+                             file_count_all_entries,
+                         }| {
+                            existing_file_count = file_count_all_entries;
+                            manifest
+                        },
+                    )
                 } else {
                     // If manifest list doesn't exist, there is no manifest
                     None
@@ -241,6 +307,12 @@ impl Operation {
                 // Write manifest files
                 // Split manifest file if limit is exceeded
                 if n_splits == 0 {
+                    let manifest_location = table_metadata.location.to_string()
+                        + "/metadata/"
+                        + snapshot_uuid
+                        + "-m"
+                        + &0.to_string()
+                        + ".avro";
                     let mut manifest_writer = if let Some(manifest) = selected_manifest_opt {
                         let manifest_bytes: Vec<u8> = object_store
                             .get(&strip_prefix(&manifest.manifest_path).as_str().into())
@@ -252,23 +324,19 @@ impl Operation {
                         ManifestWriter::from_existing(
                             &manifest_bytes,
                             manifest,
+                            &manifest_location,
+                            snapshot_id,
                             &manifest_schema,
                             table_metadata,
                             branch.as_deref(),
                         )?
                     } else {
-                        let manifest_location = table_metadata.location.to_string()
-                            + "/metadata/"
-                            + snapshot_uuid
-                            + "-m"
-                            + &0.to_string()
-                            + ".avro";
-
                         ManifestWriter::new(
                             &manifest_location,
                             snapshot_id,
                             &manifest_schema,
                             table_metadata,
+                            manifest_list::Content::Data,
                             branch.as_deref(),
                         )?
                     };
@@ -321,6 +389,7 @@ impl Operation {
                             snapshot_id,
                             &manifest_schema,
                             table_metadata,
+                            manifest_list::Content::Data,
                             branch.as_deref(),
                         )?;
 
@@ -347,25 +416,27 @@ impl Operation {
                 snapshot_builder
                     .with_snapshot_id(snapshot_id)
                     .with_manifest_list(new_manifest_list_location)
-                    .with_sequence_number(
-                        old_snapshot
-                            .map(|x| *x.sequence_number() + 1)
-                            .unwrap_or_default(),
-                    )
+                    .with_sequence_number(sequence_number)
                     .with_summary(Summary {
                         operation: iceberg_rust_spec::spec::snapshot::Operation::Append,
                         other: additional_summary.unwrap_or_default(),
                     })
                     .with_schema_id(*schema.schema_id());
+                if let Some(old_snapshot) = old_snapshot {
+                    snapshot_builder.with_parent_snapshot_id(*old_snapshot.snapshot_id());
+                }
                 let snapshot = snapshot_builder
                     .build()
                     .map_err(iceberg_rust_spec::error::Error::from)?;
 
                 Ok((
-                    old_snapshot.map(|x| TableRequirement::AssertRefSnapshotId {
-                        r#ref: branch.clone().unwrap_or("main".to_owned()),
-                        snapshot_id: *x.snapshot_id(),
-                    }),
+                    old_snapshot
+                        .map(|x| TableRequirement::AssertRefSnapshotId {
+                            r#ref: branch.clone().unwrap_or("main".to_owned()),
+                            snapshot_id: *x.snapshot_id(),
+                        })
+                        .into_iter()
+                        .collect(),
                     vec![
                         TableUpdate::AddSnapshot { snapshot },
                         TableUpdate::SetSnapshotRef {
@@ -462,6 +533,7 @@ impl Operation {
                         snapshot_id,
                         &manifest_schema,
                         table_metadata,
+                        manifest_list::Content::Data,
                         branch.as_deref(),
                     )?;
 
@@ -494,6 +566,7 @@ impl Operation {
                             snapshot_id,
                             &manifest_schema,
                             table_metadata,
+                            manifest_list::Content::Data,
                             branch.as_deref(),
                         )?;
 
@@ -519,13 +592,16 @@ impl Operation {
                 let mut snapshot_builder = SnapshotBuilder::default();
                 snapshot_builder
                     .with_snapshot_id(snapshot_id)
-                    .with_sequence_number(0)
+                    .with_sequence_number(sequence_number)
                     .with_schema_id(*schema.schema_id())
                     .with_manifest_list(new_manifest_list_location)
                     .with_summary(Summary {
                         operation: iceberg_rust_spec::spec::snapshot::Operation::Append,
                         other: additional_summary.unwrap_or_default(),
                     });
+                if let Some(old_snapshot) = old_snapshot {
+                    snapshot_builder.with_parent_snapshot_id(*old_snapshot.snapshot_id());
+                }
                 let snapshot = snapshot_builder
                     .build()
                     .map_err(iceberg_rust_spec::error::Error::from)?;
@@ -534,10 +610,13 @@ impl Operation {
                     table_metadata.snapshots.keys().map(Clone::clone).collect();
 
                 Ok((
-                    old_snapshot.map(|x| TableRequirement::AssertRefSnapshotId {
-                        r#ref: branch.clone().unwrap_or("main".to_owned()),
-                        snapshot_id: *x.snapshot_id(),
-                    }),
+                    old_snapshot
+                        .map(|x| TableRequirement::AssertRefSnapshotId {
+                            r#ref: branch.clone().unwrap_or("main".to_owned()),
+                            snapshot_id: *x.snapshot_id(),
+                        })
+                        .into_iter()
+                        .collect(),
                     vec![
                         TableUpdate::RemoveSnapshots {
                             snapshot_ids: old_snapshot_ids,
@@ -553,8 +632,277 @@ impl Operation {
                     ],
                 ))
             }
+            Operation::RowDelta {
+                branch,
+                files,
+                delete_files,
+                additional_summary,
+            } => {
+                if table_metadata.format_version == FormatVersion::V1 {
+                    return Err(Error::NotSupported(
+                        "Delete files in format version 1".to_owned(),
+                    ));
+                }
+                if files
+                    .iter()
+                    .any(|file| !matches!(file.content(), Content::Data))
+                    || delete_files
+                        .iter()
+                        .any(|file| matches!(file.content(), Content::Data))
+                {
+                    return Err(Error::InvalidFormat(
+                        "Content of row delta files".to_owned(),
+                    ));
+                }
+
+                let partition_spec = table_metadata.default_partition_spec()?;
+                let schema = table_metadata.current_schema(branch.as_deref())?;
+                let old_snapshot = table_metadata.current_snapshot(branch.as_deref())?;
+
+                let mut manifest_list_writer =
+                    apache_avro::Writer::new(manifest_list_schema_v2(), Vec::new());
+
+                // The manifests of the previous snapshot are kept as they are
+                if let Some(old_snapshot) = old_snapshot {
+                    let old_manifest_list_bytes = object_store
+                        .get(&strip_prefix(old_snapshot.manifest_list()).as_str().into())
+                        .await?
+                        .bytes()
+                        .await?;
+
+                    let manifest_list_reader =
+                        ManifestListReader::new(old_manifest_list_bytes.as_ref(), table_metadata)?;
+
+                    for manifest in manifest_list_reader {
+                        manifest_list_writer.append_ser(manifest?)?;
+                    }
+                }
+
+                let snapshot_id = generate_snapshot_id();
+                let sequence_number = table_metadata.last_sequence_number + 1;
+
+                let manifest_schema = ManifestEntry::schema(
+                    &partition_value_schema(partition_spec.fields(), schema)?,
+                    &table_metadata.format_version,
+                )?;
+
+                let snapshot_uuid = &uuid::Uuid::new_v4().to_string();
+                let new_manifest_list_location = table_metadata.location.to_string()
+                    + "/metadata/snap-"
+                    + &snapshot_id.to_string()
+                    + snapshot_uuid
+                    + ".avro";
+
+                let operation = if files.is_empty() {
+                    iceberg_rust_spec::spec::snapshot::Operation::Delete
+                } else {
+                    iceberg_rust_spec::spec::snapshot::Operation::Overwrite
+                };
+
+                // Data files and delete files are written to separate manifests
+                for (i, (content, files)) in [
+                    (manifest_list::Content::Data, files),
+                    (manifest_list::Content::Deletes, delete_files),
+                ]
+                .into_iter()
+                .enumerate()
+                {
+                    if files.is_empty() {
+                        continue;
+                    }
+                    let manifest_location = table_metadata.location.to_string()
+                        + "/metadata/"
+                        + snapshot_uuid
+                        + "-m"
+                        + &i.to_string()
+                        + ".avro";
+
+                    let mut manifest_writer = ManifestWriter::new(
+                        &manifest_location,
+                        snapshot_id,
+                        &manifest_schema,
+                        table_metadata,
+                        content,
+                        branch.as_deref(),
+                    )?;
+
+                    for data_file in files {
+                        let manifest_entry = ManifestEntry::builder()
+                            .with_format_version(table_metadata.format_version)
+                            .with_status(Status::Added)
+                            .with_snapshot_id(snapshot_id)
+                            .with_sequence_number(sequence_number)
+                            .with_data_file(data_file)
+                            .build()
+                            .map_err(crate::spec::error::Error::from)?;
+                        manifest_writer.append(manifest_entry)?;
+                    }
+
+                    let manifest = manifest_writer.finish(object_store.clone()).await?;
+
+                    manifest_list_writer.append_ser(manifest)?;
+                }
+
+                let manifest_list_bytes = manifest_list_writer.into_inner()?;
+
+                object_store
+                    .put(
+                        &strip_prefix(&new_manifest_list_location).into(),
+                        manifest_list_bytes.into(),
+                    )
+                    .await?;
+
+                let mut snapshot_builder = SnapshotBuilder::default();
+                snapshot_builder
+                    .with_snapshot_id(snapshot_id)
+                    .with_manifest_list(new_manifest_list_location)
+                    .with_sequence_number(sequence_number)
+                    .with_summary(Summary {
+                        operation,
+                        other: additional_summary.unwrap_or_default(),
+                    })
+                    .with_schema_id(*schema.schema_id());
+                if let Some(old_snapshot) = old_snapshot {
+                    snapshot_builder.with_parent_snapshot_id(*old_snapshot.snapshot_id());
+                }
+                let snapshot = snapshot_builder
+                    .build()
+                    .map_err(iceberg_rust_spec::error::Error::from)?;
+
+                Ok((
+                    old_snapshot
+                        .map(|x| TableRequirement::AssertRefSnapshotId {
+                            r#ref: branch.clone().unwrap_or("main".to_owned()),
+                            snapshot_id: *x.snapshot_id(),
+                        })
+                        .into_iter()
+                        .collect(),
+                    vec![
+                        TableUpdate::AddSnapshot { snapshot },
+                        TableUpdate::SetSnapshotRef {
+                            ref_name: branch.unwrap_or("main".to_owned()),
+                            snapshot_reference: SnapshotReference {
+                                snapshot_id,
+                                retention: SnapshotRetention::default(),
+                            },
+                        },
+                    ],
+                ))
+            }
+            Operation::Overwrite {
+                branch,
+                files,
+                mode,
+                additional_summary,
+            } => {
+                if files
+                    .iter()
+                    .any(|file| !matches!(file.content(), Content::Data))
+                {
+                    return Err(Error::InvalidFormat(
+                        "Content of overwrite files".to_owned(),
+                    ));
+                }
+
+                let partitions: HashSet<Struct> =
+                    files.iter().map(|file| file.partition().clone()).collect();
+
+                replace_files(
+                    table_metadata,
+                    object_store,
+                    branch,
+                    files,
+                    |entry, schema| mode.removes(entry, &partitions, schema),
+                    iceberg_rust_spec::spec::snapshot::Operation::Overwrite,
+                    additional_summary,
+                )
+                .await
+            }
+            Operation::RewriteFiles {
+                branch,
+                files_to_delete,
+                files,
+                additional_summary,
+            } => {
+                if files
+                    .iter()
+                    .chain(files_to_delete.iter())
+                    .any(|file| !matches!(file.content(), Content::Data))
+                {
+                    return Err(Error::InvalidFormat(
+                        "Content of rewritten files".to_owned(),
+                    ));
+                }
+
+                let mut paths: HashSet<String> = files_to_delete
+                    .iter()
+                    .map(|file| file.file_path().clone())
+                    .collect();
+
+                let result = replace_files(
+                    table_metadata,
+                    object_store,
+                    branch,
+                    files,
+                    |entry, _| Ok(paths.remove(entry.data_file().file_path())),
+                    iceberg_rust_spec::spec::snapshot::Operation::Replace,
+                    additional_summary,
+                )
+                .await?;
+
+                // All rewritten files have to be part of the current snapshot
+                if let Some(path) = paths.into_iter().next() {
+                    return Err(Error::NotFound("Data file".to_owned(), path));
+                }
+
+                Ok(result)
+            }
+            Operation::RewriteManifests {
+                branch,
+                additional_summary,
+            } => rewrite_manifests(table_metadata, object_store, branch, additional_summary).await,
+            Operation::ExpireSnapshots {
+                older_than,
+                retain_last,
+                delete_files: _,
+            } => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis() as i64;
+                let Expired { snapshot_ids, refs } =
+                    expire(table_metadata, now, older_than, retain_last);
+
+                let mut updates = refs
+                    .into_iter()
+                    .map(|ref_name| TableUpdate::RemoveSnapshotRef { ref_name })
+                    .collect::<Vec<_>>();
+                if !snapshot_ids.is_empty() {
+                    updates.push(TableUpdate::RemoveSnapshots { snapshot_ids });
+                }
+
+                // The retention is computed from the history of every reference, a concurrent change of any of them invalidates it
+                let mut refs = table_metadata.refs.iter().collect::<Vec<_>>();
+                refs.sort_unstable_by_key(|(name, _)| *name);
+                let requirements = refs
+                    .into_iter()
+                    .map(|(name, reference)| TableRequirement::AssertRefSnapshotId {
+                        r#ref: name.clone(),
+                        snapshot_id: reference.snapshot_id,
+                    })
+                    .collect();
+
+                Ok((requirements, updates))
+            }
+            Operation::ManageSnapshots { ref_name, change } => {
+                manage::apply(table_metadata, ref_name, change)
+            }
+            Operation::CherryPick {
+                branch,
+                snapshot_id,
+            } => cherry_pick(table_metadata, object_store, branch, snapshot_id).await,
             Operation::UpdateProperties(entries) => Ok((
-                None,
+                Vec::new(),
                 vec![TableUpdate::SetProperties {
                     updates: HashMap::from_iter(entries),
                 }],
@@ -566,7 +914,9 @@ impl Operation {
                     .map(|x| TableRequirement::AssertRefSnapshotId {
                         r#ref: key.clone(),
                         snapshot_id: x.snapshot_id,
-                    }),
+                    })
+                    .into_iter()
+                    .collect(),
                 vec![TableUpdate::SetSnapshotRef {
                     ref_name: key,
                     snapshot_reference: value,
@@ -575,7 +925,7 @@ impl Operation {
             Operation::AddSchema(schema) => {
                 let last_column_id = schema.fields().iter().map(|x| x.id).max();
                 Ok((
-                    None,
+                    Vec::new(),
                     vec![TableUpdate::AddSchema {
                         schema,
                         last_column_id,
@@ -583,8 +933,569 @@ impl Operation {
                 ))
             }
             Operation::SetDefaultSpec(spec_id) => {
-                Ok((None, vec![TableUpdate::SetDefaultSpec { spec_id }]))
+                Ok((Vec::new(), vec![TableUpdate::SetDefaultSpec { spec_id }]))
+            }
+        }
+    }
+}
+
+/// Create a snapshot that adds the new data files and removes the files of the previous snapshot selected by `removes`.
+/// Manifests that contain removed files are rewritten, the removed files are marked as deleted.
+async fn replace_files(
+    table_metadata: &TableMetadata,
+    object_store: Arc<dyn ObjectStore>,
+    branch: Option<String>,
+    files: Vec<DataFile>,
+    mut removes: impl FnMut(&ManifestEntry, &Schema) -> Result<bool, Error>,
+    operation: iceberg_rust_spec::spec::snapshot::Operation,
+    additional_summary: Option<HashMap<String, String>>,
+) -> Result<(Vec<TableRequirement>, Vec<TableUpdate>), Error> {
+    let partition_spec = table_metadata.default_partition_spec()?;
+    let schema = table_metadata.current_schema(branch.as_deref())?;
+    let old_snapshot = table_metadata.current_snapshot(branch.as_deref())?;
+
+    let manifest_list_schema = match table_metadata.format_version {
+        FormatVersion::V1 => manifest_list_schema_v1(),
+        FormatVersion::V2 => manifest_list_schema_v2(),
+    };
+
+    let mut manifest_list_writer = apache_avro::Writer::new(manifest_list_schema, Vec::new());
+
+    let snapshot_id = generate_snapshot_id();
+    let sequence_number = table_metadata.last_sequence_number + 1;
+
+    let manifest_schema = ManifestEntry::schema(
+        &partition_value_schema(partition_spec.fields(), schema)?,
+        &table_metadata.format_version,
+    )?;
+
+    let snapshot_uuid = &uuid::Uuid::new_v4().to_string();
+    let new_manifest_list_location = table_metadata.location.to_string()
+        + "/metadata/snap-"
+        + &snapshot_id.to_string()
+        + snapshot_uuid
+        + ".avro";
+
+    if let Some(old_snapshot) = old_snapshot {
+        let old_manifest_list_bytes = object_store
+            .get(&strip_prefix(old_snapshot.manifest_list()).as_str().into())
+            .await?
+            .bytes()
+            .await?;
+
+        let manifest_list_reader =
+            ManifestListReader::new(old_manifest_list_bytes.as_ref(), table_metadata)?;
+
+        for (i, manifest) in manifest_list_reader.enumerate() {
+            let manifest = manifest?;
+
+            let manifest_bytes = object_store
+                .get(&strip_prefix(&manifest.manifest_path).as_str().into())
+                .await?
+                .bytes()
+                .await?;
+
+            let entries = ManifestReader::new(manifest_bytes.as_ref())?
+                .map(|entry| {
+                    entry.map(|mut entry| {
+                        entry.inherit(&manifest);
+                        entry
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let removed = entries
+                .iter()
+                .map(|entry| {
+                    if matches!(entry.status(), Status::Deleted) {
+                        Ok(false)
+                    } else {
+                        removes(entry, schema)
+                    }
+                })
+                .collect::<Result<Vec<bool>, Error>>()?;
+
+            // Manifests without removed files are kept as they are
+            if !removed.contains(&true) {
+                manifest_list_writer.append_ser(manifest)?;
+                continue;
+            }
+
+            if manifest.partition_spec_id != *partition_spec.spec_id() {
+                return Err(Error::NotSupported(
+                    "Overwriting files of a previous partition spec".to_owned(),
+                ));
+            }
+
+            let manifest_location = table_metadata.location.to_string()
+                + "/metadata/"
+                + snapshot_uuid
+                + "-r"
+                + &i.to_string()
+                + ".avro";
+
+            let mut manifest_writer = ManifestWriter::new(
+                &manifest_location,
+                snapshot_id,
+                &manifest_schema,
+                table_metadata,
+                manifest.content.clone(),
+                branch.as_deref(),
+            )?;
+
+            // Removed files are marked as deleted by this snapshot, deletes of previous snapshots are dropped
+            for (entry, removed) in entries.into_iter().zip(removed) {
+                if matches!(entry.status(), Status::Deleted) {
+                    continue;
+                }
+                let mut builder = ManifestEntry::builder();
+                builder
+                    .with_format_version(table_metadata.format_version)
+                    .with_data_file(entry.data_file().clone());
+                if let Some(sequence_number) = entry.sequence_number() {
+                    builder.with_sequence_number(*sequence_number);
+                }
+                if removed {
+                    builder
+                        .with_status(Status::Deleted)
+                        .with_snapshot_id(snapshot_id);
+                } else {
+                    builder.with_status(Status::Existing);
+                    if let Some(snapshot_id) = entry.snapshot_id() {
+                        builder.with_snapshot_id(*snapshot_id);
+                    }
+                }
+                manifest_writer
+                    .append(builder.build().map_err(crate::spec::error::Error::from)?)?;
+            }
+
+            let manifest = manifest_writer.finish(object_store.clone()).await?;
+
+            manifest_list_writer.append_ser(manifest)?;
+        }
+    }
+
+    if !files.is_empty() {
+        let manifest_location =
+            table_metadata.location.to_string() + "/metadata/" + snapshot_uuid + "-m0.avro";
+
+        let mut manifest_writer = ManifestWriter::new(
+            &manifest_location,
+            snapshot_id,
+            &manifest_schema,
+            table_metadata,
+            manifest_list::Content::Data,
+            branch.as_deref(),
+        )?;
+
+        for data_file in files {
+            let manifest_entry = ManifestEntry::builder()
+                .with_format_version(table_metadata.format_version)
+                .with_status(Status::Added)
+                .with_snapshot_id(snapshot_id)
+                .with_sequence_number(sequence_number)
+                .with_data_file(data_file)
+                .build()
+                .map_err(crate::spec::error::Error::from)?;
+            manifest_writer.append(manifest_entry)?;
+        }
+
+        let manifest = manifest_writer.finish(object_store.clone()).await?;
+
+        manifest_list_writer.append_ser(manifest)?;
+    }
+
+    let manifest_list_bytes = manifest_list_writer.into_inner()?;
+
+    object_store
+        .put(
+            &strip_prefix(&new_manifest_list_location).into(),
+            manifest_list_bytes.into(),
+        )
+        .await?;
+
+    let mut snapshot_builder = SnapshotBuilder::default();
+    snapshot_builder
+        .with_snapshot_id(snapshot_id)
+        .with_manifest_list(new_manifest_list_location)
+        .with_sequence_number(sequence_number)
+        .with_summary(Summary {
+            operation,
+            other: additional_summary.unwrap_or_default(),
+        })
+        .with_schema_id(*schema.schema_id());
+    if let Some(old_snapshot) = old_snapshot {
+        snapshot_builder.with_parent_snapshot_id(*old_snapshot.snapshot_id());
+    }
+    let snapshot = snapshot_builder
+        .build()
+        .map_err(iceberg_rust_spec::error::Error::from)?;
+
+    Ok((
+        old_snapshot
+            .map(|x| TableRequirement::AssertRefSnapshotId {
+                r#ref: branch.clone().unwrap_or("main".to_owned()),
+                snapshot_id: *x.snapshot_id(),
+            })
+            .into_iter()
+            .collect(),
+        vec![
+            TableUpdate::AddSnapshot { snapshot },
+            TableUpdate::SetSnapshotRef {
+                ref_name: branch.unwrap_or("main".to_owned()),
+                snapshot_reference: SnapshotReference {
+                    snapshot_id,
+                    retention: SnapshotRetention::default(),
+                },
+            },
+        ],
+    ))
+}
+
+/// Create a snapshot with the same data files as the previous snapshot, whose data manifests of the default partition spec are rewritten.
+/// The live entries are sorted by partition and split into manifests of the target size, so that every manifest covers a small partition range.
+async fn rewrite_manifests(
+    table_metadata: &TableMetadata,
+    object_store: Arc<dyn ObjectStore>,
+    branch: Option<String>,
+    additional_summary: Option<HashMap<String, String>>,
+) -> Result<(Vec<TableRequirement>, Vec<TableUpdate>), Error> {
+    let Some(old_snapshot) = table_metadata.current_snapshot(branch.as_deref())? else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+    let partition_spec = table_metadata.default_partition_spec()?;
+    let schema = table_metadata.current_schema(branch.as_deref())?;
+
+    let target_size = table_metadata
+        .properties
+        .get(MANIFEST_TARGET_SIZE_BYTES)
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(MANIFEST_TARGET_SIZE_BYTES_DEFAULT);
+
+    let manifest_list_schema = match table_metadata.format_version {
+        FormatVersion::V1 => manifest_list_schema_v1(),
+        FormatVersion::V2 => manifest_list_schema_v2(),
+    };
+
+    let mut manifest_list_writer = apache_avro::Writer::new(manifest_list_schema, Vec::new());
+
+    let snapshot_id = generate_snapshot_id();
+    let sequence_number = table_metadata.last_sequence_number + 1;
+
+    let manifest_schema = ManifestEntry::schema(
+        &partition_value_schema(partition_spec.fields(), schema)?,
+        &table_metadata.format_version,
+    )?;
+
+    let snapshot_uuid = &uuid::Uuid::new_v4().to_string();
+    let new_manifest_list_location = table_metadata.location.to_string()
+        + "/metadata/snap-"
+        + &snapshot_id.to_string()
+        + snapshot_uuid
+        + ".avro";
+
+    let old_manifest_list_bytes = object_store
+        .get(&strip_prefix(old_snapshot.manifest_list()).as_str().into())
+        .await?
+        .bytes()
+        .await?;
+
+    let manifest_list_reader =
+        ManifestListReader::new(old_manifest_list_bytes.as_ref(), table_metadata)?;
+
+    let mut entries = Vec::new();
+    let (mut old_size, mut old_entry_count) = (0, 0);
+
+    for manifest in manifest_list_reader {
+        let manifest = manifest?;
+
+        // Delete manifests and manifests of previous partition specs are kept as they are
+        if !matches!(manifest.content, manifest_list::Content::Data)
+            || manifest.partition_spec_id != *partition_spec.spec_id()
+        {
+            manifest_list_writer.append_ser(manifest)?;
+            continue;
+        }
+
+        let manifest_bytes = object_store
+            .get(&strip_prefix(&manifest.manifest_path).as_str().into())
+            .await?
+            .bytes()
+            .await?;
+
+        for entry in ManifestReader::new(manifest_bytes.as_ref())? {
+            let mut entry = entry?;
+            old_entry_count += 1;
+            if matches!(entry.status(), Status::Deleted) {
+                continue;
+            }
+            entry.inherit(&manifest);
+            entries.push(entry);
+        }
+        old_size += manifest.manifest_length;
+    }
+
+    // Estimate the number of entries per manifest from the size of the old manifests
+    let entries_per_manifest = if old_entry_count == 0 {
+        1
+    } else {
+        (target_size / (old_size / old_entry_count).max(1)).max(1) as usize
+    };
+
+    entries.sort_by(|x, y| x.data_file().partition().cmp(y.data_file().partition()));
+
+    for (i, chunk) in entries.chunks(entries_per_manifest).enumerate() {
+        let manifest_location = table_metadata.location.to_string()
+            + "/metadata/"
+            + snapshot_uuid
+            + "-m"
+            + &i.to_string()
+            + ".avro";
+
+        let mut manifest_writer = ManifestWriter::new(
+            &manifest_location,
+            snapshot_id,
+            &manifest_schema,
+            table_metadata,
+            manifest_list::Content::Data,
+            branch.as_deref(),
+        )?;
+
+        for entry in chunk {
+            let mut builder = ManifestEntry::builder();
+            builder
+                .with_format_version(table_metadata.format_version)
+                .with_status(Status::Existing)
+                .with_data_file(entry.data_file().clone());
+            if let Some(snapshot_id) = entry.snapshot_id() {
+                builder.with_snapshot_id(*snapshot_id);
+            }
+            if let Some(sequence_number) = entry.sequence_number() {
+                builder.with_sequence_number(*sequence_number);
             }
+            manifest_writer.append(builder.build().map_err(crate::spec::error::Error::from)?)?;
         }
+
+        let manifest = manifest_writer.finish(object_store.clone()).await?;
+
+        manifest_list_writer.append_ser(manifest)?;
+    }
+
+    let manifest_list_bytes = manifest_list_writer.into_inner()?;
+
+    object_store
+        .put(
+            &strip_prefix(&new_manifest_list_location).into(),
+            manifest_list_bytes.into(),
+        )
+        .await?;
+
+    let snapshot = SnapshotBuilder::default()
+        .with_snapshot_id(snapshot_id)
+        .with_parent_snapshot_id(*old_snapshot.snapshot_id())
+        .with_manifest_list(new_manifest_list_location)
+        .with_sequence_number(sequence_number)
+        .with_summary(Summary {
+            operation: iceberg_rust_spec::spec::snapshot::Operation::Replace,
+            other: additional_summary.unwrap_or_default(),
+        })
+        .with_schema_id(*schema.schema_id())
+        .build()
+        .map_err(iceberg_rust_spec::error::Error::from)?;
+
+    Ok((
+        vec![TableRequirement::AssertRefSnapshotId {
+            r#ref: branch.clone().unwrap_or("main".to_owned()),
+            snapshot_id: *old_snapshot.snapshot_id(),
+        }],
+        vec![
+            TableUpdate::AddSnapshot { snapshot },
+            TableUpdate::SetSnapshotRef {
+                ref_name: branch.unwrap_or("main".to_owned()),
+                snapshot_reference: SnapshotReference {
+                    snapshot_id,
+                    retention: SnapshotRetention::default(),
+                },
+            },
+        ],
+    ))
+}
+
+/// Apply the changes of an append or overwrite snapshot to the branch. If the parent of the snapshot is the current snapshot of the branch, the branch is fast-forwarded.
+/// Otherwise a new snapshot is created that adds the same files and removes the same data files, which all have to be part of the branch.
+async fn cherry_pick(
+    table_metadata: &TableMetadata,
+    object_store: Arc<dyn ObjectStore>,
+    branch: Option<String>,
+    snapshot_id: i64,
+) -> Result<(Vec<TableRequirement>, Vec<TableUpdate>), Error> {
+    let ref_name = branch.clone().unwrap_or(MAIN_BRANCH.to_owned());
+    let snapshot = table_metadata
+        .snapshots
+        .get(&snapshot_id)
+        .ok_or(Error::NotFound(
+            "Snapshot".to_owned(),
+            snapshot_id.to_string(),
+        ))?;
+    let head = table_metadata.current_snapshot(branch.as_deref())?;
+
+    if let Some(head) = head {
+        if is_ancestor(table_metadata, snapshot_id, *head.snapshot_id()) {
+            return Err(Error::InvalidFormat(format!(
+                "Snapshot {} is already an ancestor of {}",
+                snapshot_id, ref_name
+            )));
+        }
+        let source_snapshot_id = snapshot_id.to_string();
+        if ancestors(table_metadata, *head.snapshot_id()).any(|ancestor| {
+            ancestor.summary().other.get(SOURCE_SNAPSHOT_ID) == Some(&source_snapshot_id)
+        }) {
+            return Err(Error::InvalidFormat(format!(
+                "Snapshot {} has already been cherry-picked to {}",
+                snapshot_id, ref_name
+            )));
+        }
+    }
+
+    // Fast-forward
+    if *snapshot.parent_snapshot_id() == head.map(|head| *head.snapshot_id()) {
+        return Ok((
+            head.map(|head| TableRequirement::AssertRefSnapshotId {
+                r#ref: ref_name.clone(),
+                snapshot_id: *head.snapshot_id(),
+            })
+            .into_iter()
+            .collect(),
+            vec![TableUpdate::SetSnapshotRef {
+                snapshot_reference: SnapshotReference {
+                    snapshot_id,
+                    retention: table_metadata
+                        .refs
+                        .get(&ref_name)
+                        .map(|reference| reference.retention.clone())
+                        .unwrap_or_default(),
+                },
+                ref_name,
+            }],
+        ));
+    }
+
+    let operation = snapshot.summary().operation.clone();
+    if !matches!(
+        operation,
+        iceberg_rust_spec::spec::snapshot::Operation::Append
+            | iceberg_rust_spec::spec::snapshot::Operation::Overwrite
+    ) {
+        return Err(Error::NotSupported(format!(
+            "Cherry-picking {:?} snapshots",
+            operation
+        )));
+    }
+
+    let (added, removed) = snapshot_changes(snapshot, table_metadata, object_store.clone()).await?;
+    if added
+        .iter()
+        .chain(removed.iter())
+        .any(|entry| !matches!(entry.data_file().content(), Content::Data))
+    {
+        return Err(Error::NotSupported(
+            "Cherry-picking snapshots with delete files".to_owned(),
+        ));
+    }
+
+    let mut paths: HashSet<String> = removed
+        .iter()
+        .map(|entry| entry.data_file().file_path().clone())
+        .collect();
+
+    let result = replace_files(
+        table_metadata,
+        object_store,
+        branch,
+        added
+            .into_iter()
+            .map(|entry| entry.data_file().clone())
+            .collect(),
+        |entry, _| Ok(paths.remove(entry.data_file().file_path())),
+        operation,
+        Some(HashMap::from_iter(vec![(
+            SOURCE_SNAPSHOT_ID.to_owned(),
+            snapshot_id.to_string(),
+        )])),
+    )
+    .await?;
+
+    // The removed files have to be part of the branch, otherwise the snapshot conflicts with the branch
+    if let Some(path) = paths.into_iter().next() {
+        return Err(Error::NotFound("Data file".to_owned(), path));
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use iceberg_rust_spec::spec::{
+        snapshot::{SnapshotBuilder, SnapshotReference, SnapshotRetention},
+        table_metadata::TableMetadata,
+    };
+    use object_store::memory::InMemory;
+
+    use crate::catalog::commit::TableRequirement;
+
+    use super::Operation;
+
+    #[tokio::test]
+    async fn test_expire_snapshots_requirements() {
+        let reference = |snapshot_id| SnapshotReference {
+            snapshot_id,
+            retention: SnapshotRetention::default(),
+        };
+        let metadata = TableMetadata {
+            snapshots: HashMap::from_iter((1..=2).map(|id| {
+                (
+                    id,
+                    SnapshotBuilder::default()
+                        .with_snapshot_id(id)
+                        .with_sequence_number(id)
+                        .with_timestamp_ms(id * 100)
+                        .with_manifest_list(format!("/test/snap-{}.avro", id))
+                        .build()
+                        .unwrap(),
+                )
+            })),
+            current_snapshot_id: Some(2),
+            refs: HashMap::from_iter(vec![
+                ("main".to_owned(), reference(2)),
+                ("dev".to_owned(), reference(1)),
+            ]),
+            ..Default::default()
+        };
+
+        let (requirements, _) = Operation::ExpireSnapshots {
+            older_than: None,
+            retain_last: None,
+            delete_files: false,
+        }
+        .execute(&metadata, Arc::new(InMemory::new()))
+        .await
+        .unwrap();
+
+        // Every reference whose history determined the retained snapshots is asserted
+        assert_eq!(
+            requirements,
+            vec![
+                TableRequirement::AssertRefSnapshotId {
+                    r#ref: "dev".to_owned(),
+                    snapshot_id: 1,
+                },
+                TableRequirement::AssertRefSnapshotId {
+                    r#ref: "main".to_owned(),
+                    snapshot_id: 2,
+                },
+            ]
+        );
     }
 }
@@ -0,0 +1,355 @@
+/*!
+ * Defines how an overwrite operation selects the data files it replaces.
+*/
+
+use std::{cmp::Ordering, collections::HashSet, mem::discriminant};
+
+use iceberg_rust_spec::spec::{
+    manifest::{Content, DataFile, ManifestEntry},
+    schema::Schema,
+    values::{Struct, Value},
+};
+
+use crate::error::Error;
+
+/// Condition on a single column of the table
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// Column is equal to the value
+    Eq(String, Value),
+    /// Column is less than the value
+    Lt(String, Value),
+    /// Column is less than or equal to the value
+    LtEq(String, Value),
+    /// Column is greater than the value
+    Gt(String, Value),
+    /// Column is greater than or equal to the value
+    GtEq(String, Value),
+    /// Column is null
+    IsNull(String),
+    /// Column is not null
+    NotNull(String),
+    /// Column is equal to one of the values
+    In(String, Vec<Value>),
+}
+
+impl Predicate {
+    /// Name of the column the predicate applies to
+    pub fn column(&self) -> &str {
+        match self {
+            Predicate::Eq(name, _)
+            | Predicate::Lt(name, _)
+            | Predicate::LtEq(name, _)
+            | Predicate::Gt(name, _)
+            | Predicate::GtEq(name, _)
+            | Predicate::IsNull(name)
+            | Predicate::NotNull(name)
+            | Predicate::In(name, _) => name,
+        }
+    }
+
+    /// Apply the predicate to another column
+    pub(crate) fn with_column(self, name: String) -> Self {
+        match self {
+            Predicate::Eq(_, value) => Predicate::Eq(name, value),
+            Predicate::Lt(_, value) => Predicate::Lt(name, value),
+            Predicate::LtEq(_, value) => Predicate::LtEq(name, value),
+            Predicate::Gt(_, value) => Predicate::Gt(name, value),
+            Predicate::GtEq(_, value) => Predicate::GtEq(name, value),
+            Predicate::IsNull(_) => Predicate::IsNull(name),
+            Predicate::NotNull(_) => Predicate::NotNull(name),
+            Predicate::In(_, values) => Predicate::In(name, values),
+        }
+    }
+}
+
+/// Determines which existing files are removed by an overwrite
+#[derive(Debug, Clone, PartialEq)]
+pub enum OverwriteMode {
+    /// Remove the data files whose rows all satisfy the conjunction of the predicates. An empty filter matches every row.
+    /// The overwrite fails if a data file contains rows that satisfy the filter as well as rows that don't.
+    Filter(Vec<Predicate>),
+    /// Replace all data and delete files in the partitions the new files are written to
+    Dynamic,
+}
+
+impl OverwriteMode {
+    /// Check whether the overwrite removes the file of the manifest entry. `partitions` are the partitions of the new data files.
+    pub(crate) fn removes(
+        &self,
+        entry: &ManifestEntry,
+        partitions: &HashSet<Struct>,
+        schema: &Schema,
+    ) -> Result<bool, Error> {
+        match self {
+            OverwriteMode::Dynamic => Ok(partitions.contains(entry.data_file().partition())),
+            OverwriteMode::Filter(filter) => {
+                if !matches!(entry.data_file().content(), Content::Data) {
+                    return Ok(false);
+                }
+                match filter_match(filter, entry.data_file(), schema)? {
+                    FilterMatch::All => Ok(true),
+                    FilterMatch::None => Ok(false),
+                    FilterMatch::Partial => Err(Error::InvalidFormat(format!(
+                        "Data file {} only partly matches the overwrite filter",
+                        entry.data_file().file_path()
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+/// How the rows of a data file match a filter, determined from the column metrics of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FilterMatch {
+    /// All rows match
+    All,
+    /// Some rows might match, or the metrics are not sufficient to tell
+    Partial,
+    /// No row matches
+    None,
+}
+
+/// Determine how the rows of the data file match the conjunction of the predicates
+pub(crate) fn filter_match(
+    filter: &[Predicate],
+    data_file: &DataFile,
+    schema: &Schema,
+) -> Result<FilterMatch, Error> {
+    if *data_file.record_count() == 0 {
+        return Ok(FilterMatch::All);
+    }
+    let mut result = FilterMatch::All;
+    for predicate in filter {
+        match predicate_match(predicate, data_file, schema)? {
+            FilterMatch::None => return Ok(FilterMatch::None),
+            FilterMatch::Partial => result = FilterMatch::Partial,
+            FilterMatch::All => (),
+        }
+    }
+    Ok(result)
+}
+
+/// Determine how the rows of the data file match a single predicate
+pub(crate) fn predicate_match(
+    predicate: &Predicate,
+    data_file: &DataFile,
+    schema: &Schema,
+) -> Result<FilterMatch, Error> {
+    let name = predicate.column();
+    let id = schema
+        .fields()
+        .get_name(name)
+        .ok_or(Error::NotFound("Column".to_owned(), name.to_owned()))?
+        .id;
+
+    let record_count = *data_file.record_count();
+    let null_count = data_file
+        .null_value_counts()
+        .as_ref()
+        .and_then(|counts| counts.get(&id))
+        .copied();
+    let all_null = null_count == Some(record_count);
+    let no_null = null_count == Some(0);
+
+    let (value, bounds_match): (&Value, fn(Ordering, Ordering) -> FilterMatch) = match predicate {
+        // The values match like a disjunction of equality predicates
+        Predicate::In(name, values) => {
+            let mut result = FilterMatch::None;
+            for value in values {
+                match predicate_match(
+                    &Predicate::Eq(name.clone(), value.clone()),
+                    data_file,
+                    schema,
+                )? {
+                    FilterMatch::All => return Ok(FilterMatch::All),
+                    FilterMatch::Partial => result = FilterMatch::Partial,
+                    FilterMatch::None => (),
+                }
+            }
+            return Ok(result);
+        }
+        Predicate::IsNull(_) => {
+            return Ok(match null_count {
+                Some(_) if all_null => FilterMatch::All,
+                Some(0) => FilterMatch::None,
+                _ => FilterMatch::Partial,
+            })
+        }
+        Predicate::NotNull(_) => {
+            return Ok(match null_count {
+                Some(0) => FilterMatch::All,
+                Some(_) if all_null => FilterMatch::None,
+                _ => FilterMatch::Partial,
+            })
+        }
+        // The orderings compare the value to the lower and the upper bound of the column
+        Predicate::Eq(_, value) => (value, |lower, upper| match (lower, upper) {
+            (Ordering::Less, _) | (_, Ordering::Greater) => FilterMatch::None,
+            (Ordering::Equal, Ordering::Equal) => FilterMatch::All,
+            _ => FilterMatch::Partial,
+        }),
+        Predicate::Lt(_, value) => (value, |lower, upper| match (lower, upper) {
+            (_, Ordering::Greater) => FilterMatch::All,
+            (Ordering::Less | Ordering::Equal, _) => FilterMatch::None,
+            _ => FilterMatch::Partial,
+        }),
+        Predicate::LtEq(_, value) => (value, |lower, upper| match (lower, upper) {
+            (_, Ordering::Greater | Ordering::Equal) => FilterMatch::All,
+            (Ordering::Less, _) => FilterMatch::None,
+            _ => FilterMatch::Partial,
+        }),
+        Predicate::Gt(_, value) => (value, |lower, upper| match (lower, upper) {
+            (Ordering::Less, _) => FilterMatch::All,
+            (_, Ordering::Greater | Ordering::Equal) => FilterMatch::None,
+            _ => FilterMatch::Partial,
+        }),
+        Predicate::GtEq(_, value) => (value, |lower, upper| match (lower, upper) {
+            (Ordering::Less | Ordering::Equal, _) => FilterMatch::All,
+            (_, Ordering::Greater) => FilterMatch::None,
+            _ => FilterMatch::Partial,
+        }),
+    };
+
+    // Null values never satisfy a comparison
+    if all_null {
+        return Ok(FilterMatch::None);
+    }
+
+    let lower = data_file
+        .lower_bounds()
+        .as_ref()
+        .and_then(|bounds| bounds.get(&id));
+    let upper = data_file
+        .upper_bounds()
+        .as_ref()
+        .and_then(|bounds| bounds.get(&id));
+    let (Some(lower), Some(upper)) = (lower, upper) else {
+        return Ok(FilterMatch::Partial);
+    };
+    let (Some(lower), Some(upper)) = (compare(value, lower), compare(value, upper)) else {
+        return Ok(FilterMatch::Partial);
+    };
+
+    Ok(match bounds_match(lower, upper) {
+        FilterMatch::All if !no_null => FilterMatch::Partial,
+        x => x,
+    })
+}
+
+/// Compare values of the same type
+pub(crate) fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    (discriminant(left) == discriminant(right)).then(|| left.cmp(right))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use iceberg_rust_spec::spec::{
+        manifest::{AvroMap, Content, DataFile, FileFormat},
+        schema::Schema,
+        types::{PrimitiveType, StructField, StructType, Type},
+        values::{Struct, Value},
+    };
+
+    use super::{filter_match, FilterMatch, Predicate};
+
+    fn data_file(lower: i64, upper: i64, null_count: i64) -> DataFile {
+        DataFile::builder()
+            .with_content(Content::Data)
+            .with_file_path("/test/data.parquet".to_owned())
+            .with_file_format(FileFormat::Parquet)
+            .with_partition(Struct::from_iter(Vec::<(String, Option<Value>)>::new()))
+            .with_record_count(10)
+            .with_file_size_in_bytes(100)
+            .with_column_sizes(None)
+            .with_value_counts(None)
+            .with_null_value_counts(Some(AvroMap(HashMap::from_iter(vec![(1, null_count)]))))
+            .with_nan_value_counts(None)
+            .with_distinct_counts(None)
+            .with_lower_bounds(Some(HashMap::from_iter(vec![(1, Value::LongInt(lower))])))
+            .with_upper_bounds(Some(HashMap::from_iter(vec![(1, Value::LongInt(upper))])))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_filter_match() {
+        let schema = Schema::builder()
+            .with_schema_id(0)
+            .with_fields(
+                StructType::builder()
+                    .with_struct_field(StructField {
+                        id: 1,
+                        name: "id".to_string(),
+                        required: false,
+                        field_type: Type::Primitive(PrimitiveType::Long),
+                        doc: None,
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let file = data_file(1, 5, 0);
+        let check = |filter: Vec<Predicate>| filter_match(&filter, &file, &schema).unwrap();
+
+        assert_eq!(check(vec![]), FilterMatch::All);
+        assert_eq!(
+            check(vec![Predicate::GtEq("id".to_owned(), Value::LongInt(1))]),
+            FilterMatch::All
+        );
+        assert_eq!(
+            check(vec![Predicate::Gt("id".to_owned(), Value::LongInt(1))]),
+            FilterMatch::Partial
+        );
+        assert_eq!(
+            check(vec![Predicate::Lt("id".to_owned(), Value::LongInt(1))]),
+            FilterMatch::None
+        );
+        assert_eq!(
+            check(vec![Predicate::Eq("id".to_owned(), Value::LongInt(7))]),
+            FilterMatch::None
+        );
+        assert_eq!(
+            check(vec![
+                Predicate::LtEq("id".to_owned(), Value::LongInt(5)),
+                Predicate::Gt("id".to_owned(), Value::LongInt(3)),
+            ]),
+            FilterMatch::Partial
+        );
+        assert_eq!(
+            check(vec![Predicate::NotNull("id".to_owned())]),
+            FilterMatch::All
+        );
+        assert_eq!(
+            check(vec![Predicate::In(
+                "id".to_owned(),
+                vec![Value::LongInt(0), Value::LongInt(8)]
+            )]),
+            FilterMatch::None
+        );
+        assert_eq!(
+            check(vec![Predicate::In(
+                "id".to_owned(),
+                vec![Value::LongInt(0), Value::LongInt(4)]
+            )]),
+            FilterMatch::Partial
+        );
+        assert!(filter_match(&[Predicate::IsNull("name".to_owned())], &file, &schema).is_err());
+
+        let file = data_file(1, 5, 2);
+        assert_eq!(
+            filter_match(
+                &[Predicate::LtEq("id".to_owned(), Value::LongInt(5))],
+                &file,
+                &schema
+            )
+            .unwrap(),
+            FilterMatch::Partial
+        );
+    }
+}
@@ -90,7 +90,7 @@ impl Operation {
                     timestamp_ms: SystemTime::now()
                         .duration_since(UNIX_EPOCH)
                         .unwrap()
-                        .as_micros() as i64,
+                        .as_millis() as i64,
                     storage_table: version.storage_table.clone(),
                     lineage: Some(lineage),
                 };
@@ -482,11 +482,15 @@ impl Catalog for SqlCatalog {
                     ));
                 };
                 if !check_table_requirements(&commit.requirements, &metadata) {
-                    return Err(IcebergError::InvalidFormat(
+                    return Err(IcebergError::CommitConflict(
                         "Table requirements not valid".to_owned(),
                     ));
                 }
-                apply_table_updates(&mut metadata, commit.updates)?;
+                apply_table_updates(
+                    &mut metadata,
+                    commit.updates,
+                    Some(previous_metadata_location.as_str()),
+                )?;
                 let metadata_location = new_metadata_location(&metadata);
                 self.object_store
                     .put(
@@ -495,15 +499,22 @@ impl Catalog for SqlCatalog {
                     )
                     .await?;
                 let mut connection = self.connection.lock().await;
-                connection.transaction(|txn|{
+                // The update only succeeds if no other writer committed a new metadata file in the meantime
+                let result = connection.transaction(|txn|{
                 let catalog_name = self.name.clone();
                 let namespace = identifier.namespace().to_string();
                 let name = identifier.name().to_string();
                 let metadata_file_location = metadata_location.to_string();
                 let previous_metadata_file_location = previous_metadata_location.to_string();
                 Box::pin(async move {
-            sqlx::query(&format!("update iceberg_tables set metadata_location = '{}', previous_metadata_location = '{}' where catalog_name = '{}' and table_namespace = '{}' and table_name = '{}';", metadata_file_location, previous_metadata_file_location,catalog_name,namespace,name)).execute(&mut **txn).await
+            sqlx::query(&format!("update iceberg_tables set metadata_location = '{}', previous_metadata_location = '{}' where catalog_name = '{}' and table_namespace = '{}' and table_name = '{}' and metadata_location = '{}';", metadata_file_location, previous_metadata_file_location,catalog_name,namespace,name,previous_metadata_file_location)).execute(&mut **txn).await
         })}).await.map_err(Error::from)?;
+                if result.rows_affected() == 0 {
+                    return Err(IcebergError::CommitConflict(format!(
+                        "Metadata of table {} was updated concurrently",
+                        identifier
+                    )));
+                }
             }
         }
         self.clone()
@@ -559,15 +570,22 @@ impl Catalog for SqlCatalog {
                 }?;
 
                 let mut connection = self.connection.lock().await;
-                connection.transaction(|txn|{
+                // The update only succeeds if no other writer committed a new metadata file in the meantime
+                let result = connection.transaction(|txn|{
                 let catalog_name = self.name.clone();
                 let namespace = identifier.namespace().to_string();
                 let name = identifier.name().to_string();
                 let metadata_file_location = metadata_location.to_string();
                 let previous_metadata_file_location = previous_metadata_location.to_string();
                 Box::pin(async move {
-            sqlx::query(&format!("update iceberg_tables set metadata_location = '{}', previous_metadata_location = '{}' where catalog_name = '{}' and table_namespace = '{}' and table_name = '{}';", metadata_file_location, previous_metadata_file_location,catalog_name,namespace,name)).execute(&mut **txn).await
+            sqlx::query(&format!("update iceberg_tables set metadata_location = '{}', previous_metadata_location = '{}' where catalog_name = '{}' and table_namespace = '{}' and table_name = '{}' and metadata_location = '{}';", metadata_file_location, previous_metadata_file_location,catalog_name,namespace,name,previous_metadata_file_location)).execute(&mut **txn).await
         })}).await.map_err(Error::from)?;
+                if result.rows_affected() == 0 {
+                    return Err(IcebergError::CommitConflict(format!(
+                        "Metadata of table {} was updated concurrently",
+                        identifier
+                    )));
+                }
             }
         }
         if let Tabular::View(view) = self.clone().load_tabular(&identifier).await? {
@@ -621,15 +639,22 @@ impl Catalog for SqlCatalog {
                 }?;
 
                 let mut connection = self.connection.lock().await;
-                connection.transaction(|txn|{
+                // The update only succeeds if no other writer committed a new metadata file in the meantime
+                let result = connection.transaction(|txn|{
                 let catalog_name = self.name.clone();
                 let namespace = identifier.namespace().to_string();
                 let name = identifier.name().to_string();
                 let metadata_file_location = metadata_location.to_string();
                 let previous_metadata_file_location = previous_metadata_location.to_string();
                 Box::pin(async move {
-            sqlx::query(&format!("update iceberg_tables set metadata_location = '{}', previous_metadata_location = '{}' where catalog_name = '{}' and table_namespace = '{}' and table_name = '{}';", metadata_file_location, previous_metadata_file_location,catalog_name,namespace,name)).execute(&mut **txn).await
+            sqlx::query(&format!("update iceberg_tables set metadata_location = '{}', previous_metadata_location = '{}' where catalog_name = '{}' and table_namespace = '{}' and table_name = '{}' and metadata_location = '{}';", metadata_file_location, previous_metadata_file_location,catalog_name,namespace,name,previous_metadata_file_location)).execute(&mut **txn).await
         })}).await.map_err(Error::from)?;
+                if result.rows_affected() == 0 {
+                    return Err(IcebergError::CommitConflict(format!(
+                        "Metadata of table {} was updated concurrently",
+                        identifier
+                    )));
+                }
             }
         }
         if let Tabular::MaterializedView(matview) = self.clone().load_tabular(&identifier).await? {